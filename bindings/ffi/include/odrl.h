@@ -36,4 +36,23 @@ const char *fetch_odrl_world(int64_t *handle,
 int32_t remove_odrl_world(int64_t *handle,
                           const char *key);
 
+int32_t fulfill_odrl_duty(int64_t *handle,
+                          const char *party,
+                          const char *duty);
+
+int64_t fetch_odrl_usage(int64_t *handle,
+                         const char *assignee,
+                         const char *action,
+                         const char *target);
+
+int32_t reset_odrl_usage(int64_t *handle,
+                         const char *assignee,
+                         const char *action,
+                         const char *target);
+
+int32_t strict_odrl_world(int64_t *handle,
+                          int32_t strict);
+
+int32_t register_odrl_profile(const char *profile);
+
 #endif  /* _ODRL_ */
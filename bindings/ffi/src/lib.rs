@@ -46,7 +46,8 @@ pub mod ffi {
     use tokio::runtime::Runtime;
     use rusadrl::{CONFIG, odrl_loader::OdrlLoader, handle_to_policy};
     use rusadrl::model::policy::{OdrlRequest, PolicyEngine};
-    use rusadrl::model::stateworld::{GLOBAL_WORLD_CACHE};
+    use rusadrl::model::profile::{Profile, GLOBAL_PROFILE_REGISTRY};
+    use rusadrl::model::stateworld::{UsageKey, GLOBAL_WORLD_CACHE};
 
     pub struct Engine;
 
@@ -256,6 +257,143 @@ pub mod ffi {
             state.value_mut().remove_state(key.as_str());
             return 0;
         }
+
+        pub fn fulfill_odrl_duty(handle: *mut i64,party: *const c_char,duty: *const c_char) ->  i32 {
+            if handle.is_null() || party.is_null() || duty.is_null() {
+                return -1;
+            }
+
+            let policy = handle_to_policy(handle);
+            if policy.is_none() {
+                return -1;
+            }
+
+            let policy = policy.unwrap();
+            let world_key = PolicyEngine::find_world_key(policy);
+            if world_key.is_none() {
+                return -1;
+            }
+
+            let world_key = world_key.unwrap();
+            let cache = GLOBAL_WORLD_CACHE.clone();
+            let state = cache.find_world(world_key.as_str());
+            if state.is_none() {
+                return -1;
+            }
+            let mut state = state.unwrap();
+
+            let party = unsafe { CStr::from_ptr(party).to_string_lossy().into_owned() };
+            let duty = unsafe { CStr::from_ptr(duty).to_string_lossy().into_owned() };
+            state.value_mut().fulfill_duty(party.as_str(),duty.as_str());
+            return 0;
+        }
+
+        //usage count of the action on the target by the assignee, -1 on error
+        pub fn fetch_odrl_usage(handle: *mut i64,assignee: *const c_char,action: *const c_char,target: *const c_char) ->  i64 {
+            if handle.is_null() || assignee.is_null() || action.is_null() || target.is_null() {
+                return -1;
+            }
+
+            let policy = handle_to_policy(handle);
+            if policy.is_none() {
+                return -1;
+            }
+
+            let policy = policy.unwrap();
+            let world_key = PolicyEngine::find_world_key(policy);
+            if world_key.is_none() {
+                return -1;
+            }
+
+            let world_key = world_key.unwrap();
+            let cache = GLOBAL_WORLD_CACHE.clone();
+            let state = cache.find_world(world_key.as_str());
+            if state.is_none() {
+                return -1;
+            }
+            let state = state.unwrap();
+
+            let usage = Engine::usage_key(assignee, action, target);
+            state.value().usage_count(&usage) as i64
+        }
+
+        pub fn reset_odrl_usage(handle: *mut i64,assignee: *const c_char,action: *const c_char,target: *const c_char) ->  i32 {
+            if handle.is_null() || assignee.is_null() || action.is_null() || target.is_null() {
+                return -1;
+            }
+
+            let policy = handle_to_policy(handle);
+            if policy.is_none() {
+                return -1;
+            }
+
+            let policy = policy.unwrap();
+            let world_key = PolicyEngine::find_world_key(policy);
+            if world_key.is_none() {
+                return -1;
+            }
+
+            let world_key = world_key.unwrap();
+            let cache = GLOBAL_WORLD_CACHE.clone();
+            let state = cache.find_world(world_key.as_str());
+            if state.is_none() {
+                return -1;
+            }
+            let mut state = state.unwrap();
+
+            let usage = Engine::usage_key(assignee, action, target);
+            state.value_mut().reset_usage(&usage);
+            return 0;
+        }
+
+        //deny when a constraint of the policy can not be evaluated
+        pub fn strict_odrl_world(handle: *mut i64,strict: bool) ->  i32 {
+            if handle.is_null() {
+                return -1;
+            }
+
+            let policy = handle_to_policy(handle);
+            if policy.is_none() {
+                return -1;
+            }
+
+            let policy = policy.unwrap();
+            let world_key = PolicyEngine::find_world_key(policy);
+            if world_key.is_none() {
+                return -1;
+            }
+
+            let world_key = world_key.unwrap();
+            let cache = GLOBAL_WORLD_CACHE.clone();
+            let state = cache.find_world(world_key.as_str());
+            if state.is_none() {
+                return -1;
+            }
+            let mut state = state.unwrap();
+
+            state.value_mut().set_strict(strict);
+            return 0;
+        }
+
+        fn usage_key(assignee: *const c_char,action: *const c_char,target: *const c_char) -> UsageKey {
+            let assignee = unsafe { CStr::from_ptr(assignee).to_string_lossy().into_owned() };
+            let action = unsafe { CStr::from_ptr(action).to_string_lossy().into_owned() };
+            let target = unsafe { CStr::from_ptr(target).to_string_lossy().into_owned() };
+            UsageKey::new(assignee.as_str(), action.as_str(), target.as_str())
+        }
+
+        //declare a vocabulary only profile, policies declaring it are accepted
+        pub fn register_odrl_profile(profile: *const c_char) -> i32 {
+            if profile.is_null() {
+                return -1;
+            }
+
+            let profile = unsafe { CStr::from_ptr(profile).to_string_lossy().into_owned() };
+            match GLOBAL_PROFILE_REGISTRY.register(Profile::new(profile.as_str())) {
+                Ok(_) => 0,
+                Err(_) => -1,
+            }
+        }
     }
 }
 
@@ -334,11 +472,55 @@ pub extern "C" fn remove_odrl_world(handle: *mut i64,key: *const c_char) ->  i32
     ffi::Engine::remove_odrl_world(handle, key)
 }
 
+#[no_mangle]
+pub extern "C" fn fulfill_odrl_duty(handle: *mut i64,party: *const c_char,duty: *const c_char) ->  i32 {
+    ffi::Engine::fulfill_odrl_duty(handle, party, duty)
+}
+
+#[no_mangle]
+pub extern "C" fn fetch_odrl_usage(handle: *mut i64,assignee: *const c_char,action: *const c_char,target: *const c_char) ->  i64 {
+    ffi::Engine::fetch_odrl_usage(handle, assignee, action, target)
+}
+
+#[no_mangle]
+pub extern "C" fn reset_odrl_usage(handle: *mut i64,assignee: *const c_char,action: *const c_char,target: *const c_char) ->  i32 {
+    ffi::Engine::reset_odrl_usage(handle, assignee, action, target)
+}
+
+#[no_mangle]
+pub extern "C" fn strict_odrl_world(handle: *mut i64,strict: i32) ->  i32 {
+    ffi::Engine::strict_odrl_world(handle, strict != 0)
+}
+
+#[no_mangle]
+pub extern "C" fn register_odrl_profile(profile: *const c_char) ->  i32 {
+    ffi::Engine::register_odrl_profile(profile)
+}
+
 #[cfg(test)]
 mod tests {
     use std::ffi::CString;
-    use rusadrl::model::policy::OdrlRequest;
+    use std::sync::Arc;
+    use rusadrl::handle_to_policy;
+    use rusadrl::model::clock::ManualClock;
+    use rusadrl::model::policy::{OdrlRequest, PolicyEngine};
+    use rusadrl::model::stateworld::GLOBAL_WORLD_CACHE;
     use super::*;
+
+    //the fixtures declare this profile
+    fn register_profile() {
+        let profile = CString::new("https://datasate.ids/profiles/0001").unwrap();
+        assert_eq!(register_odrl_profile(profile.as_c_str().as_ptr()), 0);
+    }
+
+    //drive the world time by hand instead of sleeping
+    fn manual_clock(handle: *mut i64) -> ManualClock {
+        let clock = ManualClock::new(1_700_000_000_000);
+        let key = PolicyEngine::find_world_key(handle_to_policy(handle).unwrap()).unwrap();
+        let mut world = GLOBAL_WORLD_CACHE.find_world(key.as_str()).unwrap();
+        world.value_mut().set_clock(Arc::new(clock.clone()));
+        clock
+    }
     #[test]
     fn it_works() {
         let json = r#"{
@@ -421,6 +603,7 @@ mod tests {
 
         //covert json to *const c_char
         let json = CString::new(json).unwrap();
+        register_profile();
         let handle = create_odrl_world(json.as_c_str().as_ptr());
 
         //eval policy
@@ -483,6 +666,7 @@ mod tests {
 
         //covert json to *const c_char
         let json = CString::new(json).unwrap();
+        register_profile();
         let handle = create_odrl_world(json.as_c_str().as_ptr());
 
         //eval policy
@@ -498,7 +682,17 @@ mod tests {
         req.set_assigner(to_iri("https://datasate.ids/users/gaosg"));
         req.set_target(to_iri("https://datasate.ids/llm/dataset/0001"));
         let result = ffi::Engine::policy_evaluate(handle,req);
-        println!("result: {:?}", result);
+        assert_eq!(result, 1);
+
+        //count constraints are evaluated against the usage counted by the engine
+        let assignee = CString::new("https://datasate.ids/usercollection/liumazi").unwrap();
+        let action = CString::new("http://www.w3.org/ns/odrl/2/use").unwrap();
+        let target = CString::new("https://datasate.ids/llm/dataset/0001").unwrap();
+        assert_eq!(fetch_odrl_usage(handle, assignee.as_ptr(), action.as_ptr(), target.as_ptr()), 1);
+        assert_eq!(reset_odrl_usage(handle, assignee.as_ptr(), action.as_ptr(), target.as_ptr()), 0);
+        assert_eq!(fetch_odrl_usage(handle, assignee.as_ptr(), action.as_ptr(), target.as_ptr()), 0);
+        assert_eq!(strict_odrl_world(handle, 1), 0);
+        assert_eq!(strict_odrl_world(std::ptr::null_mut(), 1), -1);
     }
 
     #[test]
@@ -557,7 +751,9 @@ mod tests {
 
         //covert json to *const c_char
         let json = CString::new(json).unwrap();
+        register_profile();
         let handle = create_odrl_world(json.as_c_str().as_ptr());
+        let clock = manual_clock(handle);
 
         //eval policy
         let mut req = OdrlRequest::default();
@@ -579,8 +775,7 @@ mod tests {
         println!("result: {:?}", result);
         let result = ffi::Engine::policy_evaluate(handle,req.clone());
         println!("result: {:?}", result);
-        //sleep 1 day
-        std::thread::sleep(std::time::Duration::from_secs(2));
+        clock.advance(2_000);
         let result = ffi::Engine::policy_evaluate(handle,req.clone());
         println!("result: {:?}", result);
     }
@@ -641,7 +836,9 @@ mod tests {
 
         //covert json to *const c_char
         let json = CString::new(json).unwrap();
+        register_profile();
         let handle = create_odrl_world(json.as_c_str().as_ptr());
+        let clock = manual_clock(handle);
 
         //eval policy
         let mut req = OdrlRequest::default();
@@ -665,7 +862,7 @@ mod tests {
         println!("result: {:?}", result);
         let result = ffi::Engine::policy_evaluate(handle,req.clone());
         println!("result: {:?}", result);
-        std::thread::sleep(std::time::Duration::from_secs(10));
+        clock.advance(10_000);
         let result = ffi::Engine::policy_evaluate(handle,req.clone());
         println!("result: {:?}", result);
         let result = ffi::Engine::policy_evaluate(handle,req.clone());
@@ -723,7 +920,9 @@ mod tests {
 
         //covert json to *const c_char
         let json = CString::new(json).unwrap();
+        register_profile();
         let handle = create_odrl_world(json.as_c_str().as_ptr());
+        let clock = manual_clock(handle);
 
         //eval policy
         let mut req = OdrlRequest::default();
@@ -741,11 +940,11 @@ mod tests {
 
         let result = ffi::Engine::policy_evaluate(handle,req.clone());
         println!("result: {:?}", result);
-        std::thread::sleep(std::time::Duration::from_secs(1));
+        clock.advance(1_000);
         let result = ffi::Engine::policy_evaluate(handle,req.clone());
         println!("result: {:?}", result);
 
-        std::thread::sleep(std::time::Duration::from_secs(5));
+        clock.advance(5_000);
         let result = ffi::Engine::policy_evaluate(handle,req.clone());
         println!("result: {:?}", result);
     }
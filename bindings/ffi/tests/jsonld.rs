@@ -1,5 +1,5 @@
 use std::ffi::CString;
-use adrlcffi::{create_odrl_world, ffi, to_iri};
+use adrlcffi::{create_odrl_world, ffi, register_odrl_profile, to_iri};
 use adrlcffi::ffi::*;
 use rusadrl::model::policy::OdrlRequest;
 
@@ -8,6 +8,10 @@ fn test_basic_jsonld() {
     //load and read jsonld file
     let json = std::fs::read_to_string("tests/jsonld/basic.jsonld").unwrap();
 
+    //the fixture declares this profile
+    let profile = CString::new("https://datasate.ids/profiles/0001").unwrap();
+    register_odrl_profile(profile.as_c_str().as_ptr());
+
     //covert json to *const c_char
     let json = CString::new(json).unwrap();
     let handle = create_odrl_world(json.as_c_str().as_ptr());
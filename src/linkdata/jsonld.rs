@@ -213,20 +213,16 @@ pub struct  JsonLdLogicConstraint{
     #[serde(rename = "http://www.w3.org/ns/odrl/2/operator")]
     operator: Option<JsonLdAnyValue>,
 
+    //operands may be logical constraints themselves
     #[serde(rename = "http://www.w3.org/ns/odrl/2/constraint")]
-    constraint: Option<JsonLdOptionArray<JsonLdConstraint>>,
+    constraint: Option<JsonLdOptionArray<JsonLdConstraintOne>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum JsonLdConstraintOne {
-    LogicConstraint(JsonLdLogicConstraint),
-    Constraint(JsonLdConstraint),
-}
-
-#[derive(Debug,Clone,Builder,Getter,GetterMut,Setter,Serialize,Deserialize)]
-pub struct  JsonLdConsequence {
-
+    LogicConstraint(Box<JsonLdLogicConstraint>),
+    Constraint(Box<JsonLdConstraint>),
 }
 
 #[derive(Debug,Clone,Builder,Getter,GetterMut,Setter,Serialize,Deserialize)]
@@ -236,21 +232,21 @@ pub struct  JsonLdDuty {
     uid: Option<String>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/action")]
-    action: Option<JsonLdAction>,
+    action: Option<JsonLdOptionArray<JsonLdAction>>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/target")]
     target: Option<JsonLdOptionArray<JsonLdAsset>>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/assigner")]
-    assigner: Option<JsonLdParty>,
+    assigner: Option<JsonLdOptionArray<JsonLdParty>>,
     #[serde(rename = "http://www.w3.org/ns/odrl/2/assignee")]
-    assignee: Option<JsonLdParty>,
+    assignee: Option<JsonLdOptionArray<JsonLdParty>>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/constraint")]
     constraint: Option<JsonLdOptionArray<JsonLdConstraintOne>>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/consequence")]
-    consequence: Option<JsonLdConsequence>,
+    consequence: Option<Box<JsonLdOptionArray<JsonLdDuty>>>,
 }
 
 #[derive(Debug,Clone,Builder,Getter,GetterMut,Setter,Serialize,Deserialize)]
@@ -260,21 +256,21 @@ pub struct  JsonLdPermission{
     uid: Option<String>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/action")]
-    action: Option<JsonLdAction>,
+    action: Option<JsonLdOptionArray<JsonLdAction>>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/target")]
     target: Option<JsonLdOptionArray<JsonLdAsset>>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/assigner")]
-    assigner: Option<JsonLdParty>,
+    assigner: Option<JsonLdOptionArray<JsonLdParty>>,
     #[serde(rename = "http://www.w3.org/ns/odrl/2/assignee")]
-    assignee: Option<JsonLdParty>,
+    assignee: Option<JsonLdOptionArray<JsonLdParty>>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/constraint")]
     constraint: Option<JsonLdOptionArray<JsonLdConstraintOne>>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/duty")]
-    duty: Option<JsonLdDuty>,
+    duty: Option<JsonLdOptionArray<JsonLdDuty>>,
 }
 
 #[derive(Debug,Clone,Builder,Getter,GetterMut,Setter,Serialize,Deserialize)]
@@ -284,21 +280,21 @@ pub struct  JsonLdProhibition{
     uid: Option<String>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/action")]
-    action: Option<JsonLdAction>,
+    action: Option<JsonLdOptionArray<JsonLdAction>>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/target")]
     target: Option<JsonLdOptionArray<JsonLdAsset>>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/assigner")]
-    assigner: Option<JsonLdParty>,
+    assigner: Option<JsonLdOptionArray<JsonLdParty>>,
     #[serde(rename = "http://www.w3.org/ns/odrl/2/assignee")]
-    assignee: Option<JsonLdParty>,
+    assignee: Option<JsonLdOptionArray<JsonLdParty>>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/constraint")]
     constraint: Option<JsonLdOptionArray<JsonLdConstraintOne>>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/remedy")]
-    remedy: Option<JsonLdDuty>,
+    remedy: Option<JsonLdOptionArray<JsonLdDuty>>,
 }
 
 
@@ -329,12 +325,12 @@ pub struct JsonLdPolicy {
     conflict: Option<JsonLdAnyValue>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/assigner")]
-    assigner: Option<JsonLdParty>,
+    assigner: Option<JsonLdOptionArray<JsonLdParty>>,
     #[serde(rename = "http://www.w3.org/ns/odrl/2/assignee")]
-    assignee: Option<JsonLdParty>,
+    assignee: Option<JsonLdOptionArray<JsonLdParty>>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/action")]
-    action: Option<JsonLdAction>,
+    action: Option<JsonLdOptionArray<JsonLdAction>>,
 
     #[serde(rename = "http://www.w3.org/ns/odrl/2/target")]
     target: Option<JsonLdOptionArray<JsonLdAsset>>,
@@ -29,23 +29,28 @@ use serde_json::Value;
 use static_iref::iri;
 use crate::config;
 use crate::linkdata::jsonld::{JsonLdAction, JsonLdAnyValue, JsonLdAsset, JsonLdConstraint, JsonLdConstraintOne, JsonLdDuty, JsonLdLogicConstraint, JsonLdOptionArray, JsonLdParser, JsonLdParty, JsonLdPermission, JsonLdPolicy, JsonLdProhibition};
+use crate::reference::types::TypedValue;
 use crate::model::constraint::Constraint;
 use crate::model::constraint::LogicConstraint;
 use crate::model::{constraint::ConstraintUnion};
 use crate::model::action::{Action, ActionType};
 use crate::model::asset::{Asset, AssetCollection, AssetUnion};
+use crate::model::conflict_analyzer::ConflictAnalyzer;
 use crate::model::conflict_strategy::ConflictStrategy;
 use crate::model::constraint_left_operand::ConstraintLeftOperand;
 use crate::model::constraint_left_operand::ConstraintLeftOperand::meteredTime;
 use crate::model::constraint_operator::{ConstraintLogicOperator, ConstraintOperator};
 use crate::model::constraint_right_operand::{ConstraintRightOperand, RightOperandReference, RightOperandType};
 use crate::model::duty::Duty;
+use crate::model::error::OdrlError;
 use crate::model::model_factory::ModelFactory;
 use crate::model::party::{Party, PartyCollection, PartyUnion};
 use crate::model::permission::Permission;
-use crate::model::policy::{Agreement, PolicyUnion};
+use crate::model::policy::{Agreement, Policy, PolicyEngine, PolicyUnion};
 use crate::model::prohibition::Prohibition;
 use crate::model::rule::Rule;
+use crate::model::policy_store::GLOBAL_POLICY_STORE;
+use crate::model::profile::GLOBAL_PROFILE_REGISTRY;
 use crate::model::stateworld::{StateWorld, GLOBAL_WORLD_CACHE};
 
 #[derive(Debug)]
@@ -55,6 +60,146 @@ fn to_iri(iri: &str) -> Option<IriBuf> {
     Some(IriBuf::new(iri.to_string()).unwrap())
 }
 
+//Policy level properties which are pushed down into rules during normalization
+struct RuleDefaults {
+    assigner: Option<JsonLdOptionArray<JsonLdParty>>,
+    assignee: Option<JsonLdOptionArray<JsonLdParty>>,
+    target: Option<JsonLdOptionArray<JsonLdAsset>>,
+    action: Option<JsonLdOptionArray<JsonLdAction>>,
+}
+
+//Common access to the normalizable properties of permission, prohibition and duty
+trait NormalizedRule: Clone {
+    fn uid_mut(&mut self) -> &mut Option<String>;
+    fn assigner_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdParty>>;
+    fn assignee_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdParty>>;
+    fn target_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdAsset>>;
+    fn action_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdAction>>;
+    //duties nested in the rule, like the duty of a permission or the remedy of a prohibition
+    fn normalize_nested(&mut self, defaults: &RuleDefaults);
+}
+
+impl NormalizedRule for JsonLdPermission {
+    fn uid_mut(&mut self) -> &mut Option<String> { self.get_uid_mut() }
+    fn assigner_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdParty>> { self.get_assigner_mut() }
+    fn assignee_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdParty>> { self.get_assignee_mut() }
+    fn target_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdAsset>> { self.get_target_mut() }
+    fn action_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdAction>> { self.get_action_mut() }
+    fn normalize_nested(&mut self, defaults: &RuleDefaults) { normalize_rules(self.get_duty_mut(), defaults) }
+}
+
+impl NormalizedRule for JsonLdProhibition {
+    fn uid_mut(&mut self) -> &mut Option<String> { self.get_uid_mut() }
+    fn assigner_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdParty>> { self.get_assigner_mut() }
+    fn assignee_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdParty>> { self.get_assignee_mut() }
+    fn target_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdAsset>> { self.get_target_mut() }
+    fn action_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdAction>> { self.get_action_mut() }
+    fn normalize_nested(&mut self, defaults: &RuleDefaults) { normalize_rules(self.get_remedy_mut(), defaults) }
+}
+
+impl NormalizedRule for JsonLdDuty {
+    fn uid_mut(&mut self) -> &mut Option<String> { self.get_uid_mut() }
+    fn assigner_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdParty>> { self.get_assigner_mut() }
+    fn assignee_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdParty>> { self.get_assignee_mut() }
+    fn target_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdAsset>> { self.get_target_mut() }
+    fn action_mut(&mut self) -> &mut Option<JsonLdOptionArray<JsonLdAction>> { self.get_action_mut() }
+    fn normalize_nested(&mut self, defaults: &RuleDefaults) {
+        let mut consequence = self.get_consequence_mut().take().map(|consequence| *consequence);
+        normalize_rules(&mut consequence, defaults);
+        *self.get_consequence_mut() = consequence.map(Box::new);
+    }
+}
+
+fn inherit_value<T: Clone>(slot: &mut Option<T>, value: &Option<T>) {
+    if slot.is_none() {
+        *slot = value.clone();
+    }
+}
+
+//split a multi valued property into single valued ones, keep None as a single slot
+fn split_values<T: Clone>(value: &Option<JsonLdOptionArray<T>>) -> Vec<Option<JsonLdOptionArray<T>>> {
+    match value {
+        None => vec![None],
+        Some(JsonLdOptionArray::Single(one)) => vec![Some(JsonLdOptionArray::Single(one.clone()))],
+        Some(JsonLdOptionArray::Multiple(many)) => {
+            if many.is_empty() {
+                return vec![None];
+            }
+            many.iter().map(|one| Some(JsonLdOptionArray::Single(one.clone()))).collect()
+        }
+    }
+}
+
+//split a rule into atomic rules, each one having at most one target, action, assigner and assignee
+fn atomize_rule<R: NormalizedRule>(rule: &R) -> Vec<R> {
+    let mut rule = rule.clone();
+    let targets = split_values(rule.target_mut());
+    let actions = split_values(rule.action_mut());
+    let assigners = split_values(rule.assigner_mut());
+    let assignees = split_values(rule.assignee_mut());
+
+    let mut atomic = vec![];
+    for target in &targets {
+        for action in &actions {
+            for assigner in &assigners {
+                for assignee in &assignees {
+                    let mut one = rule.clone();
+                    *one.target_mut() = target.clone();
+                    *one.action_mut() = action.clone();
+                    *one.assigner_mut() = assigner.clone();
+                    *one.assignee_mut() = assignee.clone();
+                    atomic.push(one);
+                }
+            }
+        }
+    }
+
+    //atomic rules keep apart in decisions and violations, their uids derive from the source rule
+    if atomic.len() > 1 {
+        if let Some(uid) = rule.uid_mut().clone() {
+            for (index, one) in atomic.iter_mut().enumerate() {
+                *one.uid_mut() = Some(split_uid(&uid, index + 1));
+            }
+        }
+    }
+    atomic
+}
+
+fn split_uid(uid: &str, index: usize) -> String {
+    if uid.contains('#') {
+        format!("{}-{}", uid, index)
+    } else {
+        format!("{}#{}", uid, index)
+    }
+}
+
+fn normalize_rules<R: NormalizedRule>(rules: &mut Option<JsonLdOptionArray<R>>, defaults: &RuleDefaults) {
+    let list = match rules.take() {
+        None => return,
+        Some(JsonLdOptionArray::Single(rule)) => vec![rule],
+        Some(JsonLdOptionArray::Multiple(rules)) => rules,
+    };
+
+    let mut normalized = vec![];
+    for mut rule in list {
+        inherit_value(rule.assigner_mut(), &defaults.assigner);
+        inherit_value(rule.assignee_mut(), &defaults.assignee);
+        inherit_value(rule.target_mut(), &defaults.target);
+        inherit_value(rule.action_mut(), &defaults.action);
+        rule.normalize_nested(defaults);
+        normalized.extend(atomize_rule(&rule));
+    }
+    *rules = Some(JsonLdOptionArray::Multiple(normalized));
+}
+
+//after normalization every rule property holds a single value
+fn first_value<T: Clone>(json: &JsonLdOptionArray<T>) -> Option<T> {
+    match json {
+        JsonLdOptionArray::Single(one) => Some(one.clone()),
+        JsonLdOptionArray::Multiple(many) => many.first().cloned(),
+    }
+}
+
 fn to_right_operand(val: serde_json::Value) -> Result<ConstraintRightOperand,anyhow::Error> {
     let mut operand = ConstraintRightOperand::default();
     match val {
@@ -70,6 +215,22 @@ fn to_right_operand(val: serde_json::Value) -> Result<ConstraintRightOperand,any
             operand.set_ty(RightOperandType::Literal);
             operand.set_value(Some(bool.to_string()));
         }
+        //typed or language tagged literal
+        serde_json::Value::Object(object) if object.contains_key("@value") => {
+            let value = match object.get("@value") {
+                Some(serde_json::Value::String(str)) => str.clone(),
+                Some(other) => other.to_string(),
+                None => String::new(),
+            };
+            let typed = match (object.get("@type").and_then(|ty| ty.as_str()), object.get("@language").and_then(|lang| lang.as_str())) {
+                (Some(datatype), _) => TypedValue::parse_xsd(datatype, &value)?,
+                (None, Some(language)) => TypedValue::String(value.clone(), Some(language.to_lowercase())),
+                (None, None) => TypedValue::String(value.clone(), None),
+            };
+            operand.set_ty(RightOperandType::Literal);
+            operand.set_value(Some(value));
+            operand.set_typed(Some(typed));
+        }
         serde_json::Value::Array(arr) => {
             operand.set_ty(RightOperandType::LiteralSet);
             let mut set:Vec<String> = vec![];
@@ -136,7 +297,8 @@ fn compile_constraint_one(json: &JsonLdConstraint) -> Result<Constraint,anyhow::
     //check operator
     if json.get_operator().is_some() {
         let operator = json.get_operator().clone().unwrap();
-        let op = ConstraintOperator::try_from(operator.get_uid().as_str()).unwrap();
+        let op = ConstraintOperator::try_from(operator.get_uid().as_str())
+            .map_err(|_| OdrlError::UnknownOperator(operator.get_uid().clone()))?;
         constraint.set_operator(Some(op));
     } else {
         return Err(anyhow!("None constraint operator found, error at {},{}",file!(),line!()));
@@ -155,7 +317,6 @@ fn compile_constraint_one(json: &JsonLdConstraint) -> Result<Constraint,anyhow::
         if left_operand_iri.contains("timeWindow")  {
             //adjust operator to gt
             constraint.set_operator(Some(ConstraintOperator::gt));
-            constraint.enabled_slide_window = true;
         }
 
         let left_operand = ConstraintLeftOperand::try_from(left_operand_iri)?;
@@ -167,20 +328,15 @@ fn compile_constraint_one(json: &JsonLdConstraint) -> Result<Constraint,anyhow::
     //check right operand
     if json.get_right_operand().is_some() {
         let right_operand = json.get_right_operand().clone().unwrap();
-        constraint.set_rightOperand(to_right_operand(right_operand).ok());
-
-        if constraint.enabled_slide_window {
-            match constraint.rightOperand {
-              Some(ref right) => {
-                  let val = right.get_value().clone().unwrap();
-                  constraint.set_slide_window(val);
-              }
-              None => {}
-            }
+        constraint.set_rightOperand(Some(to_right_operand(right_operand)?));
+
+        if let (Some(ConstraintLeftOperand::timeWindow), Some(right)) = (&constraint.leftOperand, &constraint.rightOperand) {
+            let window = right.get_value().clone().unwrap_or_default();
+            constraint.set_time_window(&window)?;
         }
     } else if json.get_right_operand_reference().is_some() {
         let right_operand_ref = json.get_right_operand_reference().clone().unwrap();
-        constraint.set_rightOperand(to_right_operand_reference(right_operand_ref).ok());
+        constraint.set_rightOperand(Some(to_right_operand_reference(right_operand_ref)?));
     } else {
         return Err(anyhow!("None right operand found, error at {},{}",file!(),line!()));
     }
@@ -203,20 +359,7 @@ fn compile_logic_constraint_one(json: &JsonLdLogicConstraint) -> Result<LogicCon
     }
 
     if let Some(operands) = json.get_constraint() {
-       match operands {
-           JsonLdOptionArray::Single(constraint) => {
-               let constraint = compile_constraint_one(constraint)?;
-               logic_constraint.set_operand(Some(vec![constraint]));
-           }
-           JsonLdOptionArray::Multiple(constraints) => {
-               let mut list = vec![];
-               for constraint in constraints {
-                   let constraint = compile_constraint_one(constraint)?;
-                   list.push(constraint);
-               }
-               logic_constraint.set_operand(Some(list));
-           }
-       }
+        logic_constraint.set_operand(Some(compile_constraint(operands)?));
     }
     Ok(logic_constraint)
 }
@@ -254,7 +397,46 @@ fn compile_constraint(json: &JsonLdOptionArray<JsonLdConstraintOne>) -> Result<V
     Ok(constraints_parsed)
 }
 
-fn compile_party(json: &JsonLdParty) -> Option<PartyUnion> {
+//Constraints keep rate limit counters and sequence progress in the world, duties their state,
+//unnamed ones are identified by the policy uid and their position in the policy so that they never share state
+fn identify_constraints(constraints: &mut Option<Vec<ConstraintUnion>>, scope: &str) {
+    for (index, constraint) in constraints.iter_mut().flatten().enumerate() {
+        let identity = format!("{}/constraint-{}", scope, index + 1);
+        match constraint {
+            ConstraintUnion::Constraint(constraint) => constraint.set_identity(Some(identity)),
+            ConstraintUnion::LogicConstraint(logic) => {
+                identify_constraints(logic.get_operand_mut(), &identity);
+                logic.set_identity(Some(identity));
+            }
+        }
+    }
+}
+
+fn identify_duties(duties: &mut Option<Vec<Duty>>, scope: &str, kind: &str) {
+    for (index, duty) in duties.iter_mut().flatten().enumerate() {
+        let scope = format!("{}/{}-{}", scope, kind, index + 1);
+        identify_constraints(duty.rule.get_constraint_mut(), &scope);
+        duty.set_identity(Some(scope.clone()));
+        identify_duties(&mut duty.consequence, &scope, "consequence");
+    }
+}
+
+fn identify_policy(policy: &mut Policy, uid: &str) {
+    identify_constraints(policy.get_constraint_mut(), &format!("{}#policy", uid));
+    for (index, permission) in policy.get_permission_mut().iter_mut().flatten().enumerate() {
+        let scope = format!("{}#permission-{}", uid, index + 1);
+        identify_constraints(permission.duty.get_constraint_mut(), &scope);
+        identify_duties(&mut permission.duties, &scope, "duty");
+    }
+    for (index, prohibition) in policy.get_prohibition_mut().iter_mut().flatten().enumerate() {
+        let scope = format!("{}#prohibition-{}", uid, index + 1);
+        identify_constraints(prohibition.rule.get_constraint_mut(), &scope);
+        identify_duties(&mut prohibition.remedy, &scope, "remedy");
+    }
+    identify_duties(policy.get_obligation_mut(), &format!("{}#policy", uid), "obligation");
+}
+
+fn compile_party(json: &JsonLdParty) -> Result<Option<PartyUnion>, anyhow::Error> {
     //check party type
     let party_type = json.get_party_type().clone();
     let is_collection = party_type.is_some() && party_type.unwrap().contains("PartyCollection");
@@ -274,11 +456,11 @@ fn compile_party(json: &JsonLdParty) -> Option<PartyUnion> {
                 // handle refinement
                 if json.get_refinement().is_some() {
                     let refinement = json.get_refinement().clone().unwrap();
-                    collection.set_refinement(compile_constraint(&refinement).ok());
+                    collection.set_refinement(Some(compile_constraint(&refinement)?));
                 }
-                return Some(PartyUnion::PartyCollection(collection));
+                return Ok(Some(PartyUnion::PartyCollection(collection)));
             }
-            return None;
+            return Ok(None);
         }
         PartyUnion::Party(mut party) => {
             if json.get_uid().is_some()  {
@@ -294,7 +476,7 @@ fn compile_party(json: &JsonLdParty) -> Option<PartyUnion> {
                 //check refinement
                 if json.get_refinement().is_some() {
                     let refinement = json.get_refinement().clone().unwrap();
-                    party.set_refinement(compile_constraint(&refinement).ok());
+                    party.set_refinement(Some(compile_constraint(&refinement)?));
                 }
 
                 //Update state world
@@ -308,14 +490,14 @@ fn compile_party(json: &JsonLdParty) -> Option<PartyUnion> {
                     // }
                 }
 
-                return Some(PartyUnion::Party(party));
+                return Ok(Some(PartyUnion::Party(party)));
             }
-            return None;
+            return Ok(None);
         }
     }
 }
 
-fn compile_asset_one(json: &JsonLdAsset) -> Option<AssetUnion> {
+fn compile_asset_one(json: &JsonLdAsset) -> Result<Option<AssetUnion>, anyhow::Error> {
     //check party type
     let asset_type = json.get_asset_type().clone();
 
@@ -334,12 +516,12 @@ fn compile_asset_one(json: &JsonLdAsset) -> Option<AssetUnion> {
                 //check refinement
                 if json.get_refinement().is_some() {
                     let refinement = json.get_refinement().clone().unwrap();
-                    ac.set_refinement(compile_constraint(&refinement).ok());
+                    ac.set_refinement(Some(compile_constraint(&refinement)?));
                 }
 
-                return Some(AssetUnion::AssetCollection(ac));
+                return Ok(Some(AssetUnion::AssetCollection(ac)));
             }
-            return None;
+            return Ok(None);
         }
         AssetUnion::Asset(mut asset) => {
             let uid = json.get_uid().clone();
@@ -351,12 +533,12 @@ fn compile_asset_one(json: &JsonLdAsset) -> Option<AssetUnion> {
                 asset.set_partOf(Some(vec![to_iri(part_of.as_str()).unwrap()]));
             }
 
-            return Some(AssetUnion::Asset(asset));
+            return Ok(Some(AssetUnion::Asset(asset)));
         }
     }
 }
 
-fn compile_asset(json: &JsonLdOptionArray<JsonLdAsset>) -> Option<AssetUnion> {
+fn compile_asset(json: &JsonLdOptionArray<JsonLdAsset>) -> Result<Option<AssetUnion>, anyhow::Error> {
     match json {
         JsonLdOptionArray::Single(asset) => {
             let asset = compile_asset_one(&asset);
@@ -369,7 +551,7 @@ fn compile_asset(json: &JsonLdOptionArray<JsonLdAsset>) -> Option<AssetUnion> {
             }
         }
     }
-    return None;
+    return Ok(None);
 }
 
 fn compile_profile(json: &JsonLdOptionArray<JsonLdAnyValue>) -> Option<Vec<IriBuf>> {
@@ -397,12 +579,16 @@ fn compile_conflict(json: JsonLdAnyValue) -> Option<ConflictStrategy> {
     return Some(conflict);
 }
 
-fn compile_action(json: JsonLdAction) -> Option<Action> {
+fn compile_action_type(iri: &str) -> Result<ActionType, anyhow::Error> {
+    ActionType::try_from(iri).map_err(|_| OdrlError::UnknownAction(iri.to_string()).into())
+}
+
+fn compile_action(json: JsonLdAction) -> Result<Action, anyhow::Error> {
     let mut action = Action::default();
 
     //check action type
     let action_id = json.get_uid().clone();
-    let typ = ActionType::try_from(action_id.as_str()).unwrap();
+    let typ = compile_action_type(action_id.as_str())?;
     action.set_actionType(typ);
 
     //check included in
@@ -411,7 +597,7 @@ fn compile_action(json: JsonLdAction) -> Option<Action> {
         let mut actions = vec![];
         for act_id in included_in {
             let mut a = Action::default();
-            let typ = ActionType::try_from(act_id.as_str()).unwrap();
+            let typ = compile_action_type(act_id.as_str())?;
             a.set_actionType(typ);
             actions.push(a);
         }
@@ -425,7 +611,7 @@ fn compile_action(json: JsonLdAction) -> Option<Action> {
         for act in implies {
             let mut a = Action::default();
             let action_id = act.get_uid().as_str();
-            let typ = ActionType::try_from(action_id).unwrap();
+            let typ = compile_action_type(action_id)?;
             a.set_actionType(typ);
             actions.push(a);
         }
@@ -435,10 +621,10 @@ fn compile_action(json: JsonLdAction) -> Option<Action> {
     //check refinements
     if json.get_refinement().is_some() {
         let refinement = json.get_refinement().clone().unwrap();
-        action.set_refinements(compile_constraint(&refinement).ok());
+        action.set_refinements(Some(compile_constraint(&refinement)?));
     }
 
-    return Some(action);
+    Ok(action)
 }
 
 fn compile_permission_one(json: &JsonLdPermission) -> Result<Rule,anyhow::Error> {
@@ -452,7 +638,7 @@ fn compile_permission_one(json: &JsonLdPermission) -> Result<Rule,anyhow::Error>
     //check assigner
     if json.get_assigner().is_some() {
         let assigner = json.get_assigner().clone().unwrap();
-        let assigner = compile_party(&assigner);
+        let assigner = first_value(&assigner).map(|assigner| compile_party(&assigner)).transpose()?.flatten();
         if assigner.is_some() {
             rule.set_assigner(assigner);
         }
@@ -461,7 +647,7 @@ fn compile_permission_one(json: &JsonLdPermission) -> Result<Rule,anyhow::Error>
     //check assignee
     if json.get_assignee().is_some() {
         let assignee = json.get_assignee().clone().unwrap();
-        let assignee = compile_party(&assignee);
+        let assignee = first_value(&assignee).map(|assignee| compile_party(&assignee)).transpose()?.flatten();
         if assignee.is_some() {
             rule.set_assignee(assignee);
         }
@@ -470,16 +656,16 @@ fn compile_permission_one(json: &JsonLdPermission) -> Result<Rule,anyhow::Error>
     //check action
     if json.get_action().is_some() {
         let action = json.get_action().clone().unwrap();
-        let action = compile_action(action);
+        let action = first_value(&action).map(compile_action).transpose()?;
         if action.is_some() {
-            rule.set_action(Some(action.unwrap()));
+            rule.set_action(action);
         }
     }
 
     //check target
     if json.get_target().is_some() {
         let target = json.get_target().clone().unwrap();
-        let target = compile_asset(&target);
+        let target = compile_asset(&target)?;
         if target.is_some() {
             rule.set_target(Some(target.unwrap()));
         }
@@ -488,7 +674,7 @@ fn compile_permission_one(json: &JsonLdPermission) -> Result<Rule,anyhow::Error>
     //check constraint
     if json.get_constraint().is_some() {
         let constraint = json.get_constraint().clone().unwrap();
-        rule.set_constraint(compile_constraint(&constraint).ok());
+        rule.set_constraint(Some(compile_constraint(&constraint)?));
     }
 
     return Ok(rule);
@@ -505,7 +691,7 @@ fn compile_duty_one(json: &JsonLdDuty) -> Result<Rule,anyhow::Error> {
     //check assigner
     if json.get_assigner().is_some() {
         let assigner = json.get_assigner().clone().unwrap();
-        let assigner = compile_party(&assigner);
+        let assigner = first_value(&assigner).map(|assigner| compile_party(&assigner)).transpose()?.flatten();
         if assigner.is_some() {
             rule.set_assigner(assigner);
         }
@@ -514,7 +700,7 @@ fn compile_duty_one(json: &JsonLdDuty) -> Result<Rule,anyhow::Error> {
     //check assignee
     if json.get_assignee().is_some() {
         let assignee = json.get_assignee().clone().unwrap();
-        let assignee = compile_party(&assignee);
+        let assignee = first_value(&assignee).map(|assignee| compile_party(&assignee)).transpose()?.flatten();
         if assignee.is_some() {
             rule.set_assignee(assignee);
         }
@@ -523,16 +709,16 @@ fn compile_duty_one(json: &JsonLdDuty) -> Result<Rule,anyhow::Error> {
     //check action
     if json.get_action().is_some() {
         let action = json.get_action().clone().unwrap();
-        let action = compile_action(action);
+        let action = first_value(&action).map(compile_action).transpose()?;
         if action.is_some() {
-            rule.set_action(Some(action.unwrap()));
+            rule.set_action(action);
         }
     }
 
     //check target
     if json.get_target().is_some() {
         let target = json.get_target().clone().unwrap();
-        let target = compile_asset(&target);
+        let target = compile_asset(&target)?;
         if target.is_some() {
             rule.set_target(Some(target.unwrap()));
         }
@@ -541,12 +727,24 @@ fn compile_duty_one(json: &JsonLdDuty) -> Result<Rule,anyhow::Error> {
     //check constraint
     if json.get_constraint().is_some() {
         let constraint = json.get_constraint().clone().unwrap();
-        rule.set_constraint(compile_constraint(&constraint).ok());
+        rule.set_constraint(Some(compile_constraint(&constraint)?));
     }
 
     return Ok(rule);
 }
 
+fn compile_duty(json: &JsonLdDuty) -> Result<Duty,anyhow::Error> {
+    let rule = compile_duty_one(json)?;
+    let mut duty = Duty::builder().rule(rule).build();
+
+    //check consequence, duties due when this one is violated
+    if let Some(consequence) = json.get_consequence() {
+        duty.set_consequence(Some(compile_obligation(consequence)?));
+    }
+
+    Ok(duty)
+}
+
 fn compile_prohibition_one(json: &JsonLdProhibition) -> Result<Rule,anyhow::Error> {
     let mut rule = Rule::default();
 
@@ -558,7 +756,7 @@ fn compile_prohibition_one(json: &JsonLdProhibition) -> Result<Rule,anyhow::Erro
     //check assigner
     if json.get_assigner().is_some() {
         let assigner = json.get_assigner().clone().unwrap();
-        let assigner = compile_party(&assigner);
+        let assigner = first_value(&assigner).map(|assigner| compile_party(&assigner)).transpose()?.flatten();
         if assigner.is_some() {
             rule.set_assigner(assigner);
         }
@@ -567,7 +765,7 @@ fn compile_prohibition_one(json: &JsonLdProhibition) -> Result<Rule,anyhow::Erro
     //check assignee
     if json.get_assignee().is_some() {
         let assignee = json.get_assignee().clone().unwrap();
-        let assignee = compile_party(&assignee);
+        let assignee = first_value(&assignee).map(|assignee| compile_party(&assignee)).transpose()?.flatten();
         if assignee.is_some() {
             rule.set_assignee(assignee);
         }
@@ -576,16 +774,16 @@ fn compile_prohibition_one(json: &JsonLdProhibition) -> Result<Rule,anyhow::Erro
     //check action
     if json.get_action().is_some() {
         let action = json.get_action().clone().unwrap();
-        let action = compile_action(action);
+        let action = first_value(&action).map(compile_action).transpose()?;
         if action.is_some() {
-            rule.set_action(Some(action.unwrap()));
+            rule.set_action(action);
         }
     }
 
     //check target
     if json.get_target().is_some() {
         let target = json.get_target().clone().unwrap();
-        let target = compile_asset(&target);
+        let target = compile_asset(&target)?;
         if target.is_some() {
             rule.set_target(Some(target.unwrap()));
         }
@@ -594,7 +792,7 @@ fn compile_prohibition_one(json: &JsonLdProhibition) -> Result<Rule,anyhow::Erro
     //check constraint
     if json.get_constraint().is_some() {
         let constraint = json.get_constraint().clone().unwrap();
-        rule.set_constraint(compile_constraint(&constraint).ok());
+        rule.set_constraint(Some(compile_constraint(&constraint)?));
     }
 
     return Ok(rule);
@@ -606,14 +804,16 @@ fn compile_permission(json: &JsonLdOptionArray<JsonLdPermission>) -> Result<Vec<
     return match json {
         JsonLdOptionArray::Single(permission) => {
             let duty = compile_permission_one(permission)?;
-            let perm = Permission::builder().duty(duty).build();
+            let duties = permission.get_duty().as_ref().map(compile_obligation).transpose()?;
+            let perm = Permission::builder().duty(duty).duties(duties).build();
             permissions_parsed.push(perm);
             Ok(permissions_parsed)
         }
         JsonLdOptionArray::Multiple(permissions) => {
             for perm in permissions {
                 let duty = compile_permission_one(perm)?;
-                let perm = Permission::builder().duty(duty).build();
+                let duties = perm.get_duty().as_ref().map(compile_obligation).transpose()?;
+                let perm = Permission::builder().duty(duty).duties(duties).build();
                 permissions_parsed.push(perm);
             }
             Ok(permissions_parsed)
@@ -626,15 +826,13 @@ fn compile_obligation(json: &JsonLdOptionArray<JsonLdDuty>) -> Result<Vec<Duty>,
 
     return match json {
         JsonLdOptionArray::Single(obligation) => {
-            let duty = compile_duty_one(obligation)?;
-            let duty = Duty::builder().rule(duty).build();
+            let duty = compile_duty(obligation)?;
             obligations_parsed.push(duty);
             Ok(obligations_parsed)
         }
         JsonLdOptionArray::Multiple(obligations) => {
             for obligation in obligations {
-                let duty = compile_duty_one(obligation)?;
-                let duty = Duty::builder().rule(duty).build();
+                let duty = compile_duty(obligation)?;
                 obligations_parsed.push(duty);
             }
             Ok(obligations_parsed)
@@ -648,14 +846,16 @@ fn compile_prohibition(json: &JsonLdOptionArray<JsonLdProhibition>) -> Result<Ve
     return match json {
         JsonLdOptionArray::Single(prohibition) => {
             let rule = compile_prohibition_one(prohibition)?;
-            let prohibit = Prohibition::builder().rule(rule).build();
+            let remedy = prohibition.get_remedy().as_ref().map(compile_obligation).transpose()?;
+            let prohibit = Prohibition::builder().rule(rule).remedy(remedy).build();
             prohibitions_parsed.push(prohibit);
             Ok(prohibitions_parsed)
         }
         JsonLdOptionArray::Multiple(prohibitions) => {
             for prohibition in prohibitions {
                 let rule = compile_prohibition_one(prohibition)?;
-                let prohibit = Prohibition::builder().rule(rule).build();
+                let remedy = prohibition.get_remedy().as_ref().map(compile_obligation).transpose()?;
+                let prohibit = Prohibition::builder().rule(rule).remedy(remedy).build();
                 prohibitions_parsed.push(prohibit);
             }
             Ok(prohibitions_parsed)
@@ -705,159 +905,17 @@ impl OdrlLoader {
     }
 
     async fn normalize(policy: &mut JsonLdPolicy) -> Result<(), anyhow::Error> {
-        //get policy level assigner
-        let policy_assigner = policy.get_assigner().clone();
-        //get policy level assignee
-        let policy_assignee = policy.get_assignee().clone();
-        //get policy level asset
-        let policy_asset = policy.get_target().clone();
-
-        //get policy level action
-        let policy_action = policy.get_action().clone();
-
-        //Normalize permissions
-        let permissions = policy.get_permission_mut();
-        if let Some(permission) = permissions {
-            match permission {
-                JsonLdOptionArray::Single(permission) => {
-                   //check permission level assigner
-                   let assigner = permission.get_assigner();
-                   if assigner.is_none() && policy_assigner.is_some() {
-                       permission.set_assigner(policy_assigner.clone());
-                   }
-
-                   //check permission level assignee
-                   let assignee = permission.get_assignee();
-                   if assignee.is_none() && policy_assignee.is_some() {
-                       permission.set_assignee(policy_assignee.clone());
-                   }
-                   //check permission level asset
-                   let asset = permission.get_target();
-                   if asset.is_none() && policy_asset.is_some() {
-                       permission.set_target(policy_asset.clone());
-                   }
-
-                   //check permission level action
-                   let action = permission.get_action();
-                   if action.is_none() && policy_action.is_some() {
-                        permission.set_action(policy_action.clone());
-                   }
-                },
-                JsonLdOptionArray::Multiple(permissions) => {
-                    for permission in permissions {
-                        //check permission level assigner
-                        let assigner = permission.get_assigner();
-                        if assigner.is_none() && policy_assigner.is_some() {
-                            permission.set_assigner(policy_assigner.clone());
-                        }
-
-                        //check permission level assignee
-                        let assignee = permission.get_assignee();
-                        if assignee.is_none() && policy_assignee.is_some() {
-                            permission.set_assignee(policy_assignee.clone());
-                        }
-                        //check permission level asset
-                        let asset = permission.get_target();
-                        if asset.is_none() && policy_asset.is_some() {
-                            permission.set_target(policy_asset.clone());
-                        }
-
-                        let action = permission.get_action();
-                        if action.is_none() && policy_action.is_some() {
-                            permission.set_action(policy_action.clone());
-                        }
-                    }
-                }
-            }
-        }
-
-        //Normalize obligations
-        let obligations = policy.get_obligation_mut();
-        if let Some(obligation) =  obligations {
-            match obligation {
-                JsonLdOptionArray::Single(obligation) => {
-                    //check obligation level assigner
-                    let assigner = obligation.get_assigner();
-                    if assigner.is_none() && policy_assignee.is_some() {
-                        obligation.set_assigner(policy_assignee.clone());
-                    }
-
-                    //check obligation level assignee
-                    let assignee = obligation.get_assignee();
-                    if assignee.is_none() && policy_assignee.is_some() {
-                        obligation.set_assignee(policy_assignee.clone());
-                    }
-
-                    let target = obligation.get_target();
-                    if target.is_none() && policy_asset.is_some() {
-                        obligation.set_target(policy_asset.clone());
-                    }
-                },
-                JsonLdOptionArray::Multiple(obligations) => {
-                    for obligation in obligations {
-                        //check obligation level assigner
-                        let assigner = obligation.get_assigner();
-                        if assigner.is_none() && policy_assignee.is_some() {
-                            obligation.set_assigner(policy_assignee.clone());
-                        }
-
-                        //check obligation level assignee
-                        let assignee = obligation.get_assignee();
-                        if assignee.is_none() && policy_assignee.is_some() {
-                            obligation.set_assignee(policy_assignee.clone());
-                        }
-
-                        let target = obligation.get_target();
-                        if target.is_none() && policy_asset.is_some() {
-                            obligation.set_target(policy_asset.clone());
-                        }
-                    }
-                }
-            }
-        }
-
-        //Normalize prohibitions
-        let prohibitions = policy.get_prohibition_mut();
-        if let Some(prohibition) = prohibitions {
-            match prohibition {
-                JsonLdOptionArray::Single(prohibition) => {
-                    //check prohibition level assigner
-                    let assigner = prohibition.get_assigner();
-                    if assigner.is_none() && policy_assignee.is_some() {
-                        prohibition.set_assigner(policy_assignee.clone());
-                    }
-                    //check prohibition level assignee
-                    let assignee = prohibition.get_assignee();
-                    if assignee.is_none() && policy_assignee.is_some() {
-                        prohibition.set_assignee(policy_assignee.clone());
-                    }
-
-                    let target = prohibition.get_target();
-                    if target.is_none() && policy_asset.is_some() {
-                        prohibition.set_target(policy_asset.clone());
-                    }
-                }
-                JsonLdOptionArray::Multiple(prohibitions) => {
-                    for prohibition in prohibitions {
-                        //check prohibition level assigner
-                        let assigner = prohibition.get_assigner();
-                        if assigner.is_none() && policy_assignee.is_some() {
-                            prohibition.set_assigner(policy_assignee.clone());
-                        }
-                        //check prohibition level assignee
-                        let assignee = prohibition.get_assignee();
-                        if assignee.is_none() && policy_assignee.is_some() {
-                            prohibition.set_assignee(policy_assignee.clone());
-                        }
-
-                        let target = prohibition.get_target();
-                        if target.is_none() && policy_asset.is_some() {
-                            prohibition.set_target(policy_asset.clone());
-                        }
-                    }
-                }
-            }
-        }
+        //policy level properties are interiorized into every rule which does not declare its own
+        let defaults = RuleDefaults {
+            assigner: policy.get_assigner().clone(),
+            assignee: policy.get_assignee().clone(),
+            target: policy.get_target().clone(),
+            action: policy.get_action().clone(),
+        };
+
+        normalize_rules(policy.get_permission_mut(), &defaults);
+        normalize_rules(policy.get_prohibition_mut(), &defaults);
+        normalize_rules(policy.get_obligation_mut(), &defaults);
 
         Ok(())
     }
@@ -914,104 +972,158 @@ impl OdrlLoader {
         }
     }
 
-    pub async fn compile(policy: &JsonLdPolicy) -> Result<PolicyUnion, anyhow::Error> {
-        let mut type_ = policy.get_policy_type().clone();
-        if type_.is_none() {
-            type_ = Some("http://www.w3.org/ns/odrl/2/Set".to_string());
+    fn compile_policy(policy: &JsonLdPolicy, eval: &mut Policy) -> Result<(), anyhow::Error> {
+        //copy policy uid to eval
+        let uid = policy.get_uid().clone();
+        eval.set_uid(IriBuf::new(uid.clone()).ok());
+
+        //initialize world
+        let world = StateWorld::builder().uid(IriBuf::new(uid.clone()).ok()).build();
+        {
+            //cache world
+            let world_cache = GLOBAL_WORLD_CACHE.clone();
+            world_cache.add_world(uid.as_str(),world);
         }
 
-        let evalator = ModelFactory::create(type_.unwrap());
-        match evalator {
-            crate::model::policy::PolicyUnion::Agreement(mut eval) => {
-                let eval = eval.get_policy_mut();
+        //check and copy assignee
+        let assignee = policy.get_assignee().clone();
+        if let Some(assignee) = assignee.as_ref().and_then(first_value) {
+            eval.set_assignee(compile_party(&assignee)?);
+        }
 
-                //copy policy uid to eval
-                let uid = policy.get_uid().clone();
-                eval.set_uid(IriBuf::new(uid.clone()).ok());
+        //check and copy assigner
+        let assigner = policy.get_assigner().clone();
+        if let Some(assigner) = assigner.as_ref().and_then(first_value) {
+            eval.set_assigner(compile_party(&assigner)?);
+        }
 
-                //initialize world
-                let world = StateWorld::builder().uid(IriBuf::new(uid.clone()).ok()).build();
-                {
-                    //cache world
-                    let world_cache = GLOBAL_WORLD_CACHE.clone();
-                    world_cache.add_world(uid.as_str(),world);
-                }
+        //check and copy target
+        let target = policy.get_target().clone();
+        if target.is_some() {
+            eval.set_target(compile_asset(&target.unwrap())?);
+        }
 
-                //check and copy assignee
-                let assignee = policy.get_assignee().clone();
-                if assignee.is_some() {
-                    eval.set_assignee(compile_party(&assignee.unwrap()));
-                }
+        //check and copy profile
+        let profile = policy.get_profile().clone();
+        eval.set_profile(compile_profile(&profile));
 
-                //check and copy assigner
-                let assigner = policy.get_assigner().clone();
-                if assigner.is_some() {
-                    eval.set_assigner(compile_party(&assigner.unwrap()));
-                }
+        //check and copy conflict
+        if let Some(conflict) = policy.get_conflict().clone() {
+            eval.set_conflict(compile_conflict(conflict));
+        }
 
-                //check and copy target
-                let target = policy.get_target().clone();
-                if target.is_some() {
-                    eval.set_target(compile_asset(&target.unwrap()));
-                }
+        //check and copy action
+        let action = policy.get_action().clone();
+        if let Some(action) = action.as_ref().and_then(first_value) {
+            eval.set_action(Some(compile_action(action)?));
+        }
 
-                //check and copy profile
-                let profile = policy.get_profile().clone();
-                eval.set_profile(compile_profile(&profile));
+        //check and copy constraint
+        let constraint = policy.get_constraint().clone();
+        if constraint.is_some() {
+            let constraint = constraint.unwrap();
+            eval.set_constraint(Some(compile_constraint(&constraint)?));
+        }
 
-                //check and copy conflict
-                let conflict = policy.get_conflict().clone().unwrap();
-                eval.set_conflict(compile_conflict(conflict));
+        //check and copy permission
+        let permission = policy.get_permission().clone();
+        if permission.is_some() {
+            let permission = permission.unwrap();
+            eval.set_permission(Some(compile_permission(&permission)?));
+        }
 
-                //check and copy action
-                let action = policy.get_action().clone();
-                if action.is_some() {
-                    let action = action.unwrap();
-                    eval.set_action(compile_action(action));
-                }
+        //check and copy obligation
+        let obligation = policy.get_obligation().clone();
+        if obligation.is_some() {
+            let obligation = obligation.unwrap();
+            eval.set_obligation(Some(compile_obligation(&obligation)?));
+        }
 
-                //check and copy constraint
-                let constraint = policy.get_constraint().clone();
-                if constraint.is_some() {
-                    let constraint = constraint.unwrap();
-                    eval.set_constraint(compile_constraint(&constraint).ok());
-                }
+        //check and copy prohibition
+        let prohibition = policy.get_prohibition().clone();
+        if prohibition.is_some() {
+            let prohibition = prohibition.unwrap();
+            eval.set_prohibition(Some(compile_prohibition(&prohibition)?));
+        }
 
-                //check and copy permission
-                let permission = policy.get_permission().clone();
-                if permission.is_some() {
-                    let permission = permission.unwrap();
-                    eval.set_permission(compile_permission(&permission).ok());
-                }
+        // check and copy inheritFrom
+        let inherit_from = policy.get_inherit_from().clone();
+        if inherit_from.is_some() {
+            let inherit_from = inherit_from.unwrap();
+            eval.set_inheritFrom(compile_inherit_from(&inherit_from).ok());
+        }
 
-                //check and copy obligation
-                let obligation = policy.get_obligation().clone();
-                if obligation.is_some() {
-                    let obligation = obligation.unwrap();
-                    eval.set_obligation(compile_obligation(&obligation).ok());
-                }
+        //unnamed constraints get an identity for the state they keep in the world
+        identify_policy(eval, uid.as_str());
 
-                //check and copy prohibition
-                let prohibition = policy.get_prohibition().clone();
-                if prohibition.is_some() {
-                    let prohibition = prohibition.unwrap();
-                    eval.set_prohibition(compile_prohibition(&prohibition).ok());
-                }
+        Ok(())
+    }
+
+    pub async fn compile(policy: &JsonLdPolicy) -> Result<PolicyUnion, anyhow::Error> {
+        let mut type_ = policy.get_policy_type().clone();
+        if type_.is_none() {
+            type_ = Some("http://www.w3.org/ns/odrl/2/Set".to_string());
+        }
+
+        //declared profiles are checked first, their features are unknown to the engine otherwise
+        for profile in compile_profile(policy.get_profile()).unwrap_or_default() {
+            if !GLOBAL_PROFILE_REGISTRY.is_registered(profile.as_str()) {
+                return Err(OdrlError::UnknownProfile(profile.as_str().to_string()).into());
+            }
+        }
+
+        let mut evalator = ModelFactory::create(type_.unwrap());
+        match &mut evalator {
+            PolicyUnion::Agreement(eval) => {
+                OdrlLoader::compile_policy(policy, eval.get_policy_mut())?;
+            }
+            PolicyUnion::Offer(eval) => {
+                OdrlLoader::compile_policy(policy, eval.get_policy_mut())?;
+            }
+            PolicyUnion::Set(eval) => {
+                OdrlLoader::compile_policy(policy, eval.get_policy_mut())?;
+            }
+            PolicyUnion::Privacy(eval) => {
+                OdrlLoader::compile_policy(policy, eval.get_policy_mut())?;
+            }
+            PolicyUnion::Request(eval) => {
+                OdrlLoader::compile_policy(policy, eval.get_policy_mut())?;
+            }
+            PolicyUnion::Assert(eval) => {
+                OdrlLoader::compile_policy(policy, eval.get_policy_mut())?;
+            }
+            PolicyUnion::Ticket(eval) => {
+                OdrlLoader::compile_policy(policy, eval.get_policy_mut())?;
+            }
+        }
 
-                // check and copy inheritFrom
-                let inherit_from = policy.get_inherit_from().clone();
-                if inherit_from.is_some() {
-                    let inherit_from = inherit_from.unwrap();
-                    eval.set_inheritFrom(compile_inherit_from(&inherit_from).ok());
+        //every declared profile must be known to the engine
+        GLOBAL_PROFILE_REGISTRY.validate(PolicyEngine::find_policy(&evalator))?;
+
+        //overlapping permission and prohibition void a policy with invalid conflict strategy,
+        //inherited rules and strategy take part, a parent not loaded yet is resolved when deciding
+        let merged = GLOBAL_POLICY_STORE.resolve(&evalator).unwrap_or_else(|_| evalator.clone());
+        let conflicts = ConflictAnalyzer::analyze(&merged);
+        if !conflicts.is_empty() {
+            {
+                let config = config::CONFIG.lock().unwrap();
+                if config.verbose {
+                    println!(">>--------------------ODRL RULE CONFLICTS-----------------------------<<");
+                    println!("{:#?}", conflicts);
+                    println!(">>--------------------ODRL RULE CONFLICTS end-----------------------------<<");
                 }
+            }
 
-                let agreement = Agreement::builder().policy(eval.clone()).build();
-                return Ok(PolicyUnion::Agreement(agreement));
+            let compiled = PolicyEngine::find_policy(&merged);
+            if let Some(ConflictStrategy::invalid) = compiled.get_conflict() {
+                let uid = compiled.get_uid().as_ref().map(|uid| uid.as_str().to_string()).unwrap_or_default();
+                return Err(OdrlError::ConflictingRules(uid, conflicts.len()).into());
             }
-            _ => {}
         }
 
-        Err(anyhow!("Error during compilation"))
+        //make it available to policies inheriting from it
+        GLOBAL_POLICY_STORE.add_policy(evalator.clone());
+        Ok(evalator)
     }
 }
 
@@ -1037,6 +1149,1017 @@ mod tests {
 
         let policy = OdrlLoader::parse(expanded).await;
 
+        register_profile();
         let _policy = OdrlLoader::compile(&mut policy.unwrap()).await;
     }
+
+    //the fixtures declare this profile
+    fn register_profile() {
+        use crate::model::profile::Profile;
+        GLOBAL_PROFILE_REGISTRY.register(Profile::new("http://a.com/abc")).unwrap();
+    }
+
+    async fn load_policy(path: &str) -> PolicyUnion {
+        register_profile();
+        let doc = OdrlLoader::load_file("http://www.w3.org/ns/odrl/2".to_string(), path.to_string());
+        let expanded = doc.await.unwrap();
+        let policy = OdrlLoader::parse(expanded).await.unwrap();
+        OdrlLoader::compile(&policy).await.unwrap()
+    }
+
+    #[tokio::test]
+    pub async fn test_decision() {
+        use crate::model::decision::{DecisionEffect, EvalStep, RuleKind};
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        let policy = load_policy("src/data/decision.jsonld").await;
+        let mut world = StateWorld::new("http://abc.tds/policy/decision/1");
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/use"));
+        req.set_assignee(to_iri("https://aa/cc"));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Permit);
+        assert_eq!(decision.permission, to_iri("http://abc.tds/policy/decision/1/permission/1"));
+        assert!(decision.prohibition.is_none());
+        assert_eq!(decision.conflict, Some(ConflictStrategy::prohibit));
+        let failed = decision.trace.iter().find(|t| !t.passed).unwrap();
+        assert_eq!(failed.kind, RuleKind::Prohibition);
+        assert_eq!(failed.step, EvalStep::Constraint);
+
+        //prohibition applies now, conflict strategy prohibit wins
+        world.add_state("http://www.w3.org/ns/odrl/2/purpose", "marketing");
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Deny);
+        assert_eq!(decision.prohibition, to_iri("http://abc.tds/policy/decision/1/prohibition/1"));
+        assert!(!PolicyEngine::eval(&mut world, &policy, &req).unwrap());
+
+        //assignee mismatch, no rule applies
+        req.set_assignee(to_iri("https://aa/dd"));
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::NotApplicable);
+        assert_eq!(decision.trace[0].step, EvalStep::Assignee);
+    }
+
+    #[tokio::test]
+    pub async fn test_request_attributes() {
+        use crate::model::decision::DecisionEffect;
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        let policy = load_policy("src/data/decision.jsonld").await;
+        let mut world = StateWorld::new("http://abc.tds/policy/decision/1");
+        world.add_state("http://www.w3.org/ns/odrl/2/purpose", "research");
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/use"));
+        req.set_assignee(to_iri("https://aa/cc"));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+
+        //the request purpose overrides the world state
+        let mut marketing = req.clone();
+        marketing.add_attribute("purpose", "marketing");
+        assert_eq!(marketing.get_attribute("http://www.w3.org/ns/odrl/2/purpose").unwrap().get_sval(), Some("marketing".to_string()));
+        let decision = PolicyEngine::decide(&mut world, &policy, &marketing).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Deny);
+
+        //attributes do not leak into the world, the next request falls back to world state
+        assert!(world.request_attributes.is_empty());
+        assert_eq!(world.get_state("http://www.w3.org/ns/odrl/2/purpose"), Some("research"));
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Permit);
+    }
+
+    #[tokio::test]
+    pub async fn test_operand_provider() {
+        use std::sync::Arc;
+        use async_trait::async_trait;
+        use crate::model::constraint_left_operand::ConstraintLeftOperand;
+        use crate::model::decision::DecisionEffect;
+        use crate::model::operand_provider::GLOBAL_OPERAND_PROVIDERS;
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+        use crate::reference::types::{OperandValue, OperandValueType};
+        use crate::traits::definions::{AsyncLeftOperandProvider, LeftOperandProvider};
+
+        fn string_value(value: &str) -> OperandValue {
+            let mut val = OperandValue::default();
+            val.set_ty(OperandValueType::string);
+            val.set_sval(Some(value.to_string()));
+            val
+        }
+
+        struct DeviceProvider;
+        impl LeftOperandProvider for DeviceProvider {
+            fn provide(&self, _operand: &ConstraintLeftOperand, _world: &StateWorld) -> Result<Option<OperandValue>, anyhow::Error> {
+                Ok(Some(string_value("laptop")))
+            }
+        }
+
+        //looks up the region of the caller address
+        struct GeoIpProvider;
+        #[async_trait]
+        impl AsyncLeftOperandProvider for GeoIpProvider {
+            async fn provide(&self, _operand: &ConstraintLeftOperand, req: &OdrlRequest) -> Result<Option<OperandValue>, anyhow::Error> {
+                let address = req.get_attribute("https://example.com/clientIp").and_then(|ip| ip.get_sval());
+                match address.as_deref() {
+                    Some("10.0.0.1") => Ok(Some(string_value("https://www.wikidata.org/wiki/Q46"))),
+                    _ => Ok(None),
+                }
+            }
+        }
+
+        GLOBAL_OPERAND_PROVIDERS.register("systemDevice", Arc::new(DeviceProvider));
+        GLOBAL_OPERAND_PROVIDERS.register_async("http://www.w3.org/ns/odrl/2/spatial", Arc::new(GeoIpProvider));
+
+        let policy = load_policy("src/data/provider.jsonld").await;
+        let mut world = StateWorld::new("http://abc.tds/policy/provider/1");
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/use"));
+        req.set_assignee(to_iri("https://aa/cc"));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+        req.add_attribute("https://example.com/clientIp", "10.0.0.1");
+
+        //async providers are only resolved by the async entry point
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_ne!(decision.effect, DecisionEffect::Permit);
+        let decision = PolicyEngine::decide_async(&mut world, &policy, &req).await.unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Permit);
+
+        //an unknown address has no region, the world state is the fallback
+        let mut elsewhere = req.clone();
+        elsewhere.add_attribute("https://example.com/clientIp", "10.0.0.2");
+        let decision = PolicyEngine::decide_async(&mut world, &policy, &elsewhere).await.unwrap();
+        assert_ne!(decision.effect, DecisionEffect::Permit);
+        world.add_state("http://www.w3.org/ns/odrl/2/spatial", "https://www.wikidata.org/wiki/Q46");
+        let decision = PolicyEngine::decide_async(&mut world, &policy, &elsewhere).await.unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Permit);
+
+        //the request attribute wins over the provider
+        let mut desktop = req.clone();
+        desktop.add_attribute("systemDevice", "desktop");
+        let decision = PolicyEngine::decide_async(&mut world, &policy, &desktop).await.unwrap();
+        assert_ne!(decision.effect, DecisionEffect::Permit);
+    }
+
+    #[tokio::test]
+    pub async fn test_spatial() {
+        use crate::model::decision::DecisionEffect;
+        use crate::model::geo::GLOBAL_REGION_REGISTRY;
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        let policy = load_policy("src/data/spatial.jsonld").await;
+        let mut world = StateWorld::new("http://abc.tds/policy/spatial/1");
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/use"));
+        req.set_assignee(to_iri("https://aa/cc"));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+
+        //usage is limited to the EU, by coordinates or by region code
+        let decide = |world: &mut StateWorld, req: &OdrlRequest, left_operand: &str, location: &str| {
+            let mut located = req.clone();
+            located.add_attribute(left_operand, location);
+            PolicyEngine::decide(world, &policy, &located).unwrap().effect
+        };
+        assert_eq!(decide(&mut world, &req, "spatial", "52.52,13.405"), DecisionEffect::Permit);
+        //subdivisions are only known from a loaded admin-1 dataset
+        let mut paris = req.clone();
+        paris.add_attribute("spatial", "FR-75");
+        let decision = PolicyEngine::decide(&mut world, &policy, &paris).unwrap();
+        assert_ne!(decision.effect, DecisionEffect::Permit);
+        assert!(decision.indeterminate[0].reason.contains("FR-75"));
+        GLOBAL_REGION_REGISTRY.load_geojson(r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"iso_3166_2": "FR-75", "name": "Paris"},
+             "geometry": {"type": "Polygon", "coordinates": [[[2.22, 48.81], [2.47, 48.81], [2.47, 48.91], [2.22, 48.91], [2.22, 48.81]]]}}
+        ]}"#).unwrap();
+        assert_eq!(decide(&mut world, &req, "spatial", "FR-75"), DecisionEffect::Permit);
+        assert_ne!(decide(&mut world, &req, "spatial", "46.948,7.447"), DecisionEffect::Permit);
+        assert_ne!(decide(&mut world, &req, "spatial", "GB"), DecisionEffect::Permit);
+
+        let mut display = req.clone();
+        display.set_action(to_iri("http://www.w3.org/ns/odrl/2/display"));
+        assert_eq!(decide(&mut world, &display, "spatialCoordinates", "POINT (2.3522 48.8566)"), DecisionEffect::Permit);
+        assert_ne!(decide(&mut world, &display, "spatialCoordinates", "45.764,4.8357"), DecisionEffect::Permit);
+    }
+
+    #[tokio::test]
+    pub async fn test_unit() {
+        use crate::model::decision::DecisionEffect;
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        let policy = load_policy("src/data/unit.jsonld").await;
+        let mut world = StateWorld::new("http://abc.tds/policy/unit/1");
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/use"));
+        req.set_assignee(to_iri("https://aa/cc"));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+
+        //the amount paid is compared in the unit of the policy
+        let mut paid = req.clone();
+        paid.add_attribute("payAmount", "12.50 EUR");
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &paid).unwrap().effect, DecisionEffect::Permit);
+        paid.add_attribute("payAmount", "9.99 €");
+        assert_ne!(PolicyEngine::decide(&mut world, &policy, &paid).unwrap().effect, DecisionEffect::Permit);
+
+        //another currency or a bare number is an error, not a silent deny
+        paid.add_attribute("payAmount", "12.50 USD");
+        assert!(PolicyEngine::decide(&mut world, &policy, &paid).is_err());
+        paid.add_attribute("payAmount", "12.50");
+        assert!(PolicyEngine::decide(&mut world, &policy, &paid).is_err());
+
+        //sizes convert between units of information
+        let mut distribute = req.clone();
+        distribute.set_action(to_iri("http://www.w3.org/ns/odrl/2/distribute"));
+        distribute.add_attribute("absoluteSize", "4096 KiB");
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &distribute).unwrap().effect, DecisionEffect::Permit);
+        distribute.add_attribute("absoluteSize", "5000 kB");
+        assert_ne!(PolicyEngine::decide(&mut world, &policy, &distribute).unwrap().effect, DecisionEffect::Permit);
+        distribute.add_attribute("absoluteSize", "1 h");
+        assert!(PolicyEngine::decide(&mut world, &policy, &distribute).is_err());
+    }
+
+    #[tokio::test]
+    pub async fn test_usage_count() {
+        use crate::model::decision::DecisionEffect;
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+        use crate::model::stateworld::UsageKey;
+
+        let policy = load_policy("src/data/count.jsonld").await;
+        let mut world = StateWorld::new("http://abc.tds/policy/count/1");
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/play"));
+        req.set_assignee(to_iri("https://aa/cc"));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+
+        //every permitted request is counted, 'count lteq 3' allows three plays and denies the fourth
+        for _ in 0..3 {
+            assert_eq!(PolicyEngine::decide(&mut world, &policy, &req).unwrap().effect, DecisionEffect::Permit);
+        }
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &req).unwrap().effect, DecisionEffect::Deny);
+        let usage = UsageKey::new("https://aa/cc", "http://www.w3.org/ns/odrl/2/play", "https://example.com/media/video1.mp4");
+        assert_eq!(world.usage_count(&usage), 3);
+
+        //counters are kept per assignee
+        let mut other = req.clone();
+        other.set_assignee(to_iri("https://aa/dd"));
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &other).unwrap().effect, DecisionEffect::Permit);
+        assert_eq!(world.request_usage_count(), None);
+
+        world.reset_usage(&usage);
+        assert_eq!(world.usage_count(&usage), 0);
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &req).unwrap().effect, DecisionEffect::Permit);
+        world.reset_assignee_usage("https://aa/dd");
+        assert_eq!(world.usage_count(&other.usage_key()), 0);
+    }
+
+    #[tokio::test]
+    pub async fn test_rate_limit() {
+        use std::sync::Arc;
+        use crate::model::clock::ManualClock;
+        use crate::model::decision::DecisionEffect;
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        let policy = load_policy("src/data/rate_limit.jsonld").await;
+        let clock = ManualClock::new(1_000_000);
+        let mut world = StateWorld::new_with_clock("http://abc.tds/policy/rate/1", Arc::new(clock.clone()));
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/play"));
+        req.set_assignee(to_iri("https://aa/cc"));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+
+        //two plays in any 10 seconds
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &req).unwrap().effect, DecisionEffect::Permit);
+        clock.advance(1_000);
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &req).unwrap().effect, DecisionEffect::Permit);
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &req).unwrap().effect, DecisionEffect::Deny);
+        clock.advance(9_000);
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &req).unwrap().effect, DecisionEffect::Permit);
+
+        //one display a minute, a request denied by the purpose does not use up the window
+        let mut display = req.clone();
+        display.set_action(to_iri("http://www.w3.org/ns/odrl/2/display"));
+        display.add_attribute("purpose", "marketing");
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &display).unwrap().effect, DecisionEffect::Deny);
+        display.add_attribute("purpose", "research");
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &display).unwrap().effect, DecisionEffect::Permit);
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &display).unwrap().effect, DecisionEffect::Deny);
+        clock.set(1_080_000);
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &display).unwrap().effect, DecisionEffect::Permit);
+
+        world.clear_rate_limits();
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &display).unwrap().effect, DecisionEffect::Permit);
+    }
+
+    #[tokio::test]
+    pub async fn test_rate_limit_unnamed() {
+        use std::sync::Arc;
+        use crate::model::clock::ManualClock;
+        use crate::model::decision::DecisionEffect;
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        let policy = load_policy("src/data/rate_limit_unnamed.jsonld").await;
+        let clock = ManualClock::new(1_000_000);
+        let mut world = StateWorld::new_with_clock("http://abc.tds/policy/rate/2", Arc::new(clock.clone()));
+
+        let mut play = OdrlRequest::default();
+        play.set_action(to_iri("http://www.w3.org/ns/odrl/2/play"));
+        play.set_assignee(to_iri("https://aa/cc"));
+        play.set_assigner(to_iri("https://aa/bb"));
+        play.set_target(to_iri("https://example.com/media/video1.mp4"));
+        let mut display = play.clone();
+        display.set_action(to_iri("http://www.w3.org/ns/odrl/2/display"));
+
+        //windows without uid each keep their own budget
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &play).unwrap().effect, DecisionEffect::Permit);
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &play).unwrap().effect, DecisionEffect::Deny);
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &display).unwrap().effect, DecisionEffect::Permit);
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &display).unwrap().effect, DecisionEffect::Deny);
+    }
+
+    #[tokio::test]
+    pub async fn test_rate_limit_malformed() {
+        register_profile();
+        let doc = OdrlLoader::load_file("http://www.w3.org/ns/odrl/2".to_string(), "src/data/rate_limit_malformed.jsonld".to_string());
+        let json = OdrlLoader::parse(doc.await.unwrap()).await.unwrap();
+
+        //a constraint which does not compile rejects the policy rather than dropping its siblings
+        let err = OdrlLoader::compile(&json).await.unwrap_err();
+        assert!(err.to_string().contains("1/1H"), "{}", err);
+    }
+
+    #[tokio::test]
+    pub async fn test_strict_mode() {
+        use crate::model::decision::{DecisionEffect, RuleKind};
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        let policy = load_policy("src/data/strict.jsonld").await;
+        let mut world = StateWorld::new("http://abc.tds/policy/strict/1");
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/play"));
+        req.set_assignee(to_iri("https://aa/cc"));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+        req.add_attribute("purpose", "research");
+        req.add_attribute("recipient", "red");
+
+        //a constraint of unknown data type is indeterminate, it fails the permission and is reported
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Deny);
+        assert_eq!(decision.reason.as_deref(), Some("permission constraint not satisfied"));
+        assert_eq!(decision.indeterminate.len(), 1);
+        let indeterminate = &decision.indeterminate[0];
+        assert_eq!(indeterminate.kind, RuleKind::Permission);
+        assert_eq!(indeterminate.rule.as_ref().unwrap().as_str(), "http://abc.tds/policy/strict/1/permission/1");
+        assert_eq!(indeterminate.constraint.as_ref().unwrap().as_str(), "http://abc.tds/policy/strict/1/constraint/colour");
+        assert!(!indeterminate.reason.is_empty());
+
+        //a satisfied operand decides the or, the indeterminate one does not matter
+        let mut other = req.clone();
+        other.set_assignee(to_iri("https://aa/dd"));
+        let decision = PolicyEngine::decide(&mut world, &policy, &other).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Permit);
+        assert!(decision.indeterminate.is_empty());
+
+        //otherwise the or is indeterminate as a whole
+        other.add_attribute("purpose", "marketing");
+        let decision = PolicyEngine::decide(&mut world, &policy, &other).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Deny);
+        assert_eq!(decision.indeterminate.len(), 1);
+
+        //in strict mode an indeterminate constraint denies with its reason
+        world.set_strict(true);
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Deny);
+        assert!(decision.reason.unwrap().starts_with("constraint indeterminate: "));
+        assert!(world.indeterminate.is_empty());
+        other.add_attribute("purpose", "research");
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &other).unwrap().effect, DecisionEffect::Permit);
+    }
+
+    #[tokio::test]
+    pub async fn test_logic_sequence() {
+        use crate::model::decision::DecisionEffect;
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        let policy = load_policy("src/data/sequence.jsonld").await;
+        let mut world = StateWorld::new("http://abc.tds/policy/sequence/1");
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/play"));
+        req.set_assignee(to_iri("https://aa/cc"));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+
+        //either branch of the or must hold as a whole
+        for (purpose, recipient, effect) in [
+            ("research", "hospital", DecisionEffect::Permit),
+            ("education", "school", DecisionEffect::Permit),
+            ("research", "school", DecisionEffect::Deny),
+            ("marketing", "hospital", DecisionEffect::Deny),
+        ] {
+            let mut play = req.clone();
+            play.add_attribute("purpose", purpose);
+            play.add_attribute("recipient", recipient);
+            assert_eq!(PolicyEngine::decide(&mut world, &policy, &play).unwrap().effect, effect, "{} {}", purpose, recipient);
+        }
+
+        //registration, verification and payment one request after another, only the last step is permitted
+        let mut display = req.clone();
+        display.set_action(to_iri("http://www.w3.org/ns/odrl/2/display"));
+        let event = |event: &str| {
+            let mut display = display.clone();
+            display.add_attribute("event", event);
+            display
+        };
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &event("paid")).unwrap().effect, DecisionEffect::Deny);
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &event("registered")).unwrap().effect, DecisionEffect::Deny);
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &event("paid")).unwrap().effect, DecisionEffect::Deny);
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &event("verified")).unwrap().effect, DecisionEffect::Deny);
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &event("paid")).unwrap().effect, DecisionEffect::Permit);
+        //a completed sequence holds for every request
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &event("registered")).unwrap().effect, DecisionEffect::Permit);
+
+        world.reset_sequences("https://aa/cc");
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &event("paid")).unwrap().effect, DecisionEffect::Deny);
+    }
+
+    #[tokio::test]
+    pub async fn test_schedule() {
+        use std::sync::Arc;
+        use crate::model::clock::ManualClock;
+        use crate::model::decision::DecisionEffect;
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+        use crate::model::schedule::schedule_profile;
+
+        GLOBAL_PROFILE_REGISTRY.register(schedule_profile()).unwrap();
+        let policy = load_policy("src/data/schedule.jsonld").await;
+        let clock = ManualClock::new(0);
+        let mut world = StateWorld::new_with_clock("http://abc.tds/policy/schedule/1", Arc::new(clock.clone()));
+        let millis = |instant: &str| chrono::DateTime::parse_from_rfc3339(instant).unwrap().timestamp_millis();
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/use"));
+        req.set_assignee(to_iri("https://aa/cc"));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+
+        //use is limited to business hours
+        clock.set(millis("2025-03-04T10:00:00Z"));
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &req).unwrap().effect, DecisionEffect::Permit);
+        clock.set(millis("2025-03-04T18:00:00Z"));
+        assert_ne!(PolicyEngine::decide(&mut world, &policy, &req).unwrap().effect, DecisionEffect::Permit);
+        clock.set(millis("2025-03-08T10:00:00Z"));
+        assert_ne!(PolicyEngine::decide(&mut world, &policy, &req).unwrap().effect, DecisionEffect::Permit);
+
+        //distribution is embargoed on the first Monday of the month, after business hours
+        //as use includes distribute
+        let mut distribute = req.clone();
+        distribute.set_action(to_iri("http://www.w3.org/ns/odrl/2/distribute"));
+        clock.set(millis("2025-03-03T20:00:00Z"));
+        assert_ne!(PolicyEngine::decide(&mut world, &policy, &distribute).unwrap().effect, DecisionEffect::Permit);
+        clock.set(millis("2025-03-10T20:00:00Z"));
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &distribute).unwrap().effect, DecisionEffect::Permit);
+    }
+
+    #[tokio::test]
+    pub async fn test_concept_hierarchy() {
+        use crate::model::concept_hierarchy::GLOBAL_CONCEPT_HIERARCHY;
+        use crate::model::decision::DecisionEffect;
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        GLOBAL_CONCEPT_HIERARCHY.load_file("src/data/purpose.ttl").unwrap();
+        let policy = load_policy("src/data/concept.jsonld").await;
+        let mut world = StateWorld::new("http://abc.tds/policy/concept/1");
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/use"));
+        req.set_assignee(to_iri("https://aa/cc"));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+
+        //narrower purposes are a kind of research
+        for (purpose, effect) in [
+            ("http://example.com/purpose/Research", DecisionEffect::Permit),
+            ("http://example.com/purpose/MedicalResearch", DecisionEffect::Permit),
+            ("http://example.com/purpose/ClinicalTrial", DecisionEffect::Permit),
+            ("http://example.com/purpose/AcademicResearch", DecisionEffect::Permit),
+            ("http://example.com/purpose/Marketing", DecisionEffect::Deny),
+        ] {
+            let mut purposed = req.clone();
+            purposed.add_attribute("purpose", purpose);
+            assert_eq!(PolicyEngine::decide(&mut world, &policy, &purposed).unwrap().effect, effect, "{}", purpose);
+        }
+    }
+
+    #[tokio::test]
+    pub async fn test_duty() {
+        use crate::model::decision::{DecisionEffect, EvalStep, RuleKind};
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        let policy = load_policy("src/data/duty.jsonld").await;
+        let mut world = StateWorld::new("http://abc.tds/policy/duty/1");
+        let party = "https://aa/cc";
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/play"));
+        req.set_assignee(to_iri(party));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+
+        //permission matches, but its duty is not fulfilled yet
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Deny);
+        assert_eq!(decision.reason.as_deref(), Some("permission duty not fulfilled"));
+        assert_eq!(decision.trace.last().unwrap().step, EvalStep::Duty);
+        assert_eq!(world.pending_duties(party), vec![
+            "http://abc.tds/policy/duty/1/duty/1".to_string(),
+            "http://abc.tds/policy/duty/1/obligation/1".to_string(),
+        ]);
+
+        world.fulfill_duty(party, "http://abc.tds/policy/duty/1/duty/1");
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Permit);
+
+        //violated obligation blocks the permission until its consequence is fulfilled
+        PolicyEngine::violate_duty(&mut world, &policy, party, "http://abc.tds/policy/duty/1/obligation/1").unwrap();
+        assert_eq!(world.violated_duties(party), vec!["http://abc.tds/policy/duty/1/obligation/1".to_string()]);
+        assert_eq!(world.pending_duties(party), vec!["http://abc.tds/policy/duty/1#policy/obligation-1/consequence-1".to_string()]);
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Deny);
+        assert_eq!(decision.reason.as_deref(), Some("policy obligation violated"));
+        assert_eq!(decision.trace[0].kind, RuleKind::Obligation);
+
+        world.fulfill_duty(party, "http://abc.tds/policy/duty/1#policy/obligation-1/consequence-1");
+        assert!(PolicyEngine::eval(&mut world, &policy, &req).unwrap());
+
+        assert!(PolicyEngine::violate_duty(&mut world, &policy, party, "http://abc.tds/unknown").is_err());
+    }
+
+    #[tokio::test]
+    pub async fn test_duty_unnamed() {
+        use std::sync::Arc;
+        use crate::model::clock::ManualClock;
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        let policy = load_policy("src/data/duty_unnamed.jsonld").await;
+        let clock = ManualClock::new(0);
+        let mut world = StateWorld::new_with_clock("http://abc.tds/policy/duty/2", Arc::new(clock.clone()));
+        let millis = |instant: &str| chrono::DateTime::parse_from_rfc3339(instant).unwrap().timestamp_millis();
+        let party = "https://aa/cc";
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/play"));
+        req.set_assignee(to_iri(party));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+        let mut display = req.clone();
+        display.set_action(to_iri("http://www.w3.org/ns/odrl/2/display"));
+
+        //both compensate duties are owed, each one is tracked on its own
+        clock.set(millis("2024-06-01T00:00:00Z"));
+        assert!(!PolicyEngine::eval(&mut world, &policy, &req).unwrap());
+        assert!(!PolicyEngine::eval(&mut world, &policy, &display).unwrap());
+        assert_eq!(world.pending_duties(party), vec![
+            "http://abc.tds/policy/duty/2#permission-1/duty-1".to_string(),
+            "http://abc.tds/policy/duty/2#permission-2/duty-1".to_string(),
+        ]);
+        world.fulfill_duty(party, "http://abc.tds/policy/duty/2#permission-1/duty-1");
+        assert!(PolicyEngine::eval(&mut world, &policy, &req).unwrap());
+        assert!(!PolicyEngine::eval(&mut world, &policy, &display).unwrap());
+
+        //the duty of display is only owed before its deadline
+        clock.set(millis("2025-06-01T00:00:00Z"));
+        assert!(PolicyEngine::eval(&mut world, &policy, &display).unwrap());
+    }
+
+    #[tokio::test]
+    pub async fn test_remedy() {
+        use crate::model::decision::DecisionEffect;
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        let policy = load_policy("src/data/remedy.jsonld").await;
+        let mut world = StateWorld::new("http://abc.tds/policy/remedy/1");
+        let party = "https://aa/cc";
+        let remedy = "http://abc.tds/policy/remedy/1/remedy/1".to_string();
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/distribute"));
+        req.set_assignee(to_iri(party));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Deny);
+        let violations = world.get_violations(party);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].prohibition.as_deref(), Some("http://abc.tds/policy/remedy/1/prohibition/1"));
+        assert_eq!(violations[0].action.as_deref(), Some("http://www.w3.org/ns/odrl/2/distribute"));
+        assert_eq!(world.outstanding_remedies(party), vec![remedy.clone()]);
+
+        world.fulfill_duty(party, &remedy);
+        assert!(world.outstanding_remedies(party).is_empty());
+
+        //a new violation activates the remedy again
+        assert!(!PolicyEngine::eval(&mut world, &policy, &req).unwrap());
+        assert_eq!(world.get_violations(party).len(), 2);
+        assert_eq!(world.outstanding_remedies(party), vec![remedy.clone()]);
+
+        world.clear_remedies(party);
+        assert!(world.outstanding_remedies(party).is_empty());
+        assert!(world.get_duty_state(party, &remedy).is_none());
+        assert_eq!(world.get_violations(party).len(), 2);
+    }
+
+    #[tokio::test]
+    pub async fn test_prohibition_only() {
+        use crate::model::decision::DecisionEffect;
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        let policy = load_policy("src/data/prohibition_only.jsonld").await;
+        let mut world = StateWorld::new("http://abc.tds/policy/remedy/2");
+        let party = "https://aa/cc";
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/distribute"));
+        req.set_assignee(to_iri(party));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+
+        //no permission covers the request, the prohibition still applies and is violated
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Deny);
+        assert_eq!(decision.prohibition.as_ref().unwrap().as_str(), "http://abc.tds/policy/remedy/2/prohibition/1");
+        assert_eq!(world.get_violations(party).len(), 1);
+        assert_eq!(world.outstanding_remedies(party), vec!["http://abc.tds/policy/remedy/2/remedy/1".to_string()]);
+
+        //actions neither permitted nor prohibited are not applicable
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/play"));
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::NotApplicable);
+        assert!(decision.prohibition.is_none());
+        assert_eq!(world.get_violations(party).len(), 1);
+    }
+
+    #[tokio::test]
+    pub async fn test_policy_constraint() {
+        use crate::model::decision::{DecisionEffect, EvalStep, RuleKind};
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        let policy = load_policy("src/data/policy_constraint.jsonld").await;
+        let mut world = StateWorld::new("http://abc.tds/policy/constraint/1");
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/use"));
+        req.set_assignee(to_iri("https://aa/cc"));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+
+        //the policy constraint fails before any rule is matched
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Deny);
+        assert_eq!(decision.reason.as_deref(), Some("policy constraint not satisfied"));
+        assert_eq!(decision.trace.len(), 1);
+        assert_eq!(decision.trace[0].kind, RuleKind::Policy);
+        assert_eq!(decision.trace[0].step, EvalStep::Constraint);
+        assert_eq!(decision.trace[0].rule, to_iri("http://abc.tds/policy/constraint/1"));
+
+        world.add_state("http://www.w3.org/ns/odrl/2/purpose", "research");
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Permit);
+        assert!(decision.trace[0].passed);
+    }
+
+    #[tokio::test]
+    pub async fn test_inherit_from() {
+        use crate::model::decision::DecisionEffect;
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+        use crate::model::policy_store::GLOBAL_POLICY_STORE;
+
+        let child = load_policy("src/data/inherit/child.jsonld").await;
+        GLOBAL_POLICY_STORE.remove_policy("http://abc.tds/policy/inherit/parent");
+        let mut world = StateWorld::new("http://abc.tds/policy/inherit/child");
+
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/play"));
+        req.set_assignee(to_iri("https://aa/cc"));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+
+        //parent not loaded yet
+        let decision = PolicyEngine::decide(&mut world, &child, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Indeterminate);
+        assert!(decision.reason.unwrap().contains("not found"));
+
+        load_policy("src/data/inherit/parent.jsonld").await;
+        let decision = PolicyEngine::decide(&mut world, &child, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Permit);
+        assert_eq!(decision.permission, to_iri("http://abc.tds/policy/inherit/child/permission/1"));
+        //parent conflict strategy applies across the combined rules
+        assert_eq!(decision.conflict, Some(ConflictStrategy::prohibit));
+
+        //inherited prohibition is gated by the parent policy constraint
+        world.add_state("http://www.w3.org/ns/odrl/2/spatial", "CN");
+        assert!(PolicyEngine::eval(&mut world, &child, &req).unwrap());
+        world.add_state("http://www.w3.org/ns/odrl/2/purpose", "research");
+        let decision = PolicyEngine::decide(&mut world, &child, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Deny);
+        assert_eq!(decision.prohibition, to_iri("http://abc.tds/policy/inherit/parent/prohibition/1"));
+
+        //inherited permission
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/display"));
+        let decision = PolicyEngine::decide(&mut world, &child, &req).unwrap();
+        assert_eq!(decision.permission, to_iri("http://abc.tds/policy/inherit/parent/permission/1"));
+        assert_eq!(decision.effect, DecisionEffect::Permit);
+
+        load_policy("src/data/inherit/cycle_a.jsonld").await;
+        let cycle = load_policy("src/data/inherit/cycle_b.jsonld").await;
+        let decision = PolicyEngine::decide(&mut world, &cycle, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Indeterminate);
+        assert!(decision.reason.unwrap().contains("inherits from itself"));
+    }
+
+    #[tokio::test]
+    pub async fn test_conflict_analysis() {
+        use crate::model::conflict_analyzer::ConflictAnalyzer;
+
+        //prohibit strategy resolves the overlap at evaluation time
+        let policy = load_policy("src/data/decision.jsonld").await;
+        let conflicts = ConflictAnalyzer::analyze(&policy);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].permission, to_iri("http://abc.tds/policy/decision/1/permission/1"));
+        assert_eq!(conflicts[0].prohibition, to_iri("http://abc.tds/policy/decision/1/prohibition/1"));
+
+        //invalid strategy voids the policy at load time
+        let doc = OdrlLoader::load_file("http://www.w3.org/ns/odrl/2".to_string(), "src/data/conflict_invalid.jsonld".to_string());
+        let policy = OdrlLoader::parse(doc.await.unwrap()).await.unwrap();
+        let err = OdrlLoader::compile(&policy).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<OdrlError>(), Some(OdrlError::ConflictingRules(_, 1))));
+
+        //a conflict with an inherited prohibition voids the child as well
+        load_policy("src/data/inherit/conflict_parent.jsonld").await;
+        let doc = OdrlLoader::load_file("http://www.w3.org/ns/odrl/2".to_string(), "src/data/inherit/conflict_child.jsonld".to_string());
+        let policy = OdrlLoader::parse(doc.await.unwrap()).await.unwrap();
+        let err = OdrlLoader::compile(&policy).await.unwrap_err();
+        match err.downcast_ref::<OdrlError>() {
+            Some(OdrlError::ConflictingRules(uid, 1)) => assert_eq!(uid, "http://abc.tds/policy/inherit/conflict/child"),
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    pub async fn test_profile() {
+        use std::sync::Arc;
+        use crate::model::action_registry::ActionDefinition;
+        use crate::model::decision::DecisionEffect;
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+        use crate::model::data_type::DataType;
+        use crate::model::profile::Profile;
+        use crate::reference::types::OperandValue;
+
+        let doc = OdrlLoader::load_file("http://www.w3.org/ns/odrl/2".to_string(), "src/data/profile.jsonld".to_string());
+        let json = OdrlLoader::parse(doc.await.unwrap()).await.unwrap();
+
+        //a policy declaring an unknown profile is rejected
+        let err = OdrlLoader::compile(&json).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<OdrlError>(), Some(OdrlError::UnknownProfile(_))));
+
+        let industry = || Profile::new("https://example.com/profile/industry")
+            .with_action("https://example.com/profile/industry/train", ActionDefinition::builder().includedIn(vec![ActionType::Derive]).build())
+            .with_left_operand("https://example.com/profile/industry/region", Arc::new(|world: &StateWorld| {
+                let region = world.get_state("https://example.com/profile/industry/country").map(|country| match country {
+                    "de" | "fr" => "eu",
+                    _ => "other",
+                });
+                Ok(OperandValue::builder().sval(region.map(str::to_string)).build())
+            }))
+            .with_operator("https://example.com/profile/industry/within", Arc::new(|_, left: &OperandValue, right: &OperandValue| {
+                Ok(left.get_sval().is_some() && left.get_sval() == right.get_sval())
+            }));
+        GLOBAL_PROFILE_REGISTRY.register(industry()).unwrap();
+        let policy = OdrlLoader::compile(&json).await.unwrap();
+
+        let mut world = StateWorld::new("http://abc.tds/policy/profile/1");
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("https://example.com/profile/industry/train"));
+        req.set_assignee(to_iri("https://aa/cc"));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+
+        world.add_state("https://example.com/profile/industry/country", "us");
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &req).unwrap().effect, DecisionEffect::Deny);
+        world.add_state("https://example.com/profile/industry/country", "de");
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &req).unwrap().effect, DecisionEffect::Permit);
+
+        //the profile action only matches itself, not its parents
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/derive"));
+        assert_eq!(PolicyEngine::decide(&mut world, &policy, &req).unwrap().effect, DecisionEffect::NotApplicable);
+
+        //a failing profile operator is indeterminate, strict mode denies with its reason
+        req.set_action(to_iri("https://example.com/profile/industry/train"));
+        let failing = industry().with_operator("https://example.com/profile/industry/within", Arc::new(|_, _: &OperandValue, _: &OperandValue| {
+            Err(anyhow!("no region data"))
+        }));
+        GLOBAL_PROFILE_REGISTRY.register(failing).unwrap();
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Deny);
+        assert!(decision.indeterminate[0].reason.contains("no region data"));
+        world.set_strict(true);
+        let decision = PolicyEngine::decide(&mut world, &policy, &req).unwrap();
+        assert_eq!(decision.effect, DecisionEffect::Deny);
+        let reason = decision.reason.unwrap();
+        assert!(reason.starts_with("constraint indeterminate: ") && reason.contains("no region data"));
+        let unknown = IriBuf::new("https://example.com/profile/industry/unknown".to_string()).unwrap();
+        let err = ConstraintOperator::custom(&unknown, &DataType::default(), &OperandValue::default(), &OperandValue::default()).unwrap_err();
+        assert!(err.to_string().contains("is not registered"));
+
+        //validation rules of the profile run at load time
+        let strict = industry()
+            .with_validator(Arc::new(|policy: &Policy| match policy.get_conflict() {
+                Some(_) => Ok(()),
+                None => Err(OdrlError::ProfileViolation("https://example.com/profile/industry".to_string(), "conflict strategy required".to_string())),
+            }));
+        GLOBAL_PROFILE_REGISTRY.register(strict).unwrap();
+        let err = OdrlLoader::compile(&json).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<OdrlError>(), Some(OdrlError::ProfileViolation(_, _))));
+    }
+
+    #[tokio::test]
+    pub async fn test_unknown_operator() {
+        register_profile();
+        let doc = OdrlLoader::load_file("http://www.w3.org/ns/odrl/2".to_string(), "src/data/unknown_operator.jsonld".to_string());
+        let json = OdrlLoader::parse(doc.await.unwrap()).await.unwrap();
+
+        //an operator no registered profile defines rejects the policy
+        let err = OdrlLoader::compile(&json).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<OdrlError>(), Some(OdrlError::UnknownOperator(iri)) if iri == "http://example.com/profile/near"));
+    }
+
+    #[tokio::test]
+    pub async fn test_compile_policy_types() {
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        let fixtures = vec![
+            ("agreement", "Agreement"),
+            ("offer", "Offer"),
+            ("set", "Set"),
+            ("privacy", "Privacy"),
+            ("request", "Request"),
+            ("assertion", "Assert"),
+            ("ticket", "Ticket"),
+            ("untyped", "Set"),
+        ];
+
+        for (name, variant) in fixtures {
+            let policy = load_policy(format!("src/data/policies/{}.jsonld", name).as_str()).await;
+            let compiled = match &policy {
+                PolicyUnion::Agreement(_) => "Agreement",
+                PolicyUnion::Offer(_) => "Offer",
+                PolicyUnion::Set(_) => "Set",
+                PolicyUnion::Privacy(_) => "Privacy",
+                PolicyUnion::Request(_) => "Request",
+                PolicyUnion::Assert(_) => "Assert",
+                PolicyUnion::Ticket(_) => "Ticket",
+            };
+            assert_eq!(compiled, variant);
+
+            let uid = format!("http://abc.tds/policy/{}/1", name);
+            assert_eq!(PolicyEngine::find_world_key(&policy), to_iri(uid.as_str()));
+            assert!(GLOBAL_WORLD_CACHE.find_world(uid.as_str()).is_some());
+
+            let mut world = StateWorld::new(uid.as_str());
+            let mut req = OdrlRequest::default();
+            req.set_action(to_iri("http://www.w3.org/ns/odrl/2/use"));
+            req.set_assignee(to_iri("https://aa/cc"));
+            req.set_assigner(to_iri("https://aa/bb"));
+            req.set_target(to_iri("https://example.com/media/video1.mp4"));
+            assert!(PolicyEngine::eval(&mut world, &policy, &req).unwrap(), "{} not permitted", name);
+        }
+    }
+
+    #[tokio::test]
+    pub async fn test_normalize() {
+        use crate::model::policy::{OdrlRequest, PolicyEngine};
+
+        register_profile();
+        let doc = OdrlLoader::load_file("http://www.w3.org/ns/odrl/2".to_string(), "src/data/normalize.jsonld".to_string());
+        let expanded = doc.await.unwrap();
+        let json = OdrlLoader::parse(expanded).await.unwrap();
+
+        //2 permissions atomize into 6 rules, 2 actions x 2 targets and 1 action x 2 assignees
+        let permissions = match json.get_permission().clone().unwrap() {
+            JsonLdOptionArray::Multiple(permissions) => permissions,
+            JsonLdOptionArray::Single(_) => panic!("permissions are not normalized"),
+        };
+        assert_eq!(permissions.len(), 6);
+        for permission in &permissions {
+            assert!(matches!(permission.get_action(), Some(JsonLdOptionArray::Single(_))));
+            assert!(matches!(permission.get_target(), Some(JsonLdOptionArray::Single(_))));
+            assert!(matches!(permission.get_assigner(), Some(JsonLdOptionArray::Single(_))));
+            assert!(matches!(permission.get_assignee(), Some(JsonLdOptionArray::Single(_))));
+            //the source rules have no uid, so no split uid is derived for them
+            assert!(permission.get_uid().is_none());
+        }
+
+        //prohibition and obligation inherit the policy level assigner, assignee and target
+        let prohibition = match json.get_prohibition().clone().unwrap() {
+            JsonLdOptionArray::Multiple(prohibitions) => prohibitions[0].clone(),
+            JsonLdOptionArray::Single(prohibition) => prohibition,
+        };
+        let assigner = first_value(prohibition.get_assigner().as_ref().unwrap()).unwrap();
+        assert_eq!(assigner.get_uid().clone().unwrap(), "https://aa/bb");
+        let obligation = match json.get_obligation().clone().unwrap() {
+            JsonLdOptionArray::Multiple(obligations) => obligations[0].clone(),
+            JsonLdOptionArray::Single(obligation) => obligation,
+        };
+        let assignee = first_value(obligation.get_assignee().as_ref().unwrap()).unwrap();
+        assert_eq!(assignee.get_uid().clone().unwrap(), "https://aa/cc");
+        assert!(obligation.get_target().is_some());
+
+        //policy level assignee matches permissions without their own assignee
+        let policy = OdrlLoader::compile(&json).await.unwrap();
+        let mut world = StateWorld::new("http://abc.tds/policy/normalize/1");
+        let mut req = OdrlRequest::default();
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/display"));
+        req.set_assignee(to_iri("https://aa/cc"));
+        req.set_assigner(to_iri("https://aa/bb"));
+        req.set_target(to_iri("https://example.com/media/video2.mp4"));
+        assert!(PolicyEngine::eval(&mut world, &policy, &req).unwrap());
+
+        req.set_action(to_iri("http://www.w3.org/ns/odrl/2/print"));
+        assert!(!PolicyEngine::eval(&mut world, &policy, &req).unwrap());
+        req.set_assignee(to_iri("https://aa/ee"));
+        req.set_target(to_iri("https://example.com/media/video1.mp4"));
+        assert!(PolicyEngine::eval(&mut world, &policy, &req).unwrap());
+    }
+
+    #[tokio::test]
+    pub async fn test_normalize_nested() {
+        register_profile();
+        let doc = OdrlLoader::load_file("http://www.w3.org/ns/odrl/2".to_string(), "src/data/normalize_nested.jsonld".to_string());
+        let expanded = doc.await.unwrap();
+        let json = OdrlLoader::parse(expanded).await.unwrap();
+
+        //duties, remedies and consequences take the policy level properties too
+        let permissions = match json.get_permission().clone().unwrap() {
+            JsonLdOptionArray::Multiple(permissions) => permissions,
+            JsonLdOptionArray::Single(_) => panic!("permissions are not normalized"),
+        };
+        let duties = match permissions[0].get_duty().clone().unwrap() {
+            JsonLdOptionArray::Multiple(duties) => duties,
+            JsonLdOptionArray::Single(_) => panic!("duties are not normalized"),
+        };
+        assert_eq!(duties.len(), 2);
+        for duty in &duties {
+            let assignee = first_value(duty.get_assignee().as_ref().unwrap()).unwrap();
+            assert_eq!(assignee.get_uid().clone().unwrap(), "https://aa/cc");
+            assert!(duty.get_target().is_some());
+        }
+        let prohibitions = match json.get_prohibition().clone().unwrap() {
+            JsonLdOptionArray::Multiple(prohibitions) => prohibitions,
+            JsonLdOptionArray::Single(_) => panic!("prohibitions are not normalized"),
+        };
+        let remedy = first_value(prohibitions[0].get_remedy().as_ref().unwrap()).unwrap();
+        assert!(remedy.get_assigner().is_some());
+        assert!(remedy.get_target().is_some());
+        let obligation = match json.get_obligation().clone().unwrap() {
+            JsonLdOptionArray::Multiple(obligations) => obligations[0].clone(),
+            JsonLdOptionArray::Single(obligation) => obligation,
+        };
+        let consequence = first_value(obligation.get_consequence().as_ref().unwrap()).unwrap();
+        assert!(consequence.get_assignee().is_some());
+        assert!(consequence.get_target().is_some());
+
+        //split rules get distinct uids derived from the source rule, a rule which is not split keeps its own
+        let uids = |uids: Vec<Option<String>>| uids.into_iter().map(|uid| uid.unwrap()).collect::<Vec<_>>();
+        assert_eq!(uids(permissions.iter().map(|p| p.get_uid().clone()).collect()), vec![
+            "http://abc.tds/policy/normalize/2/permission/1#1",
+            "http://abc.tds/policy/normalize/2/permission/1#2",
+        ]);
+        assert_eq!(uids(duties.iter().map(|d| d.get_uid().clone()).collect()), vec![
+            "http://abc.tds/policy/normalize/2/duty/1#1",
+            "http://abc.tds/policy/normalize/2/duty/1#2",
+        ]);
+        assert_eq!(remedy.get_uid().clone().unwrap(), "http://abc.tds/policy/normalize/2/remedy/1");
+        assert_eq!(obligation.get_uid().clone().unwrap(), "http://abc.tds/policy/normalize/2/obligation/1");
+
+        let policy = OdrlLoader::compile(&json).await.unwrap();
+        let policy = PolicyEngine::find_policy(&policy);
+        assert!(policy.find_prohibition("http://abc.tds/policy/normalize/2/prohibition/1#1").is_some());
+        assert!(policy.find_prohibition("http://abc.tds/policy/normalize/2/prohibition/1#2").is_some());
+        assert!(policy.find_duty("http://abc.tds/policy/normalize/2/duty/1#2").is_some());
+        assert_eq!(split_uid("http://abc.tds/rule#1", 2), "http://abc.tds/rule#1-2");
+    }
 }
\ No newline at end of file
@@ -15,11 +15,13 @@
 #![allow(dead_code)]
 #![allow(non_snake_case)]
 
+use iref::IriBuf;
 use lombok::{Builder, Getter, GetterMut, Setter};
-use crate::model::constraint::{ConstraintUnion};
+use crate::model::action_registry::GLOBAL_ACTION_REGISTRY;
+use crate::model::constraint::{ConstraintInference, ConstraintUnion};
+use crate::model::decision::RuleKind;
 use crate::model::metadata::Metadata;
 use crate::model::stateworld::StateWorld;
-use crate::traits::definions::LogicEval;
 
 #[derive(Debug,Clone,PartialEq)]
 pub enum ActionType {
@@ -123,9 +125,68 @@ pub enum ActionType {
     Watermark,
     //top level action
     Use,
-    Transfer
+    Transfer,
+    //action outside of the common vocabulary, declared by a profile
+    Custom(IriBuf)
 }
 
+const ODRL_NAMESPACE: &str = "http://www.w3.org/ns/odrl/2/";
+
+//Every action of the common vocabulary, in the order of the enum
+const BUILTIN_ACTIONS: &[ActionType] = &[
+    ActionType::AcceptTracking,
+    ActionType::Aggregate,
+    ActionType::Annotate,
+    ActionType::Anonymize,
+    ActionType::Archive,
+    ActionType::Attribute,
+    ActionType::Attribution,
+    ActionType::CommericalUse,
+    ActionType::Compensate,
+    ActionType::ConcurrentUse,
+    ActionType::Delete,
+    ActionType::Derive,
+    ActionType::Derivative,
+    ActionType::Digitize,
+    ActionType::Display,
+    ActionType::Distribute,
+    ActionType::Distribution,
+    ActionType::EnsureExclusivity,
+    ActionType::Execute,
+    ActionType::Extract,
+    ActionType::Give,
+    ActionType::GrantUse,
+    ActionType::Include,
+    ActionType::Index,
+    ActionType::Inform,
+    ActionType::Install,
+    ActionType::Modify,
+    ActionType::Move,
+    ActionType::NextPolicy,
+    ActionType::Notice,
+    ActionType::ObtainConsent,
+    ActionType::Play,
+    ActionType::Present,
+    ActionType::Print,
+    ActionType::Read,
+    ActionType::Reproduce,
+    ActionType::Reproduction,
+    ActionType::ReviewPolicy,
+    ActionType::Sell,
+    ActionType::ShareAlike,
+    ActionType::Sharing,
+    ActionType::SourceCode,
+    ActionType::Stream,
+    ActionType::Synchronize,
+    ActionType::TextToSpeech,
+    ActionType::Transform,
+    ActionType::Translate,
+    ActionType::Uninstall,
+    ActionType::Watermark,
+    ActionType::Use,
+    ActionType::Transfer,
+];
+
 impl Default for ActionType {
     fn default() -> Self {
         ActionType::AcceptTracking
@@ -148,79 +209,167 @@ impl Action {
     }
 }
 
+impl ActionType {
+    pub fn to_iri(&self) -> String {
+        match self {
+            ActionType::AcceptTracking => "http://www.w3.org/ns/odrl/2/acceptTracking",
+            ActionType::Aggregate => "http://www.w3.org/ns/odrl/2/aggregate",
+            ActionType::Annotate => "http://www.w3.org/ns/odrl/2/annotate",
+            ActionType::Anonymize => "http://www.w3.org/ns/odrl/2/anonymize",
+            ActionType::Archive => "http://www.w3.org/ns/odrl/2/archive",
+            ActionType::Attribute => "http://www.w3.org/ns/odrl/2/attribute",
+            ActionType::Attribution => "http://creativecommons.org/ns#Attribution",
+            ActionType::CommericalUse => "http://creativecommons.org/ns#CommericalUse",
+            ActionType::Compensate => "http://www.w3.org/ns/odrl/2/compensate",
+            ActionType::ConcurrentUse => "http://www.w3.org/ns/odrl/2/concurrentUse",
+            ActionType::Delete => "http://www.w3.org/ns/odrl/2/delete",
+            ActionType::Derive => "http://www.w3.org/ns/odrl/2/derive",
+            ActionType::Derivative => "http://creativecommons.org/ns#DerivativeWorks",
+            ActionType::Digitize => "http://www.w3.org/ns/odrl/2/digitize",
+            ActionType::Display => "http://www.w3.org/ns/odrl/2/display",
+            ActionType::Distribute => "http://www.w3.org/ns/odrl/2/distribute",
+            ActionType::Distribution => "http://creativecommons.org/ns#Distribution",
+            ActionType::EnsureExclusivity => "http://www.w3.org/ns/odrl/2/ensureExclusivity",
+            ActionType::Execute => "http://www.w3.org/ns/odrl/2/execute",
+            ActionType::Extract => "http://www.w3.org/ns/odrl/2/extract",
+            ActionType::Give => "http://www.w3.org/ns/odrl/2/give",
+            ActionType::GrantUse => "http://www.w3.org/ns/odrl/2/grantUse",
+            ActionType::Include => "http://www.w3.org/ns/odrl/2/include",
+            ActionType::Index => "http://www.w3.org/ns/odrl/2/index",
+            ActionType::Inform => "http://www.w3.org/ns/odrl/2/inform",
+            ActionType::Install => "http://www.w3.org/ns/odrl/2/install",
+            ActionType::Modify => "http://www.w3.org/ns/odrl/2/modify",
+            ActionType::Move => "http://www.w3.org/ns/odrl/2/move",
+            ActionType::NextPolicy => "http://www.w3.org/ns/odrl/2/nextPolicy",
+            ActionType::Notice => "http://creativecommons.org/ns#Notice",
+            ActionType::ObtainConsent => "http://www.w3.org/ns/odrl/2/obtainConsent",
+            ActionType::Play => "http://www.w3.org/ns/odrl/2/play",
+            ActionType::Present => "http://www.w3.org/ns/odrl/2/present",
+            ActionType::Print => "http://www.w3.org/ns/odrl/2/print",
+            ActionType::Read => "http://www.w3.org/ns/odrl/2/read",
+            ActionType::Reproduce => "http://www.w3.org/ns/odrl/2/reproduce",
+            ActionType::Reproduction => "http://creativecommons.org/ns#Reproduction",
+            ActionType::ReviewPolicy => "http://www.w3.org/ns/odrl/2/reviewPolicy",
+            ActionType::Sell => "http://www.w3.org/ns/odrl/2/sell",
+            ActionType::ShareAlike => "http://creativecommons.org/ns#ShareAlike",
+            ActionType::Sharing => "http://creativecommons.org/ns#Sharing",
+            ActionType::SourceCode => "http://creativecommons.org/ns#SourceCode",
+            ActionType::Stream => "http://www.w3.org/ns/odrl/2/stream",
+            ActionType::Synchronize => "http://www.w3.org/ns/odrl/2/synchronize",
+            ActionType::TextToSpeech => "http://www.w3.org/ns/odrl/2/textToSpeech",
+            ActionType::Transform => "http://www.w3.org/ns/odrl/2/transform",
+            ActionType::Translate => "http://www.w3.org/ns/odrl/2/translate",
+            ActionType::Uninstall => "http://www.w3.org/ns/odrl/2/uninstall",
+            ActionType::Watermark => "http://www.w3.org/ns/odrl/2/watermark",
+            ActionType::Use => "http://www.w3.org/ns/odrl/2/use",
+            ActionType::Transfer => "http://www.w3.org/ns/odrl/2/transfer",
+            ActionType::Custom(iri) => iri.as_str(),
+        }.to_string()
+    }
+
+    //Local name of the action iri, ex: 'acceptTracking'
+    pub fn name(&self) -> String {
+        let iri = self.to_iri();
+        match iri.rfind(['/', '#']) {
+            Some(index) => iri[index + 1..].to_string(),
+            None => iri,
+        }
+    }
+
+    //Parents of the action, from the common vocabulary or the action registry, use and transfer are the roots
+    pub fn included_in(&self) -> Vec<ActionType> {
+        match self {
+            ActionType::Use | ActionType::Transfer => vec![],
+            //Creative Commons actions are requirements, not part of the hierarchy
+            ActionType::Attribution
+            | ActionType::CommericalUse
+            | ActionType::Derivative
+            | ActionType::Distribution
+            | ActionType::Notice
+            | ActionType::Reproduction
+            | ActionType::ShareAlike
+            | ActionType::Sharing
+            | ActionType::SourceCode => vec![],
+            ActionType::Give | ActionType::Sell => vec![ActionType::Transfer],
+            ActionType::Play | ActionType::Display | ActionType::Print => vec![ActionType::Present],
+            ActionType::Extract => vec![ActionType::Reproduce],
+            ActionType::TextToSpeech => vec![ActionType::Read],
+            ActionType::Custom(iri) => match GLOBAL_ACTION_REGISTRY.get_definition(iri.as_str()) {
+                //a profile action without parents is a kind of use
+                Some(definition) if definition.includedIn.is_empty() => vec![ActionType::Use],
+                Some(definition) => definition.includedIn,
+                None => vec![],
+            },
+            _ => vec![ActionType::Use],
+        }
+    }
+
+    //Actions implied by exercising this one
+    pub fn implies(&self) -> Vec<ActionType> {
+        match self {
+            ActionType::Move => vec![ActionType::Delete],
+            ActionType::Custom(iri) => GLOBAL_ACTION_REGISTRY.get_definition(iri.as_str())
+                .map(|definition| definition.implies)
+                .unwrap_or_default(),
+            _ => vec![],
+        }
+    }
+
+    //Whether the action is the other one or transitively included in it
+    pub fn is_included_in(&self, other: &ActionType) -> bool {
+        let mut visited: Vec<ActionType> = vec![];
+        let mut pending = vec![self.clone()];
+        while let Some(action) = pending.pop() {
+            if action == *other {
+                return true;
+            }
+            if visited.contains(&action) {
+                //registered actions may declare cycles
+                continue;
+            }
+            pending.extend(action.included_in());
+            visited.push(action);
+        }
+        false
+    }
+}
+
 impl TryFrom<&str> for ActionType {
     type Error = String;
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let value = value.to_lowercase();
-        //find the last /
-        let index = value.rfind("/").unwrap();
-        let value = value.split_at(index+1).1.to_string();
+        //full iris must match exactly
+        if let Some(action) = BUILTIN_ACTIONS.iter().find(|action| action.to_iri() == value) {
+            return Ok(action.clone());
+        }
 
-        match value.as_str() {
-            "acceptTracking" => Ok(ActionType::AcceptTracking),
-            "aggregate" => Ok(ActionType::Aggregate),
-            "annotate" => Ok(ActionType::Annotate),
-            "anonymize" => Ok(ActionType::Anonymize),
-            "archive" => Ok(ActionType::Archive),
-            "attribute" => Ok(ActionType::Attribute),
-            "attribution" => Ok(ActionType::Attribution),
-            "commericalUse" => Ok(ActionType::CommericalUse),
-            "compensate" => Ok(ActionType::Compensate),
-            "concurrentUse" => Ok(ActionType::ConcurrentUse),
-            "delete" => Ok(ActionType::Delete),
-            "derive" => Ok(ActionType::Derive),
-            "derivative" => Ok(ActionType::Derivative),
-            "digitize" => Ok(ActionType::Digitize),
-            "display" => Ok(ActionType::Display),
-            "distribute" => Ok(ActionType::Distribute),
-            "distribution" => Ok(ActionType::Distribution),
-            "ensureExclusivity" => Ok(ActionType::EnsureExclusivity),
-            "execute" => Ok(ActionType::Execute),
-            "extract" => Ok(ActionType::Extract),
-            "give" => Ok(ActionType::Give),
-            "grantUse" => Ok(ActionType::GrantUse),
-            "include" => Ok(ActionType::Include),
-            "index" => Ok(ActionType::Index),
-            "inform" => Ok(ActionType::Inform),
-            "install" => Ok(ActionType::Install),
-            "modify" => Ok(ActionType::Modify),
-            "move" => Ok(ActionType::Move),
-            "nextPolicy" => Ok(ActionType::NextPolicy),
-            "notice" => Ok(ActionType::Notice),
-            "obtainConsent" => Ok(ActionType::ObtainConsent),
-            "play" => Ok(ActionType::Play),
-            "present" => Ok(ActionType::Present),
-            "print" => Ok(ActionType::Print),
-            "read" => Ok(ActionType::Read),
-            "reproduce" => Ok(ActionType::Reproduce),
-            "reproduction" => Ok(ActionType::Reproduction),
-            "reviewPolicy" => Ok(ActionType::ReviewPolicy),
-            "sell" => Ok(ActionType::Sell),
-            "shareAlike" => Ok(ActionType::ShareAlike),
-            "sharing" => Ok(ActionType::Sharing),
-            "sourceCode" => Ok(ActionType::SourceCode),
-            "stream" => Ok(ActionType::Stream),
-            "synchronize" => Ok(ActionType::Synchronize),
-            "textToSpeech" => Ok(ActionType::TextToSpeech),
-            "transform" => Ok(ActionType::Transform),
-            "translate" => Ok(ActionType::Translate),
-            "uninstall" => Ok(ActionType::Uninstall),
-            "watermark" => Ok(ActionType::Watermark),
-            "use" => Ok(ActionType::Use),
-            "transfer" => Ok(ActionType::Transfer),
-            _ => Err(format!("Invalid action type: {}", value))
+        //bare names, ex: 'play' or 'odrl:acceptTracking'
+        let name = value.strip_prefix("odrl:").unwrap_or(value);
+        if !name.contains([':', '/', '#']) {
+            return BUILTIN_ACTIONS.iter()
+                .find(|action| action.name().eq_ignore_ascii_case(name))
+                .cloned()
+                .ok_or(format!("Invalid action type: {}", value));
         }
-    } 
+
+        //the odrl namespace is closed, anything else is a custom action
+        if value.starts_with(ODRL_NAMESPACE) {
+            return Err(format!("Invalid action type: {}", value));
+        }
+        IriBuf::new(value.to_string())
+            .map(ActionType::Custom)
+            .map_err(|_| format!("Invalid action type: {}", value))
+    }
 }
 
 #[derive(Debug,Default,Clone)]
 pub struct ActionInferencer;
 impl ActionInferencer {
-   pub fn infer(world: &mut StateWorld, action: Action,candidate: Action) -> Result<bool, anyhow::Error> {
+   pub fn infer(world: &mut StateWorld, action: Action,candidate: Action, kind: &RuleKind) -> Result<bool, anyhow::Error> {
        let mut permitted = false;
 
 
-       if action.actionType == candidate.actionType {
+       if candidate.actionType.is_included_in(&action.actionType) {
+           //the vocabulary hierarchy, ex: 'read' is included in 'use' transitively
            permitted = true;
        } else {
            /* check includedIn
@@ -229,25 +378,20 @@ impl ActionInferencer {
             */
            if let Some(includedIn) = candidate.get_includedIn() {
                for included in includedIn {
-                   if included.actionType == action.actionType  {
+                   if included.actionType.is_included_in(&action.actionType)  {
                        permitted = true;
                        break;
                    }
                }
            }
 
-           if !permitted {
+           if !permitted && *kind == RuleKind::Prohibition {
                /* check implies
-                * if candidate is in implies of the action, then it is permitted
+                * exercising the candidate exercises the actions it implies, a prohibition on one of them covers the candidate
+                * ex: 'move' implies 'delete', a prohibition on 'delete' covers 'move', never the other way round
                 */
-               if let Some(impls) = action.get_implies() {
-                   for impls in impls {
-                       if impls.actionType == candidate.actionType {
-                           permitted = true;
-                           break;
-                       }
-                   }
-               }
+               permitted = candidate.actionType.implies().iter()
+                   .any(|implied| implied.is_included_in(&action.actionType));
            }
        }
 
@@ -255,33 +399,10 @@ impl ActionInferencer {
            return Ok(false);
        }
 
-       //check refinement
+       //check refinement, an indeterminate refinement does not match
        let mut refined = true;
        if let Some(refinement) = action.get_refinements() {
-           for refinement in refinement {
-               match refinement {
-                   ConstraintUnion::Constraint(constraint) => {
-                       let result = constraint.eval(world);
-                       match result {
-                           Ok(false) => {
-                               refined = false;
-                           },
-                           _ => {
-                           }
-                       }
-                   }
-                   ConstraintUnion::LogicConstraint(ac) => {
-                       let result = ac.eval(world);
-                       match result {
-                           Ok(false) => {
-                               refined = false;
-                           },
-                           _ => {
-                           }
-                       }
-                   }
-               }
-           }
+           refined = ConstraintInference::infer(world, refinement)?;
        }
 
        //action match already, just check refinement
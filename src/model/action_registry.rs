@@ -0,0 +1,118 @@
+// Copyright 2024 meduo <gao.brian@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+
+use std::sync::Arc;
+use dashmap::DashMap;
+use lombok::{Builder, Getter};
+use once_cell::sync::Lazy;
+use crate::model::action::ActionType;
+use crate::model::error::OdrlError;
+
+//Relationships of an action declared by a profile
+#[derive(Debug,Default,Builder,Getter,Clone)]
+pub struct ActionDefinition {
+    //parents of the action, use when empty
+    pub includedIn: Vec<ActionType>,
+    pub implies: Vec<ActionType>,
+}
+
+//Actions declared by profiles, keyed by action iri
+#[derive(Builder,Clone,Getter)]
+pub struct ActionRegistry {
+    actions: DashMap<String, ActionDefinition>,
+}
+
+impl ActionRegistry {
+    //Declare a custom action, the common vocabulary can not be redefined
+    pub fn register(&self, iri: &str, definition: ActionDefinition) -> Result<(), OdrlError> {
+        match ActionType::try_from(iri) {
+            Ok(ActionType::Custom(iri)) => {
+                self.actions.insert(iri.as_str().to_string(), definition);
+                Ok(())
+            }
+            _ => Err(OdrlError::InvalidCustomAction(iri.to_string())),
+        }
+    }
+
+    pub fn get_definition(&self, iri: &str) -> Option<ActionDefinition> {
+        self.actions.get(iri).map(|definition| definition.value().clone())
+    }
+
+    pub fn is_registered(&self, iri: &str) -> bool {
+        self.actions.contains_key(iri)
+    }
+
+    pub fn unregister(&self, iri: &str) {
+        self.actions.remove(iri);
+    }
+
+    pub fn clear_actions(&self) {
+        self.actions.clear();
+    }
+}
+
+pub static GLOBAL_ACTION_REGISTRY: Lazy<Arc<ActionRegistry>> = Lazy::new(|| {
+    Arc::new(
+        ActionRegistry {
+            actions: DashMap::new(),
+    })
+});
+
+#[cfg(test)]
+mod tests {
+    use crate::model::action::ActionType;
+    use crate::model::action_registry::{ActionDefinition, GLOBAL_ACTION_REGISTRY};
+
+    #[test]
+    fn test_parse_action() {
+        assert_eq!(ActionType::try_from("acceptTracking").unwrap(), ActionType::AcceptTracking);
+        assert_eq!(ActionType::try_from("odrl:textToSpeech").unwrap(), ActionType::TextToSpeech);
+        assert_eq!(ActionType::try_from("http://www.w3.org/ns/odrl/2/concurrentUse").unwrap(), ActionType::ConcurrentUse);
+        assert_eq!(ActionType::try_from("http://creativecommons.org/ns#Attribution").unwrap(), ActionType::Attribution);
+
+        //iris match exactly, unknown odrl actions are rejected
+        assert!(ActionType::try_from("http://www.w3.org/ns/odrl/2/Play").is_err());
+        assert!(ActionType::try_from("http://www.w3.org/ns/odrl/2/plya").is_err());
+        assert!(ActionType::try_from("plya").is_err());
+
+        let custom = ActionType::try_from("https://example.com/profile/train").unwrap();
+        assert!(matches!(&custom, ActionType::Custom(iri) if iri.as_str() == "https://example.com/profile/train"));
+        assert_eq!(custom.to_iri(), "https://example.com/profile/train");
+    }
+
+    #[test]
+    fn test_register_action() {
+        let train = ActionType::try_from("https://example.com/profile/fineTune").unwrap();
+        let evaluate = ActionType::try_from("https://example.com/profile/evaluate").unwrap();
+        assert!(!train.is_included_in(&ActionType::Use));
+
+        GLOBAL_ACTION_REGISTRY.register("https://example.com/profile/fineTune", ActionDefinition::builder()
+            .includedIn(vec![ActionType::Derive])
+            .implies(vec![evaluate.clone()])
+            .build()).unwrap();
+        GLOBAL_ACTION_REGISTRY.register("https://example.com/profile/evaluate", ActionDefinition::default()).unwrap();
+
+        assert!(train.is_included_in(&ActionType::Derive));
+        assert!(train.is_included_in(&ActionType::Use));
+        assert!(!train.is_included_in(&ActionType::Transfer));
+        assert!(evaluate.is_included_in(&ActionType::Use));
+        assert_eq!(train.implies(), vec![evaluate]);
+
+        //built-in actions are not redefined
+        assert!(GLOBAL_ACTION_REGISTRY.register("http://www.w3.org/ns/odrl/2/play", ActionDefinition::default()).is_err());
+    }
+}
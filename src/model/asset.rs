@@ -15,13 +15,14 @@
 #![allow(dead_code)]
 #![allow(non_snake_case)]
 
+use std::collections::HashMap;
 use iref::IriBuf;
 use lombok::{Builder, Getter, GetterMut, Setter};
 
 use crate::model::metadata::Metadata;
 use crate::model::stateworld::StateWorld;
-use crate::traits::definions::LogicEval;
-use super::constraint::{ConstraintUnion};
+use crate::reference::types::OperandValue;
+use super::constraint::{ConstraintInference, ConstraintUnion};
 
 //http://www.w3.org/ns/odrl/2/AssetCollection
 #[derive(Debug,Builder,Getter,GetterMut,Setter, Default, Clone)]
@@ -38,6 +39,10 @@ pub struct Asset {
     pub uid: Option<IriBuf>,
     //part of the asset collection
     pub partOf: Option<Vec<IriBuf>>,
+    //@type of the asset
+    pub ty: Option<IriBuf>,
+    //values the refinements of a matching collection are evaluated against
+    pub attributes: HashMap<String, OperandValue>,
     //refer to policy definition by IRI of Policy
     pub hasPolicy: Option<IriBuf>,
     //common metadata
@@ -77,7 +82,6 @@ impl AssetInferencer {
                 /*
                  * candidate is a sub asset of asset, basic logic is
                  * candidate.partOf.contains(asset.uid)
-                 * partOf is not supported yet
                  */
                 let partOf = candidate.get_partOf().clone();
                 if let None = partOf {
@@ -128,36 +132,11 @@ impl AssetInferencer {
                     //check refinement
                     let refinement = collection.get_refinement();
                     if let Some(refinement) = refinement {
-                        let mut refined = true;
-                        for constraint in refinement {
-                            match constraint {
-                                ConstraintUnion::Constraint(constraint) => {
-                                    let mut world = world.clone();
-                                    let ret = constraint.eval(&mut world);
-                                    match ret {
-                                        Ok(false) => {
-                                            refined = false;
-                                        }
-                                        _ => {
-
-                                        }
-                                    }
-                                }
-                                ConstraintUnion::LogicConstraint(ac) => {
-                                    let mut world = world.clone();
-                                    let ret = ac.eval(&mut world);
-                                    match ret {
-                                        Ok(false) => {
-                                            refined = false;
-                                        }
-                                        _ => {
-
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        return Ok(refined);
+                        //refinements describe the candidate, they never read the world state
+                        let mut refinement_world = world.refinement_world(candidate.get_attributes());
+                        let refined = ConstraintInference::infer(&mut refinement_world, refinement);
+                        world.add_indeterminate(refinement_world.take_indeterminate());
+                        return refined;
                     }
                     return Ok(true);
                 }
@@ -0,0 +1,76 @@
+// Copyright 2024 meduo <gao.brian@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(dead_code)]
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+
+//Source of the current instant for a world, in milliseconds since epoch
+pub trait Clock: Send + Sync {
+    fn now(&self) -> i64;
+}
+
+#[derive(Debug,Default,Clone)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        chrono::Utc::now().timestamp_millis()
+    }
+}
+
+//Always reports the same instant, to evaluate policies "as of" a point in time
+#[derive(Debug,Default,Clone)]
+pub struct FixedClock {
+    pub instant: i64,
+}
+
+impl FixedClock {
+    pub fn new(instant: i64) -> Self {
+        FixedClock { instant }
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> i64 {
+        self.instant
+    }
+}
+
+//A clock moved by hand, clones share the same instant
+#[derive(Debug,Default,Clone)]
+pub struct ManualClock {
+    instant: Arc<AtomicI64>,
+}
+
+impl ManualClock {
+    pub fn new(instant: i64) -> Self {
+        ManualClock { instant: Arc::new(AtomicI64::new(instant)) }
+    }
+
+    pub fn set(&self, instant: i64) {
+        self.instant.store(instant, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, millis: i64) {
+        self.instant.fetch_add(millis, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> i64 {
+        self.instant.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright 2024 meduo <gao.brian@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(dead_code)]
+
+use std::sync::Arc;
+use anyhow::anyhow;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use sophia::api::MownStr;
+use sophia::api::prelude::*;
+use sophia::turtle::parser::{nt, turtle};
+
+const SKOS_BROADER: &str = "http://www.w3.org/2004/02/skos/core#broader";
+const SKOS_BROADER_TRANSITIVE: &str = "http://www.w3.org/2004/02/skos/core#broaderTransitive";
+const SKOS_NARROWER: &str = "http://www.w3.org/2004/02/skos/core#narrower";
+const SKOS_NARROWER_TRANSITIVE: &str = "http://www.w3.org/2004/02/skos/core#narrowerTransitive";
+const RDFS_SUBCLASS_OF: &str = "http://www.w3.org/2000/01/rdf-schema#subClassOf";
+
+//Broader concepts of each concept, loaded from SKOS and RDFS vocabularies
+pub struct ConceptHierarchy {
+    broader: DashMap<String, Vec<String>>,
+}
+
+impl ConceptHierarchy {
+    pub fn new() -> Self {
+        ConceptHierarchy {
+            broader: DashMap::new(),
+        }
+    }
+
+    pub fn add_broader(&self, narrower: &str, broader: &str) {
+        let mut parents = self.broader.entry(narrower.to_string()).or_default();
+        if !parents.iter().any(|parent| parent == broader) {
+            parents.push(broader.to_string());
+        }
+    }
+
+    //skos:broader, skos:narrower and rdfs:subClassOf statements, other triples are ignored
+    pub fn load_turtle(&self, document: &str) -> Result<(), anyhow::Error> {
+        let mut source = turtle::parse_str(document);
+        self.load_triples(&mut source)
+    }
+
+    pub fn load_ntriples(&self, document: &str) -> Result<(), anyhow::Error> {
+        let mut source = nt::parse_str(document);
+        self.load_triples(&mut source)
+    }
+
+    //The syntax is picked from the file extension, turtle when unknown
+    pub fn load_file(&self, path: &str) -> Result<(), anyhow::Error> {
+        let document = std::fs::read_to_string(path)?;
+        if path.ends_with(".nt") {
+            self.load_ntriples(&document)
+        } else {
+            self.load_turtle(&document)
+        }
+    }
+
+    fn load_triples<S>(&self, source: &mut S) -> Result<(), anyhow::Error>
+    where
+        S: TripleSource,
+        S::Error: std::error::Error + Send + Sync + 'static,
+    {
+        source.for_each_triple(|triple| {
+            let iri = |term: Option<IriRef<MownStr>>| term.map(|iri| iri.as_str().to_string());
+            let (Some(subject), Some(predicate), Some(object)) = (iri(triple.s().iri()), iri(triple.p().iri()), iri(triple.o().iri())) else {
+                return;
+            };
+            match predicate.as_str() {
+                SKOS_BROADER | SKOS_BROADER_TRANSITIVE | RDFS_SUBCLASS_OF => self.add_broader(&subject, &object),
+                SKOS_NARROWER | SKOS_NARROWER_TRANSITIVE => self.add_broader(&object, &subject),
+                _ => {}
+            }
+        }).map_err(|e| anyhow!("invalid vocabulary: {}", e))
+    }
+
+    //Whether the concept is the ancestor itself or one of its narrower concepts, transitively
+    pub fn is_within(&self, concept: &str, ancestor: &str) -> bool {
+        let mut visited: Vec<String> = vec![];
+        let mut pending = vec![concept.to_string()];
+        while let Some(current) = pending.pop() {
+            if current == ancestor {
+                return true;
+            }
+            if visited.contains(&current) {
+                continue;
+            }
+            if let Some(parents) = self.broader.get(&current) {
+                pending.extend(parents.iter().cloned());
+            }
+            visited.push(current);
+        }
+        false
+    }
+
+    pub fn clear(&self) {
+        self.broader.clear();
+    }
+}
+
+impl Default for ConceptHierarchy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub static GLOBAL_CONCEPT_HIERARCHY: Lazy<Arc<ConceptHierarchy>> = Lazy::new(|| {
+    Arc::new(ConceptHierarchy::new())
+});
+
+#[cfg(test)]
+mod tests {
+    use crate::model::concept_hierarchy::ConceptHierarchy;
+
+    #[test]
+    fn test_concept_hierarchy() {
+        let hierarchy = ConceptHierarchy::new();
+        hierarchy.load_turtle(r#"
+            @prefix skos: <http://www.w3.org/2004/02/skos/core#> .
+            @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+            @prefix ex: <http://example.com/purpose/> .
+            ex:MedicalResearch skos:broader ex:Research .
+            ex:Research skos:narrower ex:AcademicResearch .
+            ex:ClinicalTrial rdfs:subClassOf ex:MedicalResearch .
+            ex:Marketing skos:prefLabel "Marketing" .
+        "#).unwrap();
+        hierarchy.load_ntriples("<http://example.com/purpose/Research> <http://www.w3.org/2004/02/skos/core#broader> <http://example.com/purpose/Any> .\n").unwrap();
+
+        assert!(hierarchy.is_within("http://example.com/purpose/MedicalResearch", "http://example.com/purpose/Research"));
+        assert!(hierarchy.is_within("http://example.com/purpose/AcademicResearch", "http://example.com/purpose/Research"));
+        assert!(hierarchy.is_within("http://example.com/purpose/ClinicalTrial", "http://example.com/purpose/Any"));
+        assert!(hierarchy.is_within("http://example.com/purpose/Research", "http://example.com/purpose/Research"));
+        assert!(!hierarchy.is_within("http://example.com/purpose/Research", "http://example.com/purpose/MedicalResearch"));
+        assert!(!hierarchy.is_within("http://example.com/purpose/Marketing", "http://example.com/purpose/Research"));
+
+        //cycles in a vocabulary do not loop
+        hierarchy.add_broader("http://example.com/purpose/Any", "http://example.com/purpose/ClinicalTrial");
+        assert!(!hierarchy.is_within("http://example.com/purpose/Any", "http://example.com/purpose/Marketing"));
+
+        assert!(hierarchy.load_turtle("ex:broken skos:broader").is_err());
+    }
+}
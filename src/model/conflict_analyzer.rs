@@ -0,0 +1,195 @@
+// Copyright 2024 meduo <gao.brian@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(dead_code)]
+
+use iref::IriBuf;
+use lombok::{Builder, Getter};
+use crate::model::action::Action;
+use crate::model::asset::AssetUnion;
+use crate::model::party::PartyUnion;
+use crate::model::policy::{PolicyEngine, PolicyUnion};
+use crate::model::rule::Rule;
+
+//A permission and a prohibition which may apply to the same request
+#[derive(Debug,Default,Builder,Getter,Clone)]
+pub struct RuleConflict {
+    pub permission: Option<IriBuf>,
+    pub prohibition: Option<IriBuf>,
+}
+
+#[derive(Debug,Default,Clone)]
+pub struct ConflictAnalyzer;
+
+impl ConflictAnalyzer {
+    /*
+     * A permission and a prohibition overlap when all of these may refer to the same thing:
+     * 1. assignee and assigner, same party or related by partOf
+     * 2. action, same action, related by includedIn, or the permitted action implies the prohibited one
+     * 3. target, same asset or related by partOf
+     * Constraints are not considered, they depend on the world state at evaluation time.
+     */
+    pub fn analyze(policy: &PolicyUnion) -> Vec<RuleConflict> {
+        let policy = PolicyEngine::find_policy(policy);
+        let mut conflicts = vec![];
+        let (Some(permissions), Some(prohibitions)) = (policy.get_permission(), policy.get_prohibition()) else {
+            return conflicts;
+        };
+
+        for permission in permissions {
+            for prohibit in prohibitions {
+                if ConflictAnalyzer::rules_overlap(permission.get_duty(), prohibit.get_rule()) {
+                    conflicts.push(RuleConflict {
+                        permission: permission.get_duty().get_uid().clone(),
+                        prohibition: prohibit.get_rule().get_uid().clone(),
+                    });
+                }
+            }
+        }
+        conflicts
+    }
+
+    //a is the permission, b the prohibition
+    pub fn rules_overlap(a: &Rule, b: &Rule) -> bool {
+        ConflictAnalyzer::parties_overlap(a.get_assignee(), b.get_assignee())
+            && ConflictAnalyzer::parties_overlap(a.get_assigner(), b.get_assigner())
+            && ConflictAnalyzer::actions_overlap(a.get_action(), b.get_action())
+            && ConflictAnalyzer::targets_overlap(a.get_target(), b.get_target())
+    }
+
+    //an absent party applies to anyone
+    fn parties_overlap(a: &Option<PartyUnion>, b: &Option<PartyUnion>) -> bool {
+        let (Some(a), Some(b)) = (a, b) else {
+            return true;
+        };
+
+        match (a, b) {
+            (PartyUnion::Party(a), PartyUnion::Party(b)) => {
+                ConflictAnalyzer::related(a.get_uid(), a.get_partOf(), b.get_uid(), b.get_partOf())
+            }
+            (PartyUnion::Party(party), PartyUnion::PartyCollection(collection))
+            | (PartyUnion::PartyCollection(collection), PartyUnion::Party(party)) => {
+                ConflictAnalyzer::related(party.get_uid(), party.get_partOf(), collection.get_source(), &[])
+            }
+            (PartyUnion::PartyCollection(a), PartyUnion::PartyCollection(b)) => {
+                ConflictAnalyzer::related(a.get_source(), &[], b.get_source(), &[])
+            }
+        }
+    }
+
+    //a is the permitted action, b the prohibited one
+    fn actions_overlap(a: &Option<Action>, b: &Option<Action>) -> bool {
+        let (Some(a), Some(b)) = (a, b) else {
+            return true;
+        };
+
+        if a.actionType == b.actionType {
+            return true;
+        }
+        //a request for the narrower action matches both rules
+        let included = |from: &Action, to: &Action| {
+            from.actionType.is_included_in(&to.actionType)
+                || from.get_includedIn().iter().flatten().any(|parent| parent.actionType.is_included_in(&to.actionType))
+        };
+        //exercising the permitted action exercises what it implies, never the other way round
+        let implied = a.actionType.implies().iter().any(|implied| implied.is_included_in(&b.actionType))
+            || a.get_implies().iter().flatten().any(|implied| implied.actionType.is_included_in(&b.actionType));
+        included(a, b) || included(b, a) || implied
+    }
+
+    fn targets_overlap(a: &Option<AssetUnion>, b: &Option<AssetUnion>) -> bool {
+        let (Some(a), Some(b)) = (a, b) else {
+            return true;
+        };
+
+        let (a_uid, a_part_of) = ConflictAnalyzer::asset_ids(a);
+        let (b_uid, b_part_of) = ConflictAnalyzer::asset_ids(b);
+        ConflictAnalyzer::related(a_uid, a_part_of, b_uid, b_part_of)
+    }
+
+    fn asset_ids(asset: &AssetUnion) -> (&Option<IriBuf>, &[IriBuf]) {
+        match asset {
+            AssetUnion::Asset(asset) => (asset.get_uid(), asset.get_partOf().as_deref().unwrap_or(&[])),
+            AssetUnion::AssetCollection(collection) => (collection.get_source(), &[]),
+        }
+    }
+
+    //same identifier, or one is part of the other, an unknown identifier matches anything
+    fn related(a: &Option<IriBuf>, a_part_of: &[IriBuf], b: &Option<IriBuf>, b_part_of: &[IriBuf]) -> bool {
+        let (Some(a), Some(b)) = (a, b) else {
+            return true;
+        };
+
+        a == b || a_part_of.contains(b) || b_part_of.contains(a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iref::IriBuf;
+    use crate::model::action::{Action, ActionType};
+    use crate::model::asset::{Asset, AssetUnion};
+    use crate::model::party::{Party, PartyUnion};
+    use crate::model::rule::Rule;
+    use super::*;
+
+    fn iri(s: &str) -> Option<IriBuf> {
+        IriBuf::new(s.to_string()).ok()
+    }
+
+    fn rule(action: Action, target: Asset, assignee: &str) -> Rule {
+        Rule::builder()
+            .action(Some(action))
+            .target(Some(AssetUnion::Asset(target)))
+            .assignee(Some(PartyUnion::Party(Party::builder().uid(iri(assignee)).build())))
+            .build()
+    }
+
+    fn action(ty: ActionType) -> Action {
+        Action::builder().actionType(ty).build()
+    }
+
+    fn asset(uid: &str) -> Asset {
+        Asset::builder().uid(iri(uid)).build()
+    }
+
+    #[test]
+    fn test_rules_overlap() {
+        let video = "https://example.com/media/video1.mp4";
+        let permission = rule(action(ActionType::Use), asset(video), "https://aa/cc");
+
+        assert!(ConflictAnalyzer::rules_overlap(&permission, &rule(action(ActionType::Use), asset(video), "https://aa/cc")));
+        assert!(!ConflictAnalyzer::rules_overlap(&permission, &rule(action(ActionType::Use), asset("https://example.com/media/video2.mp4"), "https://aa/cc")));
+        assert!(!ConflictAnalyzer::rules_overlap(&permission, &rule(action(ActionType::Use), asset(video), "https://aa/dd")));
+        assert!(!ConflictAnalyzer::rules_overlap(&permission, &rule(action(ActionType::Sell), asset(video), "https://aa/cc")));
+        //print is included in use by the common vocabulary
+        assert!(ConflictAnalyzer::rules_overlap(&permission, &rule(action(ActionType::Print), asset(video), "https://aa/cc")));
+
+        //sharing is outside of the vocabulary, an explicit includedIn relates it
+        let mut sharing = action(ActionType::Sharing);
+        sharing.set_includedIn(Some(vec![action(ActionType::Distribute)]));
+        assert!(ConflictAnalyzer::rules_overlap(&permission, &rule(sharing, asset(video), "https://aa/cc")));
+
+        //move implies delete, permitting move conflicts with prohibiting delete but not the reverse
+        let moving = rule(action(ActionType::Move), asset(video), "https://aa/cc");
+        let deleting = rule(action(ActionType::Delete), asset(video), "https://aa/cc");
+        assert!(ConflictAnalyzer::rules_overlap(&moving, &deleting));
+        assert!(!ConflictAnalyzer::rules_overlap(&deleting, &moving));
+
+        //a part of the permitted target
+        let mut clip = asset("https://example.com/media/video1/clip1.mp4");
+        clip.set_partOf(Some(vec![IriBuf::new(video.to_string()).unwrap()]));
+        assert!(ConflictAnalyzer::rules_overlap(&permission, &rule(action(ActionType::Use), clip, "https://aa/cc")));
+    }
+}
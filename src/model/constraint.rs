@@ -16,18 +16,80 @@
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
 
-use std::{collections::VecDeque, sync::{Arc, Mutex}};
-
-use chrono::Duration;
 use iref::IriBuf;
 use lombok::{Builder, Getter, GetterMut, Setter};
-use crate::{config, traits::definions::WorldCallBack};
 use crate::model::constraint_operator::ConstraintLogicOperator;
 use crate::model::data_type::DataType;
+use crate::model::decision::Indeterminate;
+use crate::model::error::OdrlError;
+use crate::model::geo::SpatialInferencer;
+use crate::model::schedule::ScheduleInferencer;
 use crate::model::metadata::Metadata;
+use crate::model::rate_limit::RateLimit;
 use crate::model::stateworld::StateWorld;
+use crate::reference::types::OperandValue;
 use crate::traits::definions::LogicEval;
-use super::{constraint_left_operand::{parse_xml_duration, ConstraintLeftOperand}, constraint_operator::ConstraintOperator, constraint_right_operand::ConstraintRightOperand};
+use super::{constraint_left_operand::ConstraintLeftOperand, constraint_operator::ConstraintOperator, constraint_right_operand::ConstraintRightOperand};
+
+//Outcome of a constraint, it is indeterminate when it can not be evaluated
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    True,
+    False,
+    Indeterminate(Vec<Indeterminate>),
+}
+
+impl Verdict {
+    //Errors of the engine, like comparing mismatched units, are not a verdict and are returned as is
+    pub fn from_result(result: Result<bool, anyhow::Error>, uid: &Option<IriBuf>) -> Result<Verdict, anyhow::Error> {
+        match result {
+            Ok(true) => Ok(Verdict::True),
+            Ok(false) => Ok(Verdict::False),
+            Err(e) if e.downcast_ref::<OdrlError>().is_some() => Err(e),
+            Err(e) => Ok(Verdict::indeterminate(uid, &e.to_string())),
+        }
+    }
+
+    pub fn indeterminate(uid: &Option<IriBuf>, reason: &str) -> Verdict {
+        Verdict::Indeterminate(vec![Indeterminate::builder()
+            .constraint(uid.clone())
+            .reason(reason.to_string())
+            .build()])
+    }
+
+    //False wins over indeterminate
+    pub fn and(self, other: Verdict) -> Verdict {
+        match (self, other) {
+            (Verdict::False, _) | (_, Verdict::False) => Verdict::False,
+            (Verdict::Indeterminate(mut found), Verdict::Indeterminate(more)) => {
+                found.extend(more);
+                Verdict::Indeterminate(found)
+            }
+            (Verdict::Indeterminate(found), _) | (_, Verdict::Indeterminate(found)) => Verdict::Indeterminate(found),
+            _ => Verdict::True,
+        }
+    }
+
+    //True wins over indeterminate
+    pub fn or(self, other: Verdict) -> Verdict {
+        match (self, other) {
+            (Verdict::True, _) | (_, Verdict::True) => Verdict::True,
+            (Verdict::Indeterminate(mut found), Verdict::Indeterminate(more)) => {
+                found.extend(more);
+                Verdict::Indeterminate(found)
+            }
+            (Verdict::Indeterminate(found), _) | (_, Verdict::Indeterminate(found)) => Verdict::Indeterminate(found),
+            _ => Verdict::False,
+        }
+    }
+
+    pub fn reasons(&self) -> String {
+        match self {
+            Verdict::Indeterminate(found) => found.iter().map(|i| i.reason.clone()).collect::<Vec<_>>().join("; "),
+            _ => String::new(),
+        }
+    }
+}
 
 //Identifier:	http://www.w3.org/ns/odrl/2/Constraint
 #[derive(Debug,Builder,Getter,GetterMut,Setter, Clone)]
@@ -52,10 +114,11 @@ pub struct Constraint {
     pub rightOperand: Option<ConstraintRightOperand>,
     pub metadata: Option<Metadata>,
 
-    pub counter_sequence: Arc<Mutex<VecDeque<i64>>>,
-    pub slide_window_duration: i64,
-    pub slide_window_counter: i64,
-    pub enabled_slide_window: bool,
+    //limit of a timeWindow constraint, its counters are kept in the world
+    pub rate_limit: Option<RateLimit>,
+
+    //identity of an unnamed constraint, assigned when the policy is compiled
+    pub identity: Option<String>,
 }
 
 impl Default for Constraint {
@@ -80,84 +143,25 @@ impl Constraint {
             leftOperand: None,
             rightOperand: None,
             metadata: None,
-            counter_sequence: Arc::new(Mutex::new(VecDeque::new())),
-            slide_window_duration: 0,
-            slide_window_counter: 0,
-            enabled_slide_window: false,
-        }
-    }
-
-    pub fn set_slide_window(&mut self, slide: String) {
-        //slide format: count/duration
-        let parts = slide.split('/').collect::<Vec<&str>>();
-        if parts.len() != 2 {
-            return;
-        }
-
-        let count = parts[0].parse::<i64>();
-        if count.is_err() {
-            return;
-        }
-        let count = count.unwrap();
-
-        let duration = parse_xml_duration(parts[1]);
-        if duration.is_err() {
-            return;
-        }
-        let duration = duration.unwrap();
-
-        self.update_slide_window_duration(count, duration);
-    }
-
-    pub fn update_slide_window_duration(&mut self, count : i64, duration: Duration) {
-        self.slide_window_duration = duration.num_milliseconds();
-        self.slide_window_counter = count;
-    }
-
-    pub fn calc_slide_window(&self) -> i64 {
-        //calc left window
-        let now = chrono::Utc::now().timestamp_millis();
-        let counter = self.counter_sequence.lock();
-        if counter.is_err() {
-            return 0;
-        }
-
-        let mut counter = counter.unwrap();
-        //check if the window is expired
-        loop{
-            let first = counter.get(0);
-            if first.is_none() {
-                break;
-            }
-            if now - first.unwrap() > self.slide_window_duration {
-                counter.pop_front();
-            } else {
-                break;
-            }
+            rate_limit: None,
+            identity: None,
         }
-
-        self.slide_window_counter - counter.len() as i64
     }
 
-    pub fn add_counter(&mut self, c: i64) {
-        let counter = self.counter_sequence.lock();
-        if counter.is_err() {
-            return;
-        }
-        let mut counter = counter.unwrap();
-        counter.push_back(c);
+    //A timeWindow limit such as 10/PT1H, see RateLimit::parse
+    pub fn set_time_window(&mut self, window: &str) -> Result<(), anyhow::Error> {
+        self.rate_limit = Some(RateLimit::parse(window)?);
+        Ok(())
     }
-}
 
-impl WorldCallBack for Constraint {
-    fn on_success(&mut self, world: &mut StateWorld) -> Result<(), anyhow::Error> {
-        self.add_counter(world.now());
-        Ok(())
+    //The uid declared by the policy, the default class iri identifies nothing
+    fn declared_uid(&self) -> Option<IriBuf> {
+        self.uid.clone().filter(|uid| uid.as_str() != "http://www.w3.org/ns/odrl/2/Constraint")
     }
 
-    fn on_failure(&mut self, _world: &mut StateWorld) -> Result<(), anyhow::Error> {
-        //do nothing
-        Ok(())
+    //Key of the state the constraint keeps in the world, its declared uid or the compiled identity
+    fn state_id(&self) -> Option<String> {
+        self.declared_uid().map(|uid| uid.as_str().to_string()).or_else(|| self.identity.clone())
     }
 }
 
@@ -180,36 +184,64 @@ impl LogicEval for Constraint {
         let right = right.unwrap();
 
         if let ConstraintLeftOperand::timeWindow = left {
-            return if self.enabled_slide_window {
-                let cap = self.calc_slide_window();
-                world.add_callback(Box::new(self.clone()), true);
-                Ok(cap > 0)
-            } else {
-                world.add_callback(Box::new(self.clone()), false);
-                Ok(false)
+            let rate_limit = match &self.rate_limit {
+                Some(rate_limit) => rate_limit,
+                None => return Err(anyhow::Error::msg("No valid time window defined")),
+            };
+            //a shared key would make unrelated windows share one budget
+            let id = match self.state_id() {
+                Some(id) => id,
+                None => return Err(anyhow::Error::msg("No uid defined for timeWindow")),
+            };
+            let key = rate_limit.key(&id, world);
+            if world.rate_limit_remaining(&key, rate_limit) == 0 {
+                return Ok(false);
             }
+            world.add_rate_limit_hit(key, rate_limit.clone());
+            return Ok(true);
         }
 
-        let left_value = left.value(&mut world);
-        if left_value.is_err() {
-            if self.status.is_none() {
-               let config = config::CONFIG.lock().unwrap();
-               if config.verbose {
-                   println!("{:?}", left_value.unwrap_err().to_string());
-               }
-               return   Ok(false);
-            }
+        //a missing left operand is only comparable through the status of the constraint
+        let left_value = match left.value(&mut world) {
+            Ok(left_value) => left_value,
+            Err(_) if self.status.is_some() => OperandValue::default(),
+            Err(e) => return Err(e),
+        };
+
+        let mut right_value = right.value(&mut world)?;
+        //the unit of the constraint measures quantities, a count or a point in time is compared as is
+        let temporal = matches!(DataType::try_from(self.dataType.clone()), Ok(DataType::Date | DataType::DateTime | DataType::Time));
+        if right_value.unit.is_none() && !self.unit.is_empty() && !left.is_dimensionless() && !temporal {
+            right_value.set_unit(Some(self.unit.clone()));
         }
 
-        let left_value = left_value.unwrap();
+        //spatial operands compare by containment rather than by string
+        if matches!(left, ConstraintLeftOperand::spatial | ConstraintLeftOperand::spatialCoordinates)
+            && SpatialInferencer::applies(operator) {
+            return SpatialInferencer::eval(operator, &left_value, &right_value);
+        }
+
+        //schedules compare the clock against their recurring windows
+        if ScheduleInferencer::applies(left) {
+            return ScheduleInferencer::eval(operator, &left_value, &right_value);
+        }
 
-        let right_value = right.value(&mut world);
-        if right_value.is_err() {
-           return   Ok(false);
+        //typed operands and quantities compare by value, a unit mismatch is an error rather than false
+        if operator.is_comparison() {
+            let left_value = left_value.clone().with_quantity();
+            let right_value = right_value.clone().with_quantity();
+            if left_value.is_typed() || right_value.is_typed() {
+                let dty = DataType::try_from(self.dataType.clone()).unwrap_or_default();
+                //a value that does not parse makes the constraint indeterminate
+                return operator.compare(&dty, &left_value, &right_value, &self.status);
+            }
         }
 
-        let right_value = right_value.unwrap();
         let dty = DataType::try_from(self.dataType.clone());
+        //a profile operator which fails is indeterminate rather than false
+        if let (ConstraintOperator::Custom(iri), Ok(dty)) = (operator, &dty) {
+            return ConstraintOperator::custom(iri, dty, &left_value, &right_value);
+        }
         match dty {
             Ok(dty) => {
                 let result = operator.eval(dty,&left_value, &right_value, &self.status);
@@ -220,6 +252,10 @@ impl LogicEval for Constraint {
             }
         }
     }
+
+    fn verdict(&self, world: &mut StateWorld) -> Result<Verdict, anyhow::Error> {
+        Verdict::from_result(self.eval(world), &self.declared_uid())
+    }
 }
 
 
@@ -227,7 +263,10 @@ impl LogicEval for Constraint {
 pub struct LogicConstraint {
     pub uid: Option<IriBuf>,
     pub operator: Option<ConstraintLogicOperator>,
-    pub operand: Option<Vec<Constraint>>
+    //operands may be logical constraints themselves
+    pub operand: Option<Vec<ConstraintUnion>>,
+    //identity of an unnamed constraint, assigned when the policy is compiled
+    pub identity: Option<String>,
 }
 
 impl LogicConstraint {
@@ -237,74 +276,114 @@ impl LogicConstraint {
             uid: Some(IriBuf::new(uid).unwrap()),
             operator: None,
             operand: None,
+            identity: None,
         }
     }
-    pub fn get_operands(&self) -> Option<Vec<Constraint>> {
+    pub fn get_operands(&self) -> Option<Vec<ConstraintUnion>> {
         self.operand.clone()
     }
+
+    //Key of the state the constraint keeps in the world, its declared uid or the compiled identity
+    fn state_id(&self) -> Option<String> {
+        self.uid.as_ref()
+            .map(|uid| uid.as_str().to_string())
+            .filter(|uid| uid != "http://www.w3.org/ns/odrl/2/LogicalConstraint")
+            .or_else(|| self.identity.clone())
+    }
+
+    //Progress of an andSequence is kept per assignee of the request
+    fn sequence_key(&self, id: &str, world: &StateWorld) -> String {
+        let assignee = world.request_usage.as_ref().map(|usage| usage.assignee.as_str()).unwrap_or_default();
+        format!("{}#{}", id, assignee)
+    }
 }
 
 impl LogicEval for LogicConstraint {
-    fn eval(&self, mut world: &mut StateWorld) -> Result<bool, anyhow::Error> {
-        if let None = self.uid {
-            return Err(anyhow::Error::msg("No uid defined"));
+    fn eval(&self, world: &mut StateWorld) -> Result<bool, anyhow::Error> {
+        match self.verdict(world)? {
+            Verdict::True => Ok(true),
+            Verdict::False => Ok(false),
+            verdict => Err(anyhow::Error::msg(verdict.reasons())),
         }
+    }
 
-        if let None = self.operator {
-            return Err(anyhow::Error::msg("No operator defined"));
-        }
+    fn verdict(&self, world: &mut StateWorld) -> Result<Verdict, anyhow::Error> {
+        let operator = match &self.operator {
+            Some(operator) => operator,
+            None => return Ok(Verdict::indeterminate(&self.uid, "No operator defined")),
+        };
+        let operands = self.get_operands().unwrap_or_default();
 
-        let operator = self.get_operator().clone().unwrap();
         match operator {
             ConstraintLogicOperator::or => {
-                let operands = self.get_operands().unwrap();
+                let mut verdict = Verdict::False;
                 for operand in operands {
-                    let ret = operand.eval(&mut world);
-                    match &ret {
-                        Ok(ret) => {
-                            if *ret {
-                                return Ok(true);
-                            }
-                        }
-                        Err(_) => {
-                        }
+                    verdict = verdict.or(operand.verdict(world)?);
+                    if verdict == Verdict::True {
+                        break;
                     }
                 }
-                return Ok(false);
+                Ok(verdict)
             }
             ConstraintLogicOperator::xone => {
-                let operands = self.get_operands().unwrap();
+                //more than one satisfied operand decides, whatever the indeterminate ones would be
                 let mut count = 0;
+                let mut found = vec![];
                 for operand in operands {
-                    let ret = operand.eval(&mut world);
-                    match &ret {
-                        Ok(ret) => {
-                            if *ret {
-                                count += 1;
-                            }
-                        }
-                        Err(_) => {
-                        }
+                    match operand.verdict(world)? {
+                        Verdict::True => count += 1,
+                        Verdict::False => {}
+                        Verdict::Indeterminate(more) => found.extend(more),
                     }
                 }
-                return Ok(count == 1);
+                if count > 1 {
+                    Ok(Verdict::False)
+                } else if !found.is_empty() {
+                    Ok(Verdict::Indeterminate(found))
+                } else {
+                    Ok(if count == 1 { Verdict::True } else { Verdict::False })
+                }
             }
-            ConstraintLogicOperator::andSequence |
             ConstraintLogicOperator::and => {
-                let operands = self.get_operands().unwrap();
+                let mut verdict = Verdict::True;
                 for operand in operands {
-                    let ret = operand.eval(&mut world);
-                    match &ret {
-                        Ok(ret) => {
-                            if !ret {
-                                return Ok(false);
-                            }
-                        }
-                        Err(_) => {
+                    verdict = verdict.and(operand.verdict(world)?);
+                    if verdict == Verdict::False {
+                        break;
+                    }
+                }
+                Ok(verdict)
+            }
+            ConstraintLogicOperator::andSequence => {
+                //progress is kept under the identity, a sequence without one can not be followed
+                let id = match self.state_id() {
+                    Some(id) => id,
+                    None => return Ok(Verdict::indeterminate(&self.uid, "No uid defined for andSequence")),
+                };
+                //operands are satisfied one after another, possibly over several requests,
+                //the sequence only holds once every operand was satisfied in order
+                let key = self.sequence_key(&id, world);
+                let start = world.sequence_progress(&key);
+                let mut progress = start;
+                let mut verdict = Verdict::False;
+                while let Some(operand) = operands.get(progress) {
+                    match operand.verdict(world)? {
+                        Verdict::True => progress += 1,
+                        Verdict::False => break,
+                        found => {
+                            verdict = found;
+                            break;
                         }
                     }
                 }
-                return Ok(true);
+                //steps are kept whatever the request is decided, a partial sequence is never permitted
+                if progress > start {
+                    world.add_sequence_step(key, progress);
+                }
+                if progress >= operands.len() {
+                    return Ok(Verdict::True);
+                }
+                Ok(verdict)
             }
         }
     }
@@ -316,47 +395,58 @@ pub enum ConstraintUnion {
     LogicConstraint(LogicConstraint),
 }
 
+impl LogicEval for ConstraintUnion {
+    fn eval(&self, world: &mut StateWorld) -> Result<bool, anyhow::Error> {
+        match self {
+            ConstraintUnion::Constraint(constraint) => constraint.eval(world),
+            ConstraintUnion::LogicConstraint(logic) => logic.eval(world),
+        }
+    }
+
+    fn verdict(&self, world: &mut StateWorld) -> Result<Verdict, anyhow::Error> {
+        match self {
+            ConstraintUnion::Constraint(constraint) => constraint.verdict(world),
+            ConstraintUnion::LogicConstraint(logic) => logic.verdict(world),
+        }
+    }
+}
+
 
 pub struct ConstraintInference;
 
 impl ConstraintInference {
+    //All constraints have to be satisfied, an indeterminate one fails and is kept in the world with its reason
     pub fn infer(world: &mut StateWorld, constraints: &Vec<ConstraintUnion>) -> Result<bool,anyhow::Error> {
-        let mut result = true;
+        let mut verdict = Verdict::True;
         for constraint in constraints {
-            let ret =  ConstraintInference::infer_one(world, constraint);
-            match ret {
-                Ok(false) => { result = false; },
-                _ => {
-                }
-            }
+            verdict = verdict.and(constraint.verdict(world)?);
         }
 
-        Ok(result)
+        Ok(ConstraintInference::settle(world, verdict))
     }
     pub fn infer_one(world: &mut StateWorld, constraint: &ConstraintUnion) -> Result<bool,anyhow::Error> {
-        let mut result = true;
-        match constraint {
-            ConstraintUnion::Constraint(c) => {
-                // let mut world = world.clone();
-                let ret = c.eval(world);
-                match ret {
-                    Ok(false) => { result = false; },
-                    _ => {
-                    }
-                }
-            }
-            ConstraintUnion::LogicConstraint(lc) => {
-                // let mut world = world.clone();
-                let ret = lc.eval(world);
-                match ret {
-                    Ok(false) => { result = false; },
-                    _ => {
-                    }
-                }
-            }
+        let verdict = constraint.verdict(world)?;
+        Ok(ConstraintInference::settle(world, verdict))
+    }
+    //At least one constraint has to be satisfied
+    pub fn infer_any(world: &mut StateWorld, constraints: &Vec<ConstraintUnion>) -> Result<bool,anyhow::Error> {
+        let mut verdict = Verdict::False;
+        for constraint in constraints {
+            verdict = verdict.or(constraint.verdict(world)?);
         }
 
-        Ok(result)
+        Ok(ConstraintInference::settle(world, verdict))
+    }
+
+    fn settle(world: &mut StateWorld, verdict: Verdict) -> bool {
+        match verdict {
+            Verdict::True => true,
+            Verdict::False => false,
+            Verdict::Indeterminate(found) => {
+                world.add_indeterminate(found);
+                false
+            }
+        }
     }
 }
 
@@ -420,12 +510,79 @@ mod tests {
                             IriBuf::new("http://www.w3.org/ns/odrl/2/LogicalConstraint".to_string()).unwrap(),
                          ))
                         .operator(Some(ConstraintLogicOperator::and))
-                        .operand(Some(vec![constraint1, constraint2]))
+                        .operand(Some(vec![ConstraintUnion::Constraint(constraint1), ConstraintUnion::Constraint(constraint2)]))
                         .build();
 
         let ret =  logic_constraint.eval(&mut world).unwrap();
         assert!(ret);
     }
+
+    #[test]
+    fn test_verdict() {
+        let unknown = || Verdict::indeterminate(&None, "unknown");
+        assert_eq!(Verdict::True.and(unknown()), unknown());
+        assert_eq!(Verdict::False.and(unknown()), Verdict::False);
+        assert_eq!(Verdict::True.or(unknown()), Verdict::True);
+        assert_eq!(Verdict::False.or(unknown()), unknown());
+        assert_eq!(unknown().and(unknown()).reasons(), "unknown; unknown");
+
+        //a constraint without data type can not be compared
+        let mut world = StateWorld::default();
+        world.add_state("http://www.w3.org/ns/odrl/2/version", "1.0");
+        let constraint = time_constraint("version", "eq", "", "1.0");
+        assert!(matches!(constraint.verdict(&mut world).unwrap(), Verdict::Indeterminate(_)));
+        let logic_constraint = LogicConstraint::builder()
+                        .operator(Some(ConstraintLogicOperator::xone))
+                        .operand(Some(vec![ConstraintUnion::Constraint(constraint.clone()), ConstraintUnion::Constraint(time_constraint("version", "eq", "string", "1.0"))]))
+                        .build();
+        assert!(logic_constraint.eval(&mut world).is_err());
+    }
+
+    fn time_constraint(left: &str, op: &str, dty: &str, right: &str) -> Constraint {
+        let mut constraint = Constraint::new("http://www.w3.org/ns/odrl/2/Constraint");
+        let op: ConstraintOperator = op.try_into().unwrap();
+        let left: ConstraintLeftOperand = left.try_into().unwrap();
+        let right: ConstraintRightOperand = ConstraintRightOperand::builder()
+            .value(Some(right.to_string()))
+            .ty(RightOperandType::Literal)
+            .build();
+        constraint.set_operator(Some(op));
+        constraint.set_dataType(dty.to_string());
+        constraint.set_leftOperand(Some(left));
+        constraint.set_rightOperand(Some(right));
+        constraint
+    }
+
+    #[test]
+    fn test_datetime_with_fixed_clock() {
+        use std::sync::Arc;
+        use crate::model::clock::FixedClock;
+
+        let constraint = time_constraint("datetime", "gt", "dateTime", "2025-03-24 00:00:00");
+        let instant = |s: &str| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap().and_utc().timestamp_millis();
+
+        let mut world = StateWorld::new_with_clock("http://abc.tds/world/clock", Arc::new(FixedClock::new(instant("2025-03-25 08:00:00"))));
+        assert!(constraint.eval(&mut world).unwrap());
+
+        let mut world = StateWorld::new_with_clock("http://abc.tds/world/clock", Arc::new(FixedClock::new(instant("2025-03-23 08:00:00"))));
+        assert!(!constraint.eval(&mut world).unwrap());
+    }
+
+    #[test]
+    fn test_elapsed_time_with_manual_clock() {
+        use std::sync::Arc;
+        use crate::model::clock::ManualClock;
+
+        let clock = ManualClock::new(1_000_000);
+        let mut world = StateWorld::new_with_clock("http://abc.tds/world/clock", Arc::new(clock.clone()));
+        let constraint = time_constraint("elapsedTime", "gt", "integer", "PT10S");
+        assert!(!constraint.eval(&mut world).unwrap());
+
+        clock.advance(11_000);
+        assert_eq!(world.eclipse_datetime(), 11_000);
+        assert!(constraint.eval(&mut world).unwrap());
+    }
+
 }
 
 
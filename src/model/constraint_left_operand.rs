@@ -20,12 +20,16 @@
 use std::f32::consts::E;
 use std::str::FromStr;
 use anyhow::anyhow;
-use chrono::Duration;
+use chrono::{DateTime, Duration, FixedOffset, Utc};
 use lombok::{Builder, Getter, GetterMut, Setter};
 use serde_json::to_string;
 use thiserror::Error;
+use iref::IriBuf;
+use crate::model::operand_provider::GLOBAL_OPERAND_PROVIDERS;
+use crate::model::profile::GLOBAL_PROFILE_REGISTRY;
 use crate::model::stateworld::StateWorld;
-use crate::reference::types::{OperandValue, OperandValueType};
+use crate::reference::temporal::XsdDuration;
+use crate::reference::types::{OperandValue, OperandValueType, TypedValue};
 
 // 自定义错误类型
 #[derive(Error, Debug)]
@@ -36,73 +40,28 @@ pub enum ParseError {
     IncompleteComponent,
     #[error("解析数字失败: {0}")]
     ParseNumberError(#[from] std::num::ParseFloatError),
+    #[error("解析整数失败: {0}")]
+    ParseIntegerError(#[from] std::num::ParseIntError),
+    #[error("持续时间字符串组件顺序错误")]
+    MisplacedComponent,
+    #[error("持续时间超出范围")]
+    OutOfRange,
 }
 
-// 解析 XML 持续时间字符串
-pub fn parse_xml_duration(duration_str: &str) -> Result<Duration, ParseError> {
-    let mut total_seconds: f64 = 0.0; // 使用 f64 以支持小数
-    let mut current_num = String::new(); // 累积当前数字
-    let mut is_negative = false; // 是否为负持续时间
-    let mut in_time_part = false; // 是否在时间部分
-
-    let mut chars = duration_str.chars().peekable();
-
-    // 检查负号
-    if let Some('-') = chars.peek() {
-        is_negative = true;
-        chars.next();
-    }
-
-    // 确保以 'P' 开头
-    if chars.next() != Some('P') {
-        return Err(ParseError::InvalidCharacter);
-    }
-
-    // 逐字符解析
-    while let Some(ch) = chars.next() {
-        match ch {
-            'T' => {
-                in_time_part = true;
-                continue;
-            }
-            'Y' | 'M' | 'D' | 'H' | 'S' => {
-                if current_num.is_empty() {
-                    return Err(ParseError::IncompleteComponent);
-                }
-                let value = f64::from_str(&current_num)?;
-                current_num.clear();
-                total_seconds += match (ch, in_time_part) {
-                    ('Y', false) => value * 365.25 * 24.0 * 3600.0, // 年，考虑闰年
-                    ('M', false) => value * 30.44 * 24.0 * 3600.0,  // 月，平均长度
-                    ('D', false) => value * 24.0 * 3600.0,          // 天
-                    ('H', true) => value * 3600.0,                  // 小时
-                    ('M', true) => value * 60.0,                    // 分钟
-                    ('S', true) => value,                           // 秒
-                    _ => return Err(ParseError::InvalidCharacter),
-                };
-            }
-            c if c.is_digit(10) || c == '.' => current_num.push(c),
-            _ => return Err(ParseError::InvalidCharacter),
-        }
-    }
-
-    // 检查是否有未处理的数字
-    if !current_num.is_empty() {
-        return Err(ParseError::IncompleteComponent);
-    }
-
-    // 应用正负号
-    let total_seconds = if is_negative {
-        -total_seconds
-    } else {
-        total_seconds
-    };
+//The world clock in milliseconds as an instant
+fn instant(millis: i64) -> Result<DateTime<FixedOffset>, anyhow::Error> {
+    DateTime::from_timestamp_millis(millis).map(|instant| instant.fixed_offset())
+        .ok_or_else(|| anyhow!("Invalid timestamp {}", millis))
+}
 
-    Ok(Duration::seconds(total_seconds as i64))
+// 解析 XML 持续时间字符串, 年和月按截至 anchor 时刻的日历长度计算, anchor 通常是 world 的时钟
+pub fn parse_xml_duration(duration_str: &str, anchor: DateTime<FixedOffset>) -> Result<Duration, ParseError> {
+    let duration = XsdDuration::parse(duration_str)?;
+    duration.before(anchor).ok_or(ParseError::OutOfRange)
 }
 
 
-#[derive(Debug,Clone)]
+#[derive(Debug,Clone,PartialEq)]
 pub enum ConstraintLeftOperand {
     //http://www.w3.org/ns/odrl/2/absolutePosition
     absolutePosition,
@@ -169,10 +128,17 @@ pub enum ConstraintLeftOperand {
     //http://www.w3.org/ns/odrl/2/virtualLocation
     virtualLocation,
     //http://www.w3.org/ns/odrl/2/timeWindow
-    timeWindow
+    timeWindow,
+    //left operand declared by a registered profile
+    Custom(IriBuf)
 }
 
 impl  ConstraintLeftOperand {
+    //Counters and instants kept by the engine, a unit does not measure them
+    pub fn is_dimensionless(&self) -> bool {
+        matches!(self, ConstraintLeftOperand::count | ConstraintLeftOperand::datetime)
+    }
+
     pub fn value(&self, world: &mut StateWorld) -> Result<OperandValue,anyhow::Error> {
         match self {
             //please match all the operator in the order of the enum
@@ -205,8 +171,25 @@ impl  ConstraintLeftOperand {
             | ConstraintLeftOperand::virtualLocation
             | ConstraintLeftOperand::delayPeriod
             => {
-                let state = self.to_iri().unwrap();
-                let state = world.get_state(state.as_str());
+                let iri = self.to_iri().unwrap();
+                //the request being evaluated wins over the shared world state
+                if let Some(attribute) = world.get_request_attribute(iri.as_str()) {
+                    return Ok(attribute.clone());
+                }
+                if let Some(provided) = GLOBAL_OPERAND_PROVIDERS.provide(iri.as_str(), self, world)? {
+                    return Ok(provided);
+                }
+                //usage counted by the engine including the use being requested, so 'count lteq 10' allows 10 uses,
+                //the world state is only read outside of a request
+                if let (ConstraintLeftOperand::count, Some(used)) = (self, world.request_usage_count()) {
+                    let count = used + 1;
+                    let mut val = OperandValue::default();
+                    val.set_ty(OperandValueType::string);
+                    val.set_sval(Some(count.to_string()));
+                    val.set_typed(Some(TypedValue::Integer(count as i64)));
+                    return Ok(val);
+                }
+                let state = world.get_state(iri.as_str());
 
                 let mut val = OperandValue::default();
                 val.set_ty(OperandValueType::string);
@@ -225,6 +208,7 @@ impl  ConstraintLeftOperand {
                 let mut val = OperandValue::default();
                 val.set_ty(OperandValueType::string);
                 val.set_sval(Some(time_interval.to_string()));
+                val.set_typed(Some(TypedValue::Duration(XsdDuration::between(instant(world.last_execute_time())?, instant(world.now())?))));
                 return Ok(val);
             }
             ConstraintLeftOperand::meteredTime => {
@@ -240,6 +224,7 @@ impl  ConstraintLeftOperand {
                 let mut val = OperandValue::default();
                 val.set_ty(OperandValueType::string);
                 val.set_sval(Some(now.to_string()));
+                val.set_typed(Some(TypedValue::DateTime(instant(now)?)));
                 Ok(val)
             },
             ConstraintLeftOperand::elapsedTime => {
@@ -247,9 +232,34 @@ impl  ConstraintLeftOperand {
                 let mut val = OperandValue::default();
                 val.set_ty(OperandValueType::string);
                 val.set_sval(Some(eclipsed.to_string()));
+                //calendar months of the right operand count from the start of the world
+                let now = world.now();
+                val.set_typed(Some(TypedValue::Duration(XsdDuration::between(instant(now - eclipsed)?, instant(now)?))));
 
                 Ok(val)
             }
+            ConstraintLeftOperand::Custom(iri) => {
+                if let Some(attribute) = world.get_request_attribute(iri.as_str()) {
+                    return Ok(attribute.clone());
+                }
+                if let Some(provided) = GLOBAL_OPERAND_PROVIDERS.provide(iri.as_str(), self, world)? {
+                    return Ok(provided);
+                }
+                if let Some(resolver) = GLOBAL_PROFILE_REGISTRY.get_left_operand(iri.as_str()) {
+                    return resolver(world);
+                }
+
+                //without a resolver the profile operand is read from the world state
+                match world.get_state(iri.as_str()) {
+                    Some(state) => {
+                        let mut val = OperandValue::default();
+                        val.set_ty(OperandValueType::string);
+                        val.set_sval(Some(state.to_owned()));
+                        Ok(val)
+                    }
+                    None => Err(anyhow!("constraint left operand: {} not found", iri.as_str())),
+                }
+            }
             ConstraintLeftOperand::timeWindow => {
                 //Just working
                 let time_window = 0;
@@ -362,6 +372,9 @@ impl  ConstraintLeftOperand {
             ConstraintLeftOperand::timeWindow => {
                 Ok(String::from("http://www.w3.org/ns/odrl/2/timeWindow"))
             }
+            ConstraintLeftOperand::Custom(iri) => {
+                Ok(iri.as_str().to_string())
+            }
         }
     }
 }
@@ -369,6 +382,11 @@ impl  ConstraintLeftOperand {
 impl TryFrom<&str> for ConstraintLeftOperand {
     type Error = anyhow::Error;
     fn try_from(value: &str) -> Result<Self, Self::Error> {
+        //profile operands are matched by their exact iri, before any local name
+        if GLOBAL_PROFILE_REGISTRY.get_left_operand(value).is_some() {
+            return Ok(ConstraintLeftOperand::Custom(IriBuf::new(value.to_string()).map_err(|_| anyhow!("Invalid left operand: {}", value))?));
+        }
+
         let mut value = value.to_lowercase();
         if value.contains("/") {
             let index = value.rfind("/").unwrap();
@@ -454,6 +472,7 @@ impl TryFrom<ConstraintLeftOperand> for String {
             ConstraintLeftOperand::version => Ok("version".to_string()),
             ConstraintLeftOperand::virtualLocation => Ok("virtualLocation".to_string()),
             ConstraintLeftOperand::timeWindow => Ok("timeWindow".to_string()),
+            ConstraintLeftOperand::Custom(iri) => Ok(iri.as_str().to_string()),
         }
     }
 }
@@ -466,6 +485,7 @@ impl FromStr for ConstraintLeftOperand {
 }
 
 mod test {
+    use chrono::TimeZone;
     use super::*;
 
     #[test]
@@ -479,9 +499,16 @@ mod test {
     #[test]
     fn test_constraint_left_operand_error() {
         let duration_str = "P1Y2M3DT4H5M6S"; // Example XML duration string
-        match parse_xml_duration(duration_str) {
+        let anchor = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap().fixed_offset();
+        match parse_xml_duration(duration_str, anchor) {
             Ok(duration) => println!("Total duration in seconds: {}", duration.num_seconds()),
             Err(e) => println!("Error parsing duration: {}", e),
         }
+
+        //calendar months are measured back from the anchor, not from the wall clock
+        assert_eq!(parse_xml_duration("P1M", anchor).unwrap().num_days(), 29);
+        let anchor = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap().fixed_offset();
+        assert_eq!(parse_xml_duration("P1M", anchor).unwrap().num_days(), 28);
+        assert!(parse_xml_duration("1H", anchor).is_err());
     }
 }
@@ -20,10 +20,15 @@
 use std::f32::consts::E;
 use std::str::FromStr;
 use lombok::{Builder, Getter, GetterMut, Setter};
+use iref::IriBuf;
+use crate::model::concept_hierarchy::GLOBAL_CONCEPT_HIERARCHY;
 use crate::model::data_type::DataType;
-use crate::reference::types::OperandValue;
+use crate::model::profile::GLOBAL_PROFILE_REGISTRY;
+use std::cmp::Ordering;
+use crate::model::error::OdrlError;
+use crate::reference::types::{OperandValue, TypedValue};
+use crate::reference::units::GLOBAL_UNIT_REGISTRY;
 use chrono::{NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
-use crate::model::{constraint_left_operand::parse_xml_duration };
 
 
 #[derive(Debug,Clone)]
@@ -52,41 +57,35 @@ pub enum ConstraintOperator {
     //http://www.w3.org/ns/odrl/2/isAnyOf
     isAnyOf,
     //http://www.w3.org/ns/odrl/2/isNoneOf
-    isNoneOf
+    isNoneOf,
+    //operator declared by a registered profile
+    Custom(IriBuf)
 }
 
-fn parse_datetime(input: &str) -> i64 {
-    let date = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M:%S");
-    if let Ok(date) = date {
-        return Utc.from_utc_datetime(&date).timestamp_millis()
-    } else {
-        //try to parse date
-        let date =  NaiveDate::parse_from_str(input, "%Y-%m-%d");
-        if let Ok(date) = date {
-            let datetime = date.and_hms_opt(0, 0, 0).unwrap();
-            return Utc.from_utc_datetime(&datetime).timestamp_millis()
-        }else {
-            //try to parse time
-            let time = chrono::NaiveTime::parse_from_str(input, "%H:%M:%S");
-            if let Ok(time) = time {
-                let seconds = time.num_seconds_from_midnight() as i64;
-                let milliseconds = seconds * 1_000;
-                return milliseconds;
-            } else {
-                let date =  parse_xml_duration(input);
-                if let Ok(date) = date {
-                    return date.num_milliseconds();
-                } else {
-                    return 0;
-                }
-            }
+//Left and right concepts of a hierarchy operator, each side may be a single value or a set
+fn concepts(left: &OperandValue, right: &OperandValue, de: &Option<String>) -> Result<(Vec<String>, Vec<String>), anyhow::Error> {
+    let values = |value: &OperandValue| value.get_set().or_else(|| value.get_sval().map(|value| vec![value]));
+
+    let left = match values(left) {
+        Some(left) => left,
+        //try to use de
+        None => match de {
+            Some(de) => vec![de.clone()],
+            None => return Err(anyhow::anyhow!("Left operand is None")),
         }
-    }
+    };
+    let right = values(right).ok_or_else(|| anyhow::anyhow!("Right operand is None"))?;
+    Ok((left, right))
 }
 
 impl TryFrom<&str> for ConstraintOperator {
     type Error = anyhow::Error;
     fn try_from(value: &str) -> Result<Self, Self::Error> {
+        //profile operators are matched by their exact iri, before any local name
+        if GLOBAL_PROFILE_REGISTRY.get_operator(value).is_some() {
+            return Ok(ConstraintOperator::Custom(IriBuf::new(value.to_string()).map_err(|_| anyhow::anyhow!("Invalid operator: {}", value))?));
+        }
+
         let mut value = value.to_lowercase();
         if value.contains("/") {
             let index = value.rfind("/").unwrap();
@@ -100,7 +99,8 @@ impl TryFrom<&str> for ConstraintOperator {
             "gteq" => Ok(ConstraintOperator::gteq),
             "lt" => Ok(ConstraintOperator::lt),
             "lteq" => Ok(ConstraintOperator::lteq),
-            "neq" => Ok(ConstraintOperator::neq),
+            //the W3C ODRL context maps the neq term to odrl:neg
+            "neq" | "neg" => Ok(ConstraintOperator::neq),
             "isa" => Ok(ConstraintOperator::isA),
             "haspart" => Ok(ConstraintOperator::hasPart),
             "ispartof" => Ok(ConstraintOperator::isPartOf),
@@ -170,769 +170,108 @@ impl ConstraintOperator {
                     return result;
                 }
                 return false;
-            },
-            ConstraintOperator::isPartOf => {
-                let result =  self.isPartOf(dty,left,right,de);
-                if let Ok(result) = result {
-                    return result;
-                }
-                return false;
-            },
-            ConstraintOperator::isAllOf => {
-                let result =  self.isAllOf(dty,left,right,de);
-                if let Ok(result) = result {
-                    return result;
-                }
-                return false;
-            },
-            ConstraintOperator::isAnyOf => {
-                let result =  self.isAnyOf(dty,left,right,de);
-                if let Ok(result) = result {
-                    return result;
-                }
-                return false;
-            },
-            ConstraintOperator::isNoneOf => {
-                let result =  self.isNoneOf(dty,left,right,de);
-                if let Ok(result) = result {
-                    return result;
-                }
-                return false;
-            }
-        }
-    }
-
-    fn gt(&self, dty: DataType, left: &OperandValue, right: &OperandValue,de: &Option<String>) -> Result<bool, anyhow::Error> {
-        match dty {
-           DataType::Integer => {
-               let mut  left = left.get_sval();
-               if left.is_none() {
-                   //try to use de
-                   if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                   }
-                   left = de.clone();
-               }
-               let left = left.unwrap();
-
-               let right = right.get_sval();
-               if right.is_none() {
-                   return Err(anyhow::anyhow!("Right operand is None"));
-               }
-               let right = right.unwrap();
-
-               let left_val = left.parse::<i64>();
-               if left_val.is_err() {
-                   return Err(anyhow::anyhow!("Left operand has invalid value"));
-               }
-               let left_val = left_val.unwrap();
-
-               //try to parse right as integer
-               let right_val = right.parse::<i64>();
-               if let Ok(right_val) = right_val {
-                   return Ok(left_val > right_val);
-               }
-
-               //if right is not integer, treat it as a string
-               let right = parse_xml_duration(right.as_str());
-               if let Ok(right_val) = right {
-                   return Ok(left_val > right_val.num_milliseconds());
-               }
-               return Ok(false);
-           },
-           DataType::Float => {
-               let mut  left = left.get_sval();
-               if left.is_none() {
-                   //try to use de
-                   if de.is_none() {
-                       return Err(anyhow::anyhow!("Left operand is None"));
-                   }
-                   left = de.clone();
-               }
-               let left = left.unwrap();
-
-               let right = right.get_sval();
-               if right.is_none() {
-                   return Err(anyhow::anyhow!("Right operand is None"));
-               }
-               let right = right.unwrap();
-
-
-               let left_val = left.parse::<f64>();
-               if left_val.is_err() {
-                   return Err(anyhow::anyhow!("Left operand has invalid value"));
-               }
-               let left_val = left_val.unwrap();
-
-               let right_val = right.parse::<f64>();
-               if right_val.is_err() {
-                   return Err(anyhow::anyhow!("Right operand has invalid value"));
-               }
-               let right_val = right_val.unwrap();
-
-               if left_val - right_val > std::f64::EPSILON {
-                   return Ok(true);
-               }
-               Ok(false)
-           },
-           DataType::Date => {
-               let mut  left = left.get_sval();
-               if left.is_none() {
-                   //try to use de
-                   if de.is_none() {
-                       return Err(anyhow::anyhow!("Left operand is None"));
-                   }
-                   left = de.clone();
-               }
-               let left = left.unwrap();
-
-               let right = right.get_sval();
-               if right.is_none() {
-                   return Err(anyhow::anyhow!("Right operand is None"));
-               }
-               let right = right.unwrap();
-
-               let left_val = left.parse::<i64>();
-               if left_val.is_err() {
-                   return Err(anyhow::anyhow!("Left operand has invalid value"));
-               }
-               let left_val = left_val.unwrap();
-
-               let right_val = parse_datetime(right.as_str());
-               Ok(left_val > right_val)
-           },
-           DataType::DateTime |
-           DataType::Time => {
-               let mut  left = left.get_sval();
-               if left.is_none() {
-                   //try to use de
-                   if de.is_none() {
-                       return Err(anyhow::anyhow!("Left operand is None"));
-                   }
-                   left = de.clone();
-               }
-               let left = left.unwrap();
-
-               let right = right.get_sval();
-               if right.is_none() {
-                   return Err(anyhow::anyhow!("Right operand is None"));
-               }
-               let right = right.unwrap();
-
-
-               let left_val = left.parse::<i64>();
-               if left_val.is_err() {
-                   return Err(anyhow::anyhow!("Left operand has invalid value"));
-               }
-               let left_val = left_val.unwrap();
-
-               let right_val = parse_datetime(right.as_str());
-               Ok(left_val > right_val)
-           }
-           _ => Err(anyhow::anyhow!("Invalid operator"))
-        }
-    }
-
-    fn eq(&self, dty: DataType, left: &OperandValue, right: &OperandValue,de: &Option<String>) -> Result<bool, anyhow::Error> {
-        match dty {
-            DataType::String => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                Ok(left == right)
-            },
-            DataType::Integer => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-
-                let left_val = left.parse::<i64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right_val = right.parse::<i64>();
-                if let Ok(right_val) = right_val {
-                    return Ok(left_val == right_val);
-                }
-
-                //if right is not integer, treat it as a string
-                let right = parse_xml_duration(right.as_str());
-                if let Ok(right_val) = right {
-                    return Ok(left_val == right_val.num_milliseconds());
-                }
-                return Err(anyhow::anyhow!("Right operand has invalid value"));
-            },
-            DataType::Float => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                let left_val = left.parse::<f64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right = right.parse::<f64>();
-                if let Ok(right_val) = right {
-                    return Ok((left_val - right_val).abs() < f64::EPSILON);
-                }
-                return Err(anyhow::anyhow!("Right operand has invalid value"));
-            },
-            DataType::Boolean => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-
-                let left_val = left.parse::<bool>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right = right.parse::<bool>();
-                if right.is_err() {
-                    return Err(anyhow::anyhow!("Right operand has invalid value"));
-                }
-                let right_val = right.unwrap();
-
-                Ok(left_val == right_val)
-            },
-            DataType::Date |
-            DataType::DateTime |
-            DataType::Time => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                let left_val = left.parse::<i64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right_val = parse_datetime(right.as_str());
-                Ok(left_val == right_val)
-            }
-        }
-    }
-
-    fn gteq(&self, dty: DataType, left: &OperandValue, right: &OperandValue,de :&Option<String>) -> Result<bool, anyhow::Error> {
-        match dty {
-            DataType::Integer => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                let left_val = left.parse::<i64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right_val = right.parse::<i64>();
-                if let Ok(right_val) = right_val {
-                    return Ok(left_val >= right_val);
-                }
-                //if right is not integer, treat it as a string
-                let right = parse_xml_duration(right.as_str());
-                if let Ok(right_val) = right {
-                    return Ok(left_val >= right_val.num_milliseconds());
-                }
-                return Err(anyhow::anyhow!("Right operand has invalid value"));
-            },
-            DataType::Float => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-
-                let left_val = left.parse::<f64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right_val = right.parse::<f64>();
-                if right_val.is_err() {
-                    return Err(anyhow::anyhow!("Right operand has invalid value"));
-                }
-                let right_val = right_val.unwrap();
-
-
-                Ok(left_val - right_val >= f64::EPSILON)
-            },
-            DataType::Date => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                let left_val = left.parse::<i64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right_val = parse_datetime(right.as_str());
-                Ok(left_val >= right_val)
-            },
-            DataType::DateTime |
-            DataType::Time => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                let left_val = left.parse::<i64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right_val = parse_datetime(right.as_str());
-                Ok(left_val >= right_val)
-            }
-            _ => {
-                Result::Err(anyhow::anyhow!("Invalid operator"))
-            }
-        }
-    }
-    fn lt(&self, dty: DataType, left: &OperandValue, right: &OperandValue,de: &Option<String>) -> Result<bool, anyhow::Error>  {
-        match dty {
-            DataType::Integer => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                let left_val = left.parse::<i64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right_val = right.parse::<i64>();
-                if let Ok(right_val) = right_val {
-                    return Ok(left_val < right_val);
-                }
-
-                //if right is not integer, treat it as a string
-                let right = parse_xml_duration(right.as_str());
-                if let Ok(right_val) = right {
-                    return Ok(left_val < right_val.num_milliseconds());
-                }
-                return Err(anyhow::anyhow!("Right operand has invalid value"));
-            }
-            DataType::Float => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                let left_val = left.parse::<f64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right = right.parse::<f64>();
-                if right.is_err() {
-                    return Err(anyhow::anyhow!("Right operand has invalid value"));
-                }
-                let right_val = right.unwrap();
-                Ok(left_val - right_val < f64::EPSILON)
-            },
-            DataType::Date => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                let left_val = left.parse::<i64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right_val = parse_datetime(right.as_str());
-                Ok(left_val < right_val)
-            }
-            DataType::Time => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                let left_val = left.parse::<i64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right_val = parse_datetime(right.as_str());
-                Ok(left_val < right_val)
-            }
-            DataType::DateTime => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                let left_val = left.parse::<i64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right_val = parse_datetime(right.as_str());
-
-                Ok(left_val < right_val)
-            }
-            _ => {
-                Err(anyhow::anyhow!("Invalid operator"))
-            }
-        }
-    }
-    fn lteq(&self, dty: DataType, left: &OperandValue, right: &OperandValue,de: &Option<String>) -> Result<bool, anyhow::Error> {
-        match dty {
-            DataType::Integer => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                let left_val = left.parse::<i64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right_val = right.parse::<i64>();
-                if let Ok(right_val) = right_val {
-                    return Ok(left_val <= right_val);
-                }
-
-                //if right is not integer, treat it as a string
-                let right = parse_xml_duration(right.as_str());
-                if let Ok(right_val) = right {
-                    return Ok(left_val <= right_val.num_milliseconds());
-                }
-                return Err(anyhow::anyhow!("Right operand has invalid value"));
-            },
-            DataType::Float => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                let left_val = left.parse::<f64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right_val = right.parse::<f64>();
-                if right_val.is_err() {
-                    return Err(anyhow::anyhow!("Right operand has invalid value"));
-                }
-                let right_val = right_val.unwrap();
-                Ok(left_val - right_val <= f64::EPSILON)
-            }
-            DataType::Date => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                let left = left.parse::<i64>();
-                if left.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
+            },
+            ConstraintOperator::isPartOf => {
+                let result =  self.isPartOf(dty,left,right,de);
+                if let Ok(result) = result {
+                    return result;
                 }
-                let left_val = left.unwrap();
-
-                let right_val = parse_datetime(right.as_str());
-                Ok(left_val <= right_val)
-            }
-            DataType::Time => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
+                return false;
+            },
+            ConstraintOperator::isAllOf => {
+                let result =  self.isAllOf(dty,left,right,de);
+                if let Ok(result) = result {
+                    return result;
                 }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
+                return false;
+            },
+            ConstraintOperator::isAnyOf => {
+                let result =  self.isAnyOf(dty,left,right,de);
+                if let Ok(result) = result {
+                    return result;
                 }
-                let right = right.unwrap();
-
-                let left = left.parse::<i64>();
-                if left.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
+                return false;
+            },
+            ConstraintOperator::isNoneOf => {
+                let result =  self.isNoneOf(dty,left,right,de);
+                if let Ok(result) = result {
+                    return result;
                 }
-                let left_val = left.unwrap();
-
-                let right_val = parse_datetime(right.as_str());
-                Ok(left_val <= right_val)
+                return false;
+            },
+            ConstraintOperator::Custom(iri) => {
+                Self::custom(iri, &dty, left, right).unwrap_or(false)
             }
-            DataType::DateTime => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
+        }
+    }
 
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
+    //Evaluate a profile operator, a failing or unregistered one makes the constraint indeterminate
+    pub fn custom(iri: &IriBuf, dty: &DataType, left: &OperandValue, right: &OperandValue) -> Result<bool, anyhow::Error> {
+        //the profile may have been unregistered since the policy was loaded
+        let operator = GLOBAL_PROFILE_REGISTRY.get_operator(iri.as_str())
+            .ok_or_else(|| anyhow::Error::msg(format!("operator {} is not registered", iri.as_str())))?;
+        operator(dty, left, right).map_err(|e| anyhow::Error::msg(format!("operator {} failed: {}", iri.as_str(), e)))
+    }
 
-                let left_val = left.parse::<i64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
+    pub fn is_comparison(&self) -> bool {
+        matches!(self, ConstraintOperator::eq | ConstraintOperator::neq | ConstraintOperator::gt
+            | ConstraintOperator::gteq | ConstraintOperator::lt | ConstraintOperator::lteq)
+    }
 
-                let right_val = parse_datetime(right.as_str());
-                Ok(left_val <= right_val)
+    //Compare typed operands and quantities, a unit on one side only or of another dimension is an error
+    pub fn compare(&self, dty: &DataType, left: &OperandValue, right: &OperandValue, de: &Option<String>) -> Result<bool, anyhow::Error> {
+        let ordering = match (&left.unit, &right.unit) {
+            (None, None) => {
+                let right_value = right.typed_value(dty, left.typed.as_ref(), &None)?;
+                let left_value = left.typed_value(dty, Some(&right_value), de)?;
+                left_value.compare(&right_value)?
             }
-            _ => {
-                Err(anyhow::anyhow!("Invalid operator"))
+            (Some(left_unit), Some(right_unit)) => {
+                let number = TypedValue::Decimal(0.0);
+                let left_value = left.typed_value(dty, Some(&number), de)?;
+                let right_value = right.typed_value(dty, Some(&number), &None)?;
+                if left_value.as_f64().is_nan() || right_value.as_f64().is_nan() {
+                    return Err(OdrlError::IncomparableValues(left_value.to_string(), right_value.to_string()).into());
+                }
+                let (left_value, right_value) = GLOBAL_UNIT_REGISTRY.convert_pair(
+                    (left_value.as_f64(), left_unit), (right_value.as_f64(), right_unit))?;
+                left_value.partial_cmp(&right_value)
             }
+            (Some(unit), None) | (None, Some(unit)) => {
+                return Err(OdrlError::MissingUnit(unit.clone()).into());
+            }
+        };
+
+        let ordered = |ordering: Option<Ordering>| ordering.ok_or_else(|| OdrlError::IncomparableValues(
+            left.get_sval().unwrap_or_default(), right.get_sval().unwrap_or_default()));
+        match self {
+            ConstraintOperator::eq => Ok(ordering == Some(Ordering::Equal)),
+            ConstraintOperator::neq => Ok(ordering != Some(Ordering::Equal)),
+            ConstraintOperator::gt => Ok(ordered(ordering)? == Ordering::Greater),
+            ConstraintOperator::gteq => Ok(ordered(ordering)? != Ordering::Less),
+            ConstraintOperator::lt => Ok(ordered(ordering)? == Ordering::Less),
+            ConstraintOperator::lteq => Ok(ordered(ordering)? != Ordering::Greater),
+            _ => Err(anyhow::anyhow!("Invalid operator")),
         }
     }
 
-    fn neq(&self, dty: DataType, left: &OperandValue, right: &OperandValue,de: &Option<String>) -> Result<bool, anyhow::Error> {
+    fn gt(&self, dty: DataType, left: &OperandValue, right: &OperandValue,de: &Option<String>) -> Result<bool, anyhow::Error> {
         match dty {
-            DataType::String => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
+           //numbers and temporal operands compare by typed value, instants, days and times each in their timezone
+           DataType::Integer |
+           DataType::Float |
+           DataType::Date |
+           DataType::DateTime |
+           DataType::Time => {
+               self.compare(&dty, left, right, de)
+           }
+           _ => Err(anyhow::anyhow!("Invalid operator"))
+        }
+    }
 
-                Ok(left != right)
-            }
-            DataType::Integer => {
+    fn eq(&self, dty: DataType, left: &OperandValue, right: &OperandValue,de: &Option<String>) -> Result<bool, anyhow::Error> {
+        match dty {
+            DataType::String => {
                 let mut  left = left.get_sval();
                 if left.is_none() {
                     //try to use de
@@ -949,24 +288,9 @@ impl ConstraintOperator {
                 }
                 let right = right.unwrap();
 
-                let left_val = left.parse::<i64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right_val = right.parse::<i64>();
-                if let Ok(right_val) = right_val {
-                    return Ok(left_val != right_val);
-                }
-                //if right is not integer, treat it as a string
-                let right = parse_xml_duration(right.as_str());
-                if let Ok(right_val) = right {
-                    return Ok(left_val != right_val.num_milliseconds());
-                }
-                return Ok(false);
+                Ok(left == right)
             },
-            DataType::Float => {
+            DataType::Boolean => {
                 let mut  left = left.get_sval();
                 if left.is_none() {
                     //try to use de
@@ -983,72 +307,77 @@ impl ConstraintOperator {
                 }
                 let right = right.unwrap();
 
-                let left_val = left.parse::<f64>();
+
+                let left_val = left.parse::<bool>();
                 if left_val.is_err() {
                     return Err(anyhow::anyhow!("Left operand has invalid value"));
                 }
                 let left_val = left_val.unwrap();
-                let right_val = right.parse::<f64>();
-                if right_val.is_err() {
+
+                let right = right.parse::<bool>();
+                if right.is_err() {
                     return Err(anyhow::anyhow!("Right operand has invalid value"));
                 }
-                let right_val = right_val.unwrap();
+                let right_val = right.unwrap();
 
-                Ok((left_val - right_val) > f64::EPSILON)
+                Ok(left_val == right_val)
+            },
+            DataType::Integer |
+            DataType::Float |
+            DataType::Date |
+            DataType::DateTime |
+            DataType::Time => {
+                self.compare(&dty, left, right, de)
             }
-            DataType::Date => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                let left_val = left.parse::<i64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
+        }
+    }
 
-                let right_val = parse_datetime(right.as_str());
-                Ok(left_val != right_val)
+    fn gteq(&self, dty: DataType, left: &OperandValue, right: &OperandValue,de :&Option<String>) -> Result<bool, anyhow::Error> {
+        match dty {
+            DataType::Integer |
+            DataType::Float |
+            DataType::Date |
+            DataType::DateTime |
+            DataType::Time => {
+                self.compare(&dty, left, right, de)
+            }
+            _ => {
+                Result::Err(anyhow::anyhow!("Invalid operator"))
             }
+        }
+    }
+    fn lt(&self, dty: DataType, left: &OperandValue, right: &OperandValue,de: &Option<String>) -> Result<bool, anyhow::Error>  {
+        match dty {
+            DataType::Integer |
+            DataType::Float |
+            DataType::Date |
+            DataType::DateTime |
             DataType::Time => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_sval();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                let left_val = left.parse::<i64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right_val = parse_datetime(right.as_str());
-                Ok(left_val != right_val)
+                self.compare(&dty, left, right, de)
+            }
+            _ => {
+                Err(anyhow::anyhow!("Invalid operator"))
+            }
+        }
+    }
+    fn lteq(&self, dty: DataType, left: &OperandValue, right: &OperandValue,de: &Option<String>) -> Result<bool, anyhow::Error> {
+        match dty {
+            DataType::Integer |
+            DataType::Float |
+            DataType::Date |
+            DataType::DateTime |
+            DataType::Time => {
+                self.compare(&dty, left, right, de)
             }
-            DataType::DateTime => {
+            _ => {
+                Err(anyhow::anyhow!("Invalid operator"))
+            }
+        }
+    }
+
+    fn neq(&self, dty: DataType, left: &OperandValue, right: &OperandValue,de: &Option<String>) -> Result<bool, anyhow::Error> {
+        match dty {
+            DataType::String => {
                 let mut  left = left.get_sval();
                 if left.is_none() {
                     //try to use de
@@ -1065,14 +394,14 @@ impl ConstraintOperator {
                 }
                 let right = right.unwrap();
 
-                let left_val = left.parse::<i64>();
-                if left_val.is_err() {
-                    return Err(anyhow::anyhow!("Left operand has invalid value"));
-                }
-                let left_val = left_val.unwrap();
-
-                let right_val = parse_datetime(right.as_str());
-                Ok(left_val != right_val)
+                Ok(left != right)
+            }
+            DataType::Integer |
+            DataType::Float |
+            DataType::Date |
+            DataType::DateTime |
+            DataType::Time => {
+                self.compare(&dty, left, right, de)
             }
             _ => {
                 Err(anyhow::anyhow!("Invalid operator"))
@@ -1080,6 +409,8 @@ impl ConstraintOperator {
         }
     }
 
+    //Left concepts must each be within one of the right concepts in the concept hierarchy,
+    //a concept is within itself so plain membership still holds
     #[allow(non_snake_case)]
     fn isA(&self, dty: DataType, left: &OperandValue, right: &OperandValue,de: &Option<String>) -> Result<bool, anyhow::Error> {
         match dty {
@@ -1089,23 +420,8 @@ impl ConstraintOperator {
             | DataType::Date
             | DataType::Time
             => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_set();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                Ok(right.contains(&left))
+                let (left, right) = concepts(left, right, de)?;
+                Ok(left.iter().all(|concept| right.iter().any(|ancestor| GLOBAL_CONCEPT_HIERARCHY.is_within(concept, ancestor))))
             },
             _ => {
                 Err(anyhow::anyhow!("Invalid operator"))
@@ -1113,6 +429,7 @@ impl ConstraintOperator {
         }
     }
 
+    //Right concepts must each be within one of the left concepts
     #[allow(non_snake_case)]
     fn hasPart(&self, dty: DataType, left: &OperandValue, right: &OperandValue,de: &Option<String>) -> Result<bool, anyhow::Error> {
         match dty {
@@ -1122,23 +439,8 @@ impl ConstraintOperator {
             | DataType::Date
             | DataType::Time
             => {
-                let mut  left = left.get_sval();
-                if left.is_none() {
-                    //try to use de
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_set();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                Ok(right.contains(&left))
+                let (left, right) = concepts(left, right, de)?;
+                Ok(right.iter().all(|part| left.iter().any(|whole| GLOBAL_CONCEPT_HIERARCHY.is_within(part, whole))))
             },
             _ => {
                 Err(anyhow::anyhow!("Invalid operator"))
@@ -1154,22 +456,8 @@ impl ConstraintOperator {
             | DataType::Date
             | DataType::Time
             => {
-                let mut left = left.get_sval();
-                if left.is_none() {
-                    if de.is_none() {
-                        return Err(anyhow::anyhow!("Left operand is None"));
-                    }
-                    left = de.clone();
-                }
-                let left = left.unwrap();
-
-                let right = right.get_set();
-                if right.is_none() {
-                    return Err(anyhow::anyhow!("Right operand is None"));
-                }
-                let right = right.unwrap();
-
-                Ok(right.contains(&left))
+                let (left, right) = concepts(left, right, de)?;
+                Ok(left.iter().all(|part| right.iter().any(|whole| GLOBAL_CONCEPT_HIERARCHY.is_within(part, whole))))
             }
             _ => {
                 Err(anyhow::anyhow!("Invalid operator"))
@@ -1305,7 +593,9 @@ impl TryFrom<&str> for ConstraintLogicOperator {
 #[cfg(test)]
 mod tests {
     use chrono::{NaiveDate, NaiveDateTime};
-    use crate::model::constraint_operator::parse_datetime;
+    use crate::model::constraint_operator::ConstraintOperator;
+    use crate::model::data_type::DataType;
+    use crate::reference::types::{OperandValue, TypedValue};
 
     #[test]
     fn test_parse_operator() {
@@ -1320,18 +610,53 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_datetime() {
-        let t1 = parse_datetime("2025-3-24 00:00:00");
-        let t2 = parse_datetime("2025-3-24");
-        let t3 = parse_datetime("10:00:00");
-        let t4 = parse_datetime("11:00:00");
-        let t5 = parse_datetime("PT1H");
+    fn test_compare_datetime() {
+        let value = |sval: &str| {
+            let mut value = OperandValue::default();
+            value.set_sval(Some(sval.to_string()));
+            value
+        };
+        let morning = value("2025-03-24T10:00:00+01:00");
+
+        //instants compare across timezones, epoch milliseconds as the world reports them too
+        assert!(ConstraintOperator::gt.eval(DataType::DateTime, &morning, &value("2025-03-24T08:30:00Z"), &None));
+        assert!(ConstraintOperator::eq.eval(DataType::DateTime, &morning, &value("2025-03-24T09:00:00Z"), &None));
+        assert!(ConstraintOperator::lt.eval(DataType::DateTime, &value("1742806800000"), &value("2025-03-24T09:00:01Z"), &None));
+        assert!(!ConstraintOperator::gt.eval(DataType::DateTime, &morning, &value("yesterday"), &None));
+
+        //a date is the calendar day in its timezone
+        let late = value("2025-03-24T23:30:00-02:00");
+        assert!(ConstraintOperator::eq.eval(DataType::Date, &late, &value("2025-03-24-02:00"), &None));
+        assert!(ConstraintOperator::gt.eval(DataType::Date, &late, &value("2025-03-24"), &None));
+        assert!(ConstraintOperator::lteq.eval(DataType::Date, &morning, &value("2025-3-24"), &None));
+
+        //a time is the time of day in its timezone
+        assert!(ConstraintOperator::lt.eval(DataType::Time, &morning, &value("10:00:00Z"), &None));
+        assert!(ConstraintOperator::eq.eval(DataType::Time, &morning, &value("10:00:00+01:00"), &None));
+        assert!(ConstraintOperator::neq.eval(DataType::Time, &morning, &value("10:00:00"), &None));
+    }
 
-        //print each time
-        println!("{:?}", t1);
-        println!("{:?}", t2);
-        println!("{:?}", t3);
-        println!("{:?}", t4);
-        println!("{:?}", t5);
+    #[test]
+    fn test_compare_number() {
+        let value = |sval: &str| {
+            let mut value = OperandValue::default();
+            value.set_sval(Some(sval.to_string()));
+            value
+        };
+
+        //equal floats are neither greater nor different
+        assert!(ConstraintOperator::gteq.eval(DataType::Float, &value("2.5"), &value("2.5"), &None));
+        assert!(ConstraintOperator::lteq.eval(DataType::Float, &value("2.5"), &value("2.5"), &None));
+        assert!(!ConstraintOperator::neq.eval(DataType::Float, &value("2.5"), &value("2.5"), &None));
+        assert!(ConstraintOperator::gt.eval(DataType::Float, &value("2.5000001"), &value("2.5"), &None));
+
+        //untyped and typed operands agree, an integer against a duration is milliseconds
+        assert!(ConstraintOperator::lt.eval(DataType::Integer, &value("3600000"), &value("PT2H"), &None));
+        assert!(ConstraintOperator::eq.eval(DataType::Integer, &value("7200000"), &value("PT2H"), &None));
+        let mut typed = value("3");
+        typed.typed = Some(TypedValue::Integer(3));
+        assert!(ConstraintOperator::lt.eval(DataType::Integer, &typed, &value("5"), &None));
+        assert!(ConstraintOperator::lt.eval(DataType::Integer, &value("3"), &value("5"), &None));
+        assert!(ConstraintOperator::gteq.eval(DataType::Float, &typed, &value("3.0"), &None));
     }
-}
\ No newline at end of file
+}
@@ -18,7 +18,7 @@
 use iref::IriBuf;
 use lombok::{Builder, Getter, GetterMut, Setter};
 use crate::model::stateworld::StateWorld;
-use crate::reference::types::{OperandValue, OperandValueType};
+use crate::reference::types::{OperandValue, OperandValueType, TypedValue};
 
 #[derive(Debug,Default, Clone)]
 pub enum RightOperandType {
@@ -38,7 +38,9 @@ pub struct ConstraintRightOperand {
     pub ty: RightOperandType,
     pub value: Option<String>,
     pub values: Option<Vec<String>>,
-    pub reference: Option<RightOperandReference>
+    pub reference: Option<RightOperandReference>,
+    //literal with a datatype or language tag, parsed when the policy is loaded
+    pub typed: Option<TypedValue>,
 }
 
 impl  ConstraintRightOperand {
@@ -48,6 +50,7 @@ impl  ConstraintRightOperand {
                 let mut val = OperandValue::default();
                 val.set_ty(OperandValueType::string);
                 val.set_sval(self.value.clone());
+                val.set_typed(self.typed.clone());
                 Ok(val)
             },
             RightOperandType::LiteralSet => {
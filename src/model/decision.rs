@@ -0,0 +1,133 @@
+// Copyright 2024 meduo <gao.brian@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(dead_code)]
+
+use iref::IriBuf;
+use lombok::{Builder, Getter, GetterMut, Setter};
+use crate::model::conflict_strategy::ConflictStrategy;
+
+//Outcome of evaluating a request against a policy
+#[derive(Debug,Default,Clone,PartialEq)]
+pub enum DecisionEffect {
+    //a permission matched and was not overridden by a prohibition
+    Permit,
+    //a prohibition won, a matching permission failed its constraints or duties,
+    //the policy constraints failed, or an obligation of the requesting party was violated
+    Deny,
+    //no rule of the policy applies to the request
+    #[default]
+    NotApplicable,
+    //the request or the policy could not be evaluated
+    Indeterminate,
+}
+
+#[derive(Debug,Default,Clone,PartialEq)]
+pub enum RuleKind {
+    #[default]
+    Permission,
+    Prohibition,
+    Obligation,
+    //the policy itself, for policy level constraints
+    Policy,
+}
+
+//Each check done while matching a rule against a request
+#[derive(Debug,Default,Clone,PartialEq)]
+pub enum EvalStep {
+    #[default]
+    Assignee,
+    Assigner,
+    Action,
+    Target,
+    Constraint,
+    Duty,
+}
+
+#[derive(Debug,Default,Builder,Getter,Setter,Clone)]
+pub struct TraceEntry {
+    pub kind: RuleKind,
+    //uid of the rule being checked, if it declares one
+    pub rule: Option<IriBuf>,
+    pub step: EvalStep,
+    pub passed: bool,
+}
+
+//A constraint which could not be evaluated, e.g. a missing operand or an unknown data type
+#[derive(Debug,Default,Builder,Getter,Setter,Clone,PartialEq)]
+pub struct Indeterminate {
+    pub kind: RuleKind,
+    //uid of the rule being checked, if it declares one
+    pub rule: Option<IriBuf>,
+    //uid of the constraint, if it declares one
+    pub constraint: Option<IriBuf>,
+    pub reason: String,
+}
+
+#[derive(Debug,Default,Builder,Getter,GetterMut,Setter,Clone)]
+pub struct Decision {
+    pub effect: DecisionEffect,
+    //uid of the permission which granted the request
+    pub permission: Option<IriBuf>,
+    //uid of the prohibition which matched the request
+    pub prohibition: Option<IriBuf>,
+    //conflict strategy in effect when the decision was made
+    pub conflict: Option<ConflictStrategy>,
+    //reason for Indeterminate or Deny decisions
+    pub reason: Option<String>,
+    pub trace: Vec<TraceEntry>,
+    //constraints which could not be evaluated, they count as not satisfied
+    pub indeterminate: Vec<Indeterminate>,
+}
+
+impl Decision {
+    pub fn new(effect: DecisionEffect) -> Self {
+        Decision {
+            effect,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_permitted(&self) -> bool {
+        self.effect == DecisionEffect::Permit
+    }
+
+    pub fn record(&mut self, kind: RuleKind, rule: &Option<IriBuf>, step: EvalStep, passed: bool) {
+        self.trace.push(TraceEntry {
+            kind,
+            rule: rule.clone(),
+            step,
+            passed,
+        });
+    }
+
+    //Attribute constraints found indeterminate while checking a rule
+    pub fn add_indeterminate(&mut self, kind: RuleKind, rule: &Option<IriBuf>, found: Vec<Indeterminate>) {
+        for mut indeterminate in found {
+            indeterminate.kind = kind.clone();
+            indeterminate.rule = rule.clone();
+            self.indeterminate.push(indeterminate);
+        }
+    }
+
+    pub fn with_effect(mut self, effect: DecisionEffect) -> Self {
+        self.effect = effect;
+        self
+    }
+
+    pub fn with_reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_string());
+        self
+    }
+}
@@ -17,12 +17,19 @@
 
 use lombok::{Builder, Getter, GetterMut, Setter};
 use crate::model::asset::{AssetUnion};
-use crate::model::party::{PartyUnion};
+use crate::model::constraint::Verdict;
+use crate::traits::definions::LogicEval;
+use crate::model::party::{Party, PartyUnion};
+use crate::model::stateworld::{DutyState, StateWorld};
 use super::rule::Rule;
 
 #[derive(Debug,Default,Builder,Getter,GetterMut,Setter, Clone)]
 pub struct Duty {
     pub rule: Rule,
+    //duties which become due when this one is violated
+    pub consequence: Option<Vec<Duty>>,
+    //position of the duty in its policy, assigned when the policy is compiled
+    pub identity: Option<String>,
 }
 
 impl Duty {
@@ -45,4 +52,127 @@ impl Duty {
     pub fn get_target(&self) -> &Option<AssetUnion> {
         self.rule.get_target()
     }
-}
\ No newline at end of file
+}
+#[derive(Debug,Default,Clone)]
+pub struct DutyInferencer;
+
+impl DutyInferencer {
+    //A duty is tracked in the world by its uid, or by its position in the policy when it has none
+    pub fn duty_key(duty: &Duty) -> String {
+        if let Some(uid) = duty.rule.get_uid() {
+            return uid.as_str().to_string();
+        }
+        if let Some(identity) = duty.get_identity() {
+            return identity.clone();
+        }
+        match duty.rule.get_action() {
+            Some(action) => action.actionType.to_iri(),
+            None => String::new(),
+        }
+    }
+
+    //The party owing the duty, the duty assignee if set, otherwise the requesting party
+    pub fn duty_party(duty: &Duty, candidate: &Option<Party>) -> String {
+        let fallback = match candidate {
+            Some(party) => party.get_uid().as_ref().map(|uid| uid.as_str().to_string()).unwrap_or_default(),
+            None => String::new(),
+        };
+        Self::owner(duty, &fallback)
+    }
+
+    fn owner(duty: &Duty, fallback: &str) -> String {
+        if let Some(PartyUnion::Party(party)) = duty.get_assignee() {
+            if let Some(uid) = party.get_uid() {
+                return uid.as_str().to_string();
+            }
+        }
+        fallback.to_string()
+    }
+
+    //A duty is in force unless its constraints are known not to hold, ex: a duty to be fulfilled
+    //before a deadline, an indeterminate constraint keeps it in force and is kept in the world
+    fn in_force(world: &mut StateWorld, duty: &Duty) -> Result<bool, anyhow::Error> {
+        let mut verdict = Verdict::True;
+        for constraint in duty.rule.get_constraint().iter().flatten() {
+            verdict = verdict.and(constraint.verdict(world)?);
+        }
+        match verdict {
+            Verdict::False => Ok(false),
+            Verdict::True => Ok(true),
+            Verdict::Indeterminate(found) => {
+                world.add_indeterminate(found);
+                Ok(true)
+            }
+        }
+    }
+
+    //Check whether the duty is discharged, an unknown duty in force is registered as pending
+    pub fn infer(world: &mut StateWorld, duty: &Duty, candidate: &Option<Party>) -> Result<bool, anyhow::Error> {
+        let party = Self::duty_party(duty, candidate);
+        let key = Self::duty_key(duty);
+
+        match world.get_duty_state(&party, &key) {
+            Some(DutyState::Fulfilled) | Some(DutyState::Accepted) => Ok(true),
+            //a pending duty whose constraints no longer hold is not owed
+            Some(DutyState::Pending) => Ok(!Self::in_force(world, duty)?),
+            //a violated duty is discharged once all its consequences are
+            Some(DutyState::Violated) => {
+                match duty.get_consequence() {
+                    Some(consequences) if !consequences.is_empty() => {
+                        Self::infer_all(world, consequences, candidate)
+                    }
+                    _ => Ok(false),
+                }
+            }
+            None => {
+                if !Self::in_force(world, duty)? {
+                    return Ok(true);
+                }
+                world.set_duty_state(&party, &key, DutyState::Pending);
+                Ok(false)
+            }
+        }
+    }
+
+    //All duties must be discharged, every one is visited so each gets registered
+    pub fn infer_all(world: &mut StateWorld, duties: &Vec<Duty>, candidate: &Option<Party>) -> Result<bool, anyhow::Error> {
+        let mut discharged = true;
+        for duty in duties {
+            if !Self::infer(world, duty, candidate)? {
+                discharged = false;
+            }
+        }
+        Ok(discharged)
+    }
+
+    //Whether the duty was violated and its consequences are still outstanding
+    pub fn is_violated(world: &mut StateWorld, duty: &Duty, candidate: &Option<Party>) -> Result<bool, anyhow::Error> {
+        let party = Self::duty_party(duty, candidate);
+        let key = Self::duty_key(duty);
+
+        match world.get_duty_state(&party, &key) {
+            Some(DutyState::Violated) => Ok(!Self::infer(world, duty, candidate)?),
+            Some(_) => Ok(false),
+            None => {
+                if Self::in_force(world, duty)? {
+                    world.set_duty_state(&party, &key, DutyState::Pending);
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    //Mark the duty violated for the party, its consequences become pending
+    pub fn violate(world: &mut StateWorld, duty: &Duty, party: &str) {
+        world.set_duty_state(party, &Self::duty_key(duty), DutyState::Violated);
+        if let Some(consequences) = duty.get_consequence() {
+            for consequence in consequences {
+                let owner = Self::owner(consequence, party);
+                let key = Self::duty_key(consequence);
+                if world.get_duty_state(&owner, &key).is_none() {
+                    world.set_duty_state(&owner, &key, DutyState::Pending);
+                }
+            }
+        }
+    }
+}
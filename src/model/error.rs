@@ -47,6 +47,48 @@ pub enum OdrlError {
     #[error("Rule must has a valid action")]
     MissingAction,
 
+    #[error("Unknown action {0}")]
+    UnknownAction(String),
+
+    #[error("Unknown operator {0}")]
+    UnknownOperator(String),
+
+    #[error("Action {0} is not a custom action")]
+    InvalidCustomAction(String),
+
+    #[error("Profile {0} is not registered")]
+    UnknownProfile(String),
+
+    #[error("Policy violates profile {0}: {1}")]
+    ProfileViolation(String, String),
+
+    #[error("Inherited policy {0} not found in policy store")]
+    MissingInheritedPolicy(String),
+
+    #[error("Policy {0} inherits from itself")]
+    InheritanceCycle(String),
+
+    #[error("Inherited policies of {0} declare different conflict strategies")]
+    InheritedConflictMismatch(String),
+
+    #[error("Policy {0} is void, {1} permission and prohibition pairs conflict")]
+    ConflictingRules(String, usize),
+
+    #[error("Unknown unit {0}")]
+    UnknownUnit(String),
+
+    #[error("Value compared with a quantity in {0} has no unit")]
+    MissingUnit(String),
+
+    #[error("Units {0} and {1} can not be compared")]
+    UnitMismatch(String, String),
+
+    #[error("Values {0} and {1} can not be compared")]
+    IncomparableValues(String, String),
+
+    #[error("Schedule {0} is invalid: {1}")]
+    InvalidSchedule(String, String),
+
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
\ No newline at end of file
@@ -12,10 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::model::decision::Decision;
 use crate::model::policy::{OdrlRequest};
 use crate::model::stateworld::StateWorld;
 
 
 pub trait Evaluator {
-    fn eval(&self,world: &mut StateWorld, req: &OdrlRequest) -> Result<bool, anyhow::Error>;
+    fn decide(&self,world: &mut StateWorld, req: &OdrlRequest) -> Result<Decision, anyhow::Error>;
+
+    fn eval(&self,world: &mut StateWorld, req: &OdrlRequest) -> Result<bool, anyhow::Error> {
+        let decision = self.decide(world,req)?;
+        Ok(decision.is_permitted())
+    }
 }
\ No newline at end of file
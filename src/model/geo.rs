@@ -0,0 +1,657 @@
+// Copyright 2024 meduo <gao.brian@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(dead_code)]
+
+use std::sync::Arc;
+use anyhow::anyhow;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use crate::model::constraint_operator::ConstraintOperator;
+use crate::reference::types::OperandValue;
+
+//Coarse outlines of european countries and the groups they belong to, keyed by ISO 3166 code.
+//They are illustrative only: borders are off by kilometres and there are no ISO 3166-2 subdivisions,
+//real deployments load an admin-0/admin-1 dataset such as Natural Earth with RegionRegistry::load_geojson
+const BUNDLED_REGIONS: &str = include_str!("../data/regions.geojson");
+
+//Longitude and latitude in degrees (WGS 84)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinate {
+    pub lon: f64,
+    pub lat: f64,
+}
+
+impl Coordinate {
+    pub fn new(lon: f64, lat: f64) -> Result<Self, anyhow::Error> {
+        if !(-180.0..=180.0).contains(&lon) || !(-90.0..=90.0).contains(&lat) {
+            return Err(anyhow!("coordinate out of range: {} {}", lon, lat));
+        }
+        Ok(Coordinate { lon, lat })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    pub exterior: Vec<Coordinate>,
+    pub holes: Vec<Vec<Coordinate>>,
+}
+
+impl Polygon {
+    fn contains_point(&self, point: &Coordinate) -> bool {
+        ring_contains(&self.exterior, point) && !self.holes.iter().any(|hole| ring_contains(hole, point))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Geometry {
+    Point(Coordinate),
+    Polygon(Polygon),
+    MultiPolygon(Vec<Polygon>),
+}
+
+impl Geometry {
+    //Parse a WKT or GeoJSON geometry, or a "lat,lon" coordinate (optionally as a geo: uri)
+    pub fn parse(value: &str) -> Result<Self, anyhow::Error> {
+        let value = value.trim();
+        if value.starts_with('{') {
+            let json: Value = serde_json::from_str(value)?;
+            return Geometry::from_geojson(&json);
+        }
+        if value.starts_with(|c: char| c.is_ascii_alphabetic()) && !value.starts_with("geo:") {
+            return Geometry::from_wkt(value);
+        }
+        Geometry::from_lat_lon(value)
+    }
+
+    fn from_lat_lon(value: &str) -> Result<Self, anyhow::Error> {
+        let value = value.strip_prefix("geo:").unwrap_or(value);
+        //uncertainty and other geo uri parameters are ignored
+        let value = value.split(';').next().unwrap_or_default();
+        let parts: Vec<&str> = value.split(',').map(|part| part.trim()).collect();
+        if parts.len() < 2 || parts.len() > 3 {
+            return Err(anyhow!("invalid coordinate: {}", value));
+        }
+        let lat: f64 = parts[0].parse()?;
+        let lon: f64 = parts[1].parse()?;
+        Ok(Geometry::Point(Coordinate::new(lon, lat)?))
+    }
+
+    pub fn from_wkt(value: &str) -> Result<Self, anyhow::Error> {
+        let open = value.find('(').ok_or_else(|| anyhow!("invalid wkt: {}", value))?;
+        let keyword = value[..open].trim().to_uppercase();
+        let mut parser = WktParser { chars: value[open..].chars().collect(), pos: 0 };
+        let body = parser.parse()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.chars.len() {
+            return Err(anyhow!("invalid wkt: {}", value));
+        }
+
+        match keyword.as_str() {
+            "POINT" => match body.as_slice() {
+                [WktNode::Coordinate(point)] => Ok(Geometry::Point(*point)),
+                _ => Err(anyhow!("invalid wkt point: {}", value)),
+            },
+            "POLYGON" => Ok(Geometry::Polygon(wkt_polygon(&body)?)),
+            "MULTIPOLYGON" => {
+                let polygons = body.iter()
+                    .map(|polygon| match polygon {
+                        WktNode::List(rings) => wkt_polygon(rings),
+                        _ => Err(anyhow!("invalid wkt multipolygon")),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Geometry::MultiPolygon(polygons))
+            }
+            _ => Err(anyhow!("unsupported wkt geometry: {}", keyword)),
+        }
+    }
+
+    pub fn from_geojson(json: &Value) -> Result<Self, anyhow::Error> {
+        let ty = json.get("type").and_then(|ty| ty.as_str()).unwrap_or_default();
+        match ty {
+            "Feature" => Geometry::from_geojson(json.get("geometry").unwrap_or(&Value::Null)),
+            "FeatureCollection" => {
+                let features = json.get("features").and_then(|features| features.as_array())
+                    .ok_or_else(|| anyhow!("geojson feature collection without features"))?;
+                let mut polygons = vec![];
+                for feature in features {
+                    match Geometry::from_geojson(feature)? {
+                        Geometry::Polygon(polygon) => polygons.push(polygon),
+                        Geometry::MultiPolygon(parts) => polygons.extend(parts),
+                        Geometry::Point(_) => return Err(anyhow!("geojson feature collection must only hold areas")),
+                    }
+                }
+                Ok(Geometry::MultiPolygon(polygons))
+            }
+            "Point" => Ok(Geometry::Point(geojson_position(json.get("coordinates"))?)),
+            "Polygon" => Ok(Geometry::Polygon(geojson_polygon(json.get("coordinates"))?)),
+            "MultiPolygon" => {
+                let polygons = json.get("coordinates").and_then(|coordinates| coordinates.as_array())
+                    .ok_or_else(|| anyhow!("geojson multipolygon without coordinates"))?
+                    .iter()
+                    .map(|polygon| geojson_polygon(Some(polygon)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Geometry::MultiPolygon(polygons))
+            }
+            _ => Err(anyhow!("unsupported geojson geometry: {}", ty)),
+        }
+    }
+
+    pub fn contains_point(&self, point: &Coordinate) -> bool {
+        match self {
+            Geometry::Point(own) => own == point,
+            Geometry::Polygon(polygon) => polygon.contains_point(point),
+            Geometry::MultiPolygon(polygons) => polygons.iter().any(|polygon| polygon.contains_point(point)),
+        }
+    }
+
+    //An area is contained when all of its outline is, which is exact for convex containers only
+    pub fn contains(&self, other: &Geometry) -> bool {
+        other.outline().iter().all(|point| self.contains_point(point))
+    }
+
+    fn outline(&self) -> Vec<Coordinate> {
+        match self {
+            Geometry::Point(point) => vec![*point],
+            Geometry::Polygon(polygon) => polygon.exterior.clone(),
+            Geometry::MultiPolygon(polygons) => polygons.iter().flat_map(|polygon| polygon.exterior.clone()).collect(),
+        }
+    }
+}
+
+//Ray casting, points on the outline may fall on either side
+fn ring_contains(ring: &[Coordinate], point: &Coordinate) -> bool {
+    let mut inside = false;
+    let mut j = ring.len().wrapping_sub(1);
+    for i in 0..ring.len() {
+        let (a, b) = (ring[i], ring[j]);
+        if (a.lat > point.lat) != (b.lat > point.lat)
+            && point.lon < (b.lon - a.lon) * (point.lat - a.lat) / (b.lat - a.lat) + a.lon {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+enum WktNode {
+    Coordinate(Coordinate),
+    List(Vec<WktNode>),
+}
+
+struct WktParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl WktParser {
+    //A parenthesized list of coordinates or of nested lists
+    fn parse(&mut self) -> Result<Vec<WktNode>, anyhow::Error> {
+        self.expect('(')?;
+        let mut nodes = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some('(') {
+                nodes.push(WktNode::List(self.parse()?));
+            } else {
+                nodes.push(WktNode::Coordinate(self.coordinate()?));
+            }
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(')') => return Ok(nodes),
+                _ => return Err(anyhow!("invalid wkt")),
+            }
+        }
+    }
+
+    //WKT orders longitude first
+    fn coordinate(&mut self) -> Result<Coordinate, anyhow::Error> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c != ',' && c != ')') {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        let numbers = text.split_whitespace().map(|number| number.parse::<f64>()).collect::<Result<Vec<_>, _>>()?;
+        if numbers.len() < 2 {
+            return Err(anyhow!("invalid wkt coordinate: {}", text));
+        }
+        Coordinate::new(numbers[0], numbers[1])
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), anyhow::Error> {
+        self.skip_whitespace();
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(anyhow!("invalid wkt, expected {}", expected)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+}
+
+fn wkt_polygon(rings: &[WktNode]) -> Result<Polygon, anyhow::Error> {
+    let mut rings = rings.iter().map(|ring| match ring {
+        WktNode::List(points) => points.iter().map(|point| match point {
+            WktNode::Coordinate(coordinate) => Ok(*coordinate),
+            WktNode::List(_) => Err(anyhow!("invalid wkt ring")),
+        }).collect::<Result<Vec<_>, _>>(),
+        WktNode::Coordinate(_) => Err(anyhow!("invalid wkt polygon")),
+    }).collect::<Result<Vec<_>, _>>()?;
+    if rings.is_empty() {
+        return Err(anyhow!("wkt polygon without rings"));
+    }
+    let exterior = rings.remove(0);
+    Ok(Polygon { exterior, holes: rings })
+}
+
+//GeoJSON orders longitude first
+fn geojson_position(position: Option<&Value>) -> Result<Coordinate, anyhow::Error> {
+    let position = position.and_then(|position| position.as_array())
+        .ok_or_else(|| anyhow!("invalid geojson position"))?;
+    match (position.first().and_then(|lon| lon.as_f64()), position.get(1).and_then(|lat| lat.as_f64())) {
+        (Some(lon), Some(lat)) => Coordinate::new(lon, lat),
+        _ => Err(anyhow!("invalid geojson position")),
+    }
+}
+
+fn geojson_polygon(rings: Option<&Value>) -> Result<Polygon, anyhow::Error> {
+    let mut rings = rings.and_then(|rings| rings.as_array())
+        .ok_or_else(|| anyhow!("invalid geojson polygon"))?
+        .iter()
+        .map(|ring| ring.as_array()
+            .ok_or_else(|| anyhow!("invalid geojson ring"))?
+            .iter()
+            .map(|position| geojson_position(Some(position)))
+            .collect::<Result<Vec<_>, _>>())
+        .collect::<Result<Vec<_>, _>>()?;
+    if rings.is_empty() {
+        return Err(anyhow!("geojson polygon without rings"));
+    }
+    let exterior = rings.remove(0);
+    Ok(Polygon { exterior, holes: rings })
+}
+
+//A named area, either with an outline or as a group of other regions
+#[derive(Debug, Clone, Default)]
+pub struct Region {
+    pub code: String,
+    pub name: String,
+    pub geometry: Option<Geometry>,
+    pub members: Vec<String>,
+    //ISO 3166 regions are compared by code, not by their simplified outline
+    pub iso: bool,
+}
+
+//Named regions, ISO 3166 codes from the bundled dataset and regions registered by the application
+pub struct RegionRegistry {
+    regions: DashMap<String, Region>,
+}
+
+impl RegionRegistry {
+    pub fn new() -> Self {
+        RegionRegistry {
+            regions: DashMap::new(),
+        }
+    }
+
+    //Registry holding the bundled, illustrative ISO 3166 regions
+    pub fn bundled() -> Result<Self, anyhow::Error> {
+        let registry = RegionRegistry::new();
+        registry.load_geojson(BUNDLED_REGIONS)?;
+        Ok(registry)
+    }
+
+    //Load an ISO 3166 dataset as a geojson feature collection, regions with the same code are replaced.
+    //Codes are read from the "code" property or from the Natural Earth "iso_3166_2", "ISO_A2" and "ISO_A2_EH" ones,
+    //features without a code are skipped. Returns the number of regions loaded
+    pub fn load_geojson(&self, geojson: &str) -> Result<usize, anyhow::Error> {
+        let json: Value = serde_json::from_str(geojson)?;
+        let features = json.get("features").and_then(|features| features.as_array())
+            .ok_or_else(|| anyhow!("region dataset is not a geojson feature collection"))?;
+        let mut loaded = 0;
+        for feature in features {
+            let properties = feature.get("properties").unwrap_or(&Value::Null);
+            let code = ["code", "iso_3166_2", "ISO_A2", "ISO_A2_EH"].iter()
+                .filter_map(|key| properties.get(*key).and_then(|code| code.as_str()))
+                //natural earth marks missing codes with -99
+                .find(|code| !code.is_empty() && *code != "-99");
+            let code = match code {
+                Some(code) => code,
+                None => continue,
+            };
+            let geometry = match feature.get("geometry") {
+                Some(Value::Null) | None => None,
+                Some(geometry) => Some(Geometry::from_geojson(geometry)?),
+            };
+            let members = properties.get("members").and_then(|members| members.as_array()).into_iter().flatten()
+                .filter_map(|member| member.as_str().map(|member| member.to_string()))
+                .collect();
+            let name = ["name", "NAME"].iter()
+                .find_map(|key| properties.get(*key).and_then(|name| name.as_str()))
+                .unwrap_or(code);
+            self.register(Region {
+                code: code.to_string(),
+                name: name.to_string(),
+                geometry,
+                members,
+                iso: true,
+            });
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+
+    pub fn load_file(&self, path: &str) -> Result<usize, anyhow::Error> {
+        self.load_geojson(&std::fs::read_to_string(path)?)
+    }
+
+    //Drop every region, ex: before loading a dataset which replaces the bundled one
+    pub fn clear(&self) {
+        self.regions.clear();
+    }
+
+    //Codes are matched case-insensitively, a region with the same code is replaced
+    pub fn register(&self, region: Region) {
+        self.regions.insert(region.code.to_uppercase(), region);
+    }
+
+    pub fn register_geometry(&self, code: &str, geometry: Geometry) {
+        self.register(Region { code: code.to_string(), name: code.to_string(), geometry: Some(geometry), members: vec![], iso: false });
+    }
+
+    pub fn unregister(&self, code: &str) {
+        self.regions.remove(&code.to_uppercase());
+    }
+
+    pub fn get(&self, code: &str) -> Option<Region> {
+        self.regions.get(&code.to_uppercase()).map(|region| region.value().clone())
+    }
+
+    //Known region, ISO 3166-2 subdivisions are only known once a dataset holding them is loaded
+    pub fn is_known(&self, code: &str) -> bool {
+        self.get(code).is_some()
+    }
+
+    pub fn is_iso(&self, code: &str) -> bool {
+        self.get(code).is_some_and(|region| region.iso)
+    }
+
+    //Country of an ISO 3166-2 subdivision code of a known country, by syntax only
+    fn subdivision_country(&self, code: &str) -> Option<String> {
+        let (country, subdivision) = code.split_once('-')?;
+        let valid = country.len() == 2
+            && (1..=3).contains(&subdivision.len())
+            && subdivision.chars().all(|c| c.is_ascii_alphanumeric());
+        (valid && self.get(country).is_some()).then(|| country.to_uppercase())
+    }
+
+    //Whether the inner region lies within the outer one, by code hierarchy:
+    //a subdivision is part of its country, and members are part of their group
+    pub fn region_within(&self, inner: &str, outer: &str) -> bool {
+        self.region_within_visited(inner, outer, &mut vec![])
+    }
+
+    fn region_within_visited(&self, inner: &str, outer: &str, visited: &mut Vec<String>) -> bool {
+        let outer = outer.to_uppercase();
+        if inner.eq_ignore_ascii_case(&outer) {
+            return true;
+        }
+        if visited.contains(&outer) {
+            return false;
+        }
+        visited.push(outer.clone());
+
+        if let Some(country) = self.subdivision_country(inner) {
+            if self.region_within_visited(&country, &outer, visited) {
+                return true;
+            }
+        }
+        match self.get(&outer) {
+            Some(region) => region.members.iter().any(|member| self.region_within_visited(inner, member, visited)),
+            None => false,
+        }
+    }
+
+    //Outline of the region, a group is the union of its members
+    pub fn geometry(&self, code: &str) -> Option<Geometry> {
+        let region = self.get(code)?;
+        if let Some(geometry) = region.geometry {
+            return Some(geometry);
+        }
+        let mut polygons = vec![];
+        for member in &region.members {
+            match self.geometry(member)? {
+                Geometry::Polygon(polygon) => polygons.push(polygon),
+                Geometry::MultiPolygon(parts) => polygons.extend(parts),
+                Geometry::Point(_) => return None,
+            }
+        }
+        (!polygons.is_empty()).then_some(Geometry::MultiPolygon(polygons))
+    }
+}
+
+impl Default for RegionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub static GLOBAL_REGION_REGISTRY: Lazy<Arc<RegionRegistry>> = Lazy::new(|| {
+    Arc::new(RegionRegistry::bundled().expect("bundled regions are valid geojson"))
+});
+
+//A spatial operand, either a named region or a literal geometry
+#[derive(Debug, Clone)]
+pub enum SpatialValue {
+    Region(String),
+    Geometry(Geometry),
+}
+
+impl SpatialValue {
+    pub fn parse(value: &str) -> Result<Self, anyhow::Error> {
+        let value = value.trim();
+        if GLOBAL_REGION_REGISTRY.is_known(value) {
+            return Ok(SpatialValue::Region(value.to_string()));
+        }
+        //a subdivision without outline can not be evaluated, it is not mistaken for its country
+        if GLOBAL_REGION_REGISTRY.subdivision_country(value).is_some() {
+            return Err(anyhow!("region {} is not in the loaded dataset, subdivisions need an admin-1 dataset", value));
+        }
+        Ok(SpatialValue::Geometry(Geometry::parse(value)?))
+    }
+
+    fn geometry(&self) -> Result<Geometry, anyhow::Error> {
+        match self {
+            SpatialValue::Region(code) => GLOBAL_REGION_REGISTRY.geometry(code)
+                .ok_or_else(|| anyhow!("region {} has no geometry", code)),
+            SpatialValue::Geometry(geometry) => Ok(geometry.clone()),
+        }
+    }
+
+    //Two named regions are compared by code hierarchy, anything else by geometry
+    pub fn within(&self, outer: &SpatialValue) -> Result<bool, anyhow::Error> {
+        if let (SpatialValue::Region(inner), SpatialValue::Region(outer)) = (self, outer) {
+            if GLOBAL_REGION_REGISTRY.region_within(inner, outer) {
+                return Ok(true);
+            }
+            if GLOBAL_REGION_REGISTRY.is_iso(inner) && GLOBAL_REGION_REGISTRY.is_iso(outer) {
+                return Ok(false);
+            }
+        }
+        Ok(outer.geometry()?.contains(&self.geometry()?))
+    }
+}
+
+//Containment semantics of isPartOf, isA and hasPart for spatial left operands
+pub struct SpatialInferencer;
+
+impl SpatialInferencer {
+    pub fn applies(operator: &ConstraintOperator) -> bool {
+        matches!(operator, ConstraintOperator::isPartOf | ConstraintOperator::isA | ConstraintOperator::hasPart)
+    }
+
+    //Every left value must lie within one of the right values, or contain it for hasPart
+    pub fn eval(operator: &ConstraintOperator, left: &OperandValue, right: &OperandValue) -> Result<bool, anyhow::Error> {
+        let left = SpatialInferencer::values(left)?;
+        let right = SpatialInferencer::values(right)?;
+        if left.is_empty() || right.is_empty() {
+            return Err(anyhow!("spatial operand is empty"));
+        }
+
+        for inner in &left {
+            let mut matched = false;
+            for outer in &right {
+                let within = match operator {
+                    ConstraintOperator::hasPart => outer.within(inner)?,
+                    _ => inner.within(outer)?,
+                };
+                if within {
+                    matched = true;
+                    break;
+                }
+            }
+            if !matched {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn values(value: &OperandValue) -> Result<Vec<SpatialValue>, anyhow::Error> {
+        let values = match (value.get_set(), value.get_sval()) {
+            (Some(set), _) => set,
+            (None, Some(value)) => vec![value],
+            (None, None) => vec![],
+        };
+        values.iter().map(|value| SpatialValue::parse(value)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::constraint_operator::ConstraintOperator;
+    use crate::model::geo::{Geometry, SpatialInferencer, SpatialValue, GLOBAL_REGION_REGISTRY};
+    use crate::reference::types::{OperandValue, OperandValueType};
+
+    fn value(value: &str) -> OperandValue {
+        let mut val = OperandValue::default();
+        val.set_ty(OperandValueType::string);
+        val.set_sval(Some(value.to_string()));
+        val
+    }
+
+    fn within(inner: &str, outer: &str) -> bool {
+        SpatialValue::parse(inner).unwrap().within(&SpatialValue::parse(outer).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_geometry() {
+        let paris = Geometry::parse("48.8566, 2.3522").unwrap();
+        assert_eq!(Geometry::parse("geo:48.8566,2.3522;u=35").unwrap(), paris);
+        assert_eq!(Geometry::parse("POINT (2.3522 48.8566)").unwrap(), paris);
+        assert_eq!(Geometry::parse(r#"{"type": "Point", "coordinates": [2.3522, 48.8566]}"#).unwrap(), paris);
+
+        let square = Geometry::parse("POLYGON ((0 0, 10 0, 10 10, 0 10, 0 0), (4 4, 6 4, 6 6, 4 6, 4 4))").unwrap();
+        assert!(square.contains(&Geometry::parse("1, 1").unwrap()));
+        assert!(!square.contains(&Geometry::parse("5, 5").unwrap()));
+        assert_eq!(square, Geometry::parse(r#"{"type": "Feature", "geometry": {"type": "Polygon", "coordinates": [
+            [[0, 0], [10, 0], [10, 10], [0, 10], [0, 0]], [[4, 4], [6, 4], [6, 6], [4, 6], [4, 4]]]}}"#).unwrap());
+        assert!(matches!(Geometry::parse("MULTIPOLYGON (((0 0, 1 0, 1 1, 0 0)), ((5 5, 6 5, 6 6, 5 5)))").unwrap(),
+            Geometry::MultiPolygon(polygons) if polygons.len() == 2));
+
+        assert!(Geometry::parse("91, 0").is_err());
+        assert!(Geometry::parse("POLYGON ((0 0, 1 0, 1 1, 0 0)").is_err());
+        assert!(Geometry::parse("LINESTRING (0 0, 1 1)").is_err());
+    }
+
+    #[test]
+    fn test_regions() {
+        //paris, zurich and a point in the atlantic
+        assert!(within("48.8566,2.3522", "FR"));
+        assert!(within("48.8566,2.3522", "EU"));
+        assert!(!within("47.3769,8.5417", "EU"));
+        assert!(within("47.3769,8.5417", "ch"));
+        assert!(!within("45.0,-30.0", "EEA"));
+
+        //codes follow the hierarchy, subdivisions are rejected unless a dataset holds them
+        assert!(SpatialValue::parse("DE-BY").is_err());
+        assert!(within("NO", "EEA"));
+        assert!(!within("NO", "EU"));
+        assert!(!within("CH", "EEA"));
+        assert!(SpatialValue::parse("XX-ABC").is_err());
+
+        GLOBAL_REGION_REGISTRY.register_geometry("https://example.com/zone/paris",
+            Geometry::parse("POLYGON ((2.2 48.8, 2.5 48.8, 2.5 48.95, 2.2 48.95, 2.2 48.8))").unwrap());
+        assert!(within("https://example.com/zone/paris", "FR"));
+        assert!(within("48.8566,2.3522", "https://example.com/zone/paris"));
+    }
+
+    #[test]
+    fn test_load_dataset() {
+        assert!(SpatialValue::parse("IT-25").is_err());
+
+        //an admin-1 feature as shipped by natural earth, with a coarse outline of lombardy
+        let loaded = GLOBAL_REGION_REGISTRY.load_geojson(r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"iso_3166_2": "IT-25", "name": "Lombardia"},
+             "geometry": {"type": "Polygon", "coordinates": [[[8.5, 44.7], [11.4, 44.7], [11.4, 46.6], [8.5, 46.6], [8.5, 44.7]]]}},
+            {"type": "Feature", "properties": {"ISO_A2": "-99", "NAME": "Unknown"}, "geometry": null}
+        ]}"#).unwrap();
+        assert_eq!(loaded, 1);
+        assert_eq!(GLOBAL_REGION_REGISTRY.get("it-25").unwrap().name, "Lombardia");
+
+        //milan and rome
+        assert!(within("45.4642,9.19", "IT-25"));
+        assert!(!within("41.9028,12.4964", "IT-25"));
+        assert!(within("IT-25", "IT"));
+        assert!(within("IT-25", "EU"));
+        assert!(!within("IT-25", "FR"));
+
+        let registry = super::RegionRegistry::new();
+        assert!(registry.load_geojson(r#"{"type": "Point", "coordinates": [0, 0]}"#).is_err());
+        assert_eq!(registry.load_geojson(r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"ISO_A2": "-99", "ISO_A2_EH": "NO", "NAME": "Norway"}, "geometry": null}
+        ]}"#).unwrap(), 1);
+        assert_eq!(registry.get("NO").unwrap().name, "Norway");
+    }
+
+    #[test]
+    fn test_spatial_operators() {
+        let berlin = value("POINT (13.405 52.52)");
+        let mut eea = OperandValue::default();
+        eea.set_ty(OperandValueType::set);
+        eea.set_sets(Some(vec!["EU".to_string(), "NO".to_string()]));
+
+        assert!(SpatialInferencer::eval(&ConstraintOperator::isPartOf, &berlin, &value("EU")).unwrap());
+        assert!(SpatialInferencer::eval(&ConstraintOperator::isA, &value("59.91,10.75"), &eea).unwrap());
+        assert!(!SpatialInferencer::eval(&ConstraintOperator::isPartOf, &value("46.95,7.45"), &eea).unwrap());
+        assert!(SpatialInferencer::eval(&ConstraintOperator::hasPart, &value("EU"), &berlin).unwrap());
+        assert!(SpatialInferencer::eval(&ConstraintOperator::isPartOf, &value("not a place"), &value("EU")).is_err());
+    }
+}
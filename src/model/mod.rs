@@ -13,7 +13,10 @@
 // limitations under the License.
 
 pub mod action;
+pub mod action_registry;
 pub mod conflict_strategy;
+pub mod conflict_analyzer;
+pub mod concept_hierarchy;
 pub mod constraint_operator;
 pub mod constraint_left_operand;
 pub mod constraint_right_operand;
@@ -21,6 +24,9 @@ pub mod constraint;
 pub mod metadata;
 pub mod asset;
 pub mod policy;
+pub mod policy_store;
+pub mod operand_provider;
+pub mod profile;
 pub mod rule;
 pub mod party;
 pub mod duty;
@@ -28,6 +34,11 @@ pub mod permission;
 pub mod prohibition;
 pub mod error;
 pub mod stateworld;
+pub mod clock;
 pub mod data_type;
+pub mod geo;
+pub mod schedule;
+pub mod rate_limit;
 pub mod eval;
+pub mod decision;
 pub mod model_factory;
\ No newline at end of file
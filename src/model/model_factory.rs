@@ -25,6 +25,7 @@ impl ModelFactory {
            "http://www.w3.org/ns/odrl/2/Privacy" => PolicyUnion::Privacy(Privacy::default()),
            "http://www.w3.org/ns/odrl/2/Policy" |
            "http://www.w3.org/ns/odrl/2/Set" => PolicyUnion::Set(Set::default()),
+           "http://www.w3.org/ns/odrl/2/Assertion" |
            "http://www.w3.org/ns/odrl/2/Assert" => PolicyUnion::Assert(Assert::default()),
            "http://www.w3.org/ns/odrl/2/Offer" => PolicyUnion::Offer(Offer::default()),
            _ => { PolicyUnion::Set(Set::default())}
@@ -0,0 +1,159 @@
+// Copyright 2024 meduo <gao.brian@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(dead_code)]
+
+use std::sync::Arc;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use crate::model::constraint::ConstraintUnion;
+use crate::model::constraint_left_operand::ConstraintLeftOperand;
+use crate::model::duty::Duty;
+use crate::model::policy::{attribute_key, OdrlRequest, Policy, RuleMatcher};
+use crate::model::rule::Rule;
+use crate::model::stateworld::StateWorld;
+use crate::reference::types::OperandValue;
+use crate::traits::definions::{AsyncLeftOperandProvider, LeftOperandProvider};
+
+//Left operand providers, keyed by the full iri of the operand they resolve
+pub struct OperandProviderRegistry {
+    providers: DashMap<String, Arc<dyn LeftOperandProvider>>,
+    async_providers: DashMap<String, Arc<dyn AsyncLeftOperandProvider>>,
+}
+
+impl OperandProviderRegistry {
+    pub fn new() -> Self {
+        OperandProviderRegistry {
+            providers: DashMap::new(),
+            async_providers: DashMap::new(),
+        }
+    }
+
+    //The operand may be given by name or iri
+    pub fn register(&self, left_operand: &str, provider: Arc<dyn LeftOperandProvider>) {
+        self.providers.insert(attribute_key(left_operand), provider);
+    }
+
+    pub fn register_async(&self, left_operand: &str, provider: Arc<dyn AsyncLeftOperandProvider>) {
+        self.async_providers.insert(attribute_key(left_operand), provider);
+    }
+
+    pub fn unregister(&self, left_operand: &str) {
+        let key = attribute_key(left_operand);
+        self.providers.remove(&key);
+        self.async_providers.remove(&key);
+    }
+
+    //Value of the operand from its sync provider, None when there is no provider or it has no value
+    pub fn provide(&self, iri: &str, operand: &ConstraintLeftOperand, world: &StateWorld) -> Result<Option<OperandValue>, anyhow::Error> {
+        let provider = match self.providers.get(iri) {
+            Some(provider) => provider.value().clone(),
+            None => return Ok(None),
+        };
+        provider.provide(operand, world)
+    }
+
+    //Resolve the async providers of the operands the policy refers to, their values are added
+    //to the request as attributes; attributes the request already carries are kept
+    pub async fn prefetch(&self, policy: &Policy, req: &OdrlRequest) -> Result<OdrlRequest, anyhow::Error> {
+        let mut prefetched = req.clone();
+        if self.async_providers.is_empty() {
+            return Ok(prefetched);
+        }
+
+        for operand in OperandProviderRegistry::left_operands(policy) {
+            let iri = operand.to_iri()?;
+            if prefetched.get_attribute(&iri).is_some() {
+                continue;
+            }
+            let provider = match self.async_providers.get(&iri) {
+                Some(provider) => provider.value().clone(),
+                None => continue,
+            };
+            if let Some(value) = provider.provide(&operand, req).await? {
+                prefetched.attributes.insert(iri, value);
+            }
+        }
+        Ok(prefetched)
+    }
+
+    //Left operands of every constraint and action refinement of the policy, without duplicates
+    pub fn left_operands(policy: &Policy) -> Vec<ConstraintLeftOperand> {
+        let mut operands = vec![];
+        if let Some(constraints) = policy.get_constraint() {
+            collect_constraints(constraints, &mut operands);
+        }
+        if let Some(obligations) = policy.get_obligation() {
+            collect_duties(obligations, &mut operands);
+        }
+        for rule in RuleMatcher::rules(policy) {
+            collect_rule(rule, &mut operands);
+        }
+        if let Some(permissions) = policy.get_permission() {
+            for permission in permissions {
+                if let Some(duties) = permission.get_duties() {
+                    collect_duties(duties, &mut operands);
+                }
+            }
+        }
+        operands
+    }
+}
+
+fn collect_duties(duties: &Vec<Duty>, operands: &mut Vec<ConstraintLeftOperand>) {
+    for duty in duties {
+        collect_rule(duty.get_rule(), operands);
+        if let Some(consequences) = duty.get_consequence() {
+            collect_duties(consequences, operands);
+        }
+    }
+}
+
+fn collect_rule(rule: &Rule, operands: &mut Vec<ConstraintLeftOperand>) {
+    if let Some(constraints) = rule.get_constraint() {
+        collect_constraints(constraints, operands);
+    }
+    if let Some(refinements) = rule.get_action().as_ref().and_then(|action| action.get_refinements().as_ref()) {
+        collect_constraints(refinements, operands);
+    }
+}
+
+fn collect_constraints(constraints: &Vec<ConstraintUnion>, operands: &mut Vec<ConstraintLeftOperand>) {
+    for constraint in constraints {
+        match constraint {
+            ConstraintUnion::Constraint(constraint) => {
+                if let Some(operand) = constraint.get_leftOperand() {
+                    if !operands.contains(operand) {
+                        operands.push(operand.clone());
+                    }
+                }
+            }
+            ConstraintUnion::LogicConstraint(logic) => {
+                if let Some(nested) = logic.get_operand() {
+                    collect_constraints(nested, operands);
+                }
+            }
+        }
+    }
+}
+
+impl Default for OperandProviderRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub static GLOBAL_OPERAND_PROVIDERS: Lazy<Arc<OperandProviderRegistry>> = Lazy::new(|| {
+    Arc::new(OperandProviderRegistry::new())
+});
@@ -15,13 +15,14 @@
 #![allow(dead_code)]
 #![allow(non_snake_case)]
 
+use std::collections::HashMap;
 use anyhow::anyhow;
 use iref::IriBuf;
 use lombok::{Getter,Builder,Setter,GetterMut};
-use crate::model::constraint::ConstraintUnion;
+use crate::model::constraint::{ConstraintInference, ConstraintUnion};
 use crate::model::metadata::Metadata;
 use crate::model::stateworld::StateWorld;
-use crate::traits::definions::LogicEval;
+use crate::reference::types::OperandValue;
 
 #[derive(Debug,Builder,Getter,GetterMut,Setter, Default, Clone)]
 pub struct PartyCollection {
@@ -36,6 +37,10 @@ pub struct Party {
     //link to PartCollection
     pub partOf: Vec<IriBuf>,
     pub refinement: Option<Vec<ConstraintUnion>>,
+    //@type of the party
+    pub ty: Option<IriBuf>,
+    //values the refinements of a matching party are evaluated against
+    pub attributes: HashMap<String, OperandValue>,
     //linked to Policy
     pub assignerOf: Option<IriBuf>,
     //linked to Policy
@@ -51,6 +56,8 @@ impl Party {
             metadata: Metadata::new(),
             partOf: Vec::new(),
             refinement: None,
+            ty: None,
+            attributes: HashMap::new(),
             assignerOf: None,
             assigneeOf: None,
         }
@@ -87,32 +94,11 @@ impl PartyInferencer {
                 if candidate_uid == self_uid {
                     //check refinement
                     if let Some(refinement) = party.get_refinement() {
-                        let mut refined = false;
-                        for constraint in refinement {
-                            match constraint {
-                                ConstraintUnion::Constraint(constraint) => {
-                                    let ret = constraint.eval(world);
-                                    match ret {
-                                        Ok(true) => {
-                                            refined = true;
-                                        },
-                                        _ => {
-                                        }
-                                    }
-                                }
-                                ConstraintUnion::LogicConstraint(ac) => {
-                                    let ret = ac.eval(world);
-                                    match ret {
-                                        Ok(true) => {
-                                            refined = true;
-                                        },
-                                        _ => {
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        return Ok(refined);
+                        //any satisfied refinement matches the party
+                        let mut refinement_world = world.refinement_world(candidate.get_attributes());
+                        let refined = ConstraintInference::infer_any(&mut refinement_world, refinement);
+                        world.add_indeterminate(refinement_world.take_indeterminate());
+                        return refined;
                     } else {
                         return Ok(true);
                     }
@@ -129,33 +115,16 @@ impl PartyInferencer {
 
                 if let Some(source) =  partyCollect.get_source() {
                     let source = source.as_str();
-                    if candidate_uid == source {
+                    //the candidate is the collection itself or one of its members
+                    let member = candidate.get_partOf().iter().any(|part| part.as_str() == source);
+                    if candidate_uid == source || member {
                         //check refinement
                         if let Some(refinement) = partyCollect.get_refinement() {
-                            let mut refined = false;
-                            for constraint in refinement {
-                                match constraint {
-                                    ConstraintUnion::Constraint(constraint) => {
-                                        let ret = constraint.eval(world);
-                                        match ret {
-                                            Ok(true) => {
-                                                refined = true;
-                                            }
-                                            _ => {}
-                                        }
-                                    }
-                                    ConstraintUnion::LogicConstraint(ac) => {
-                                        let ret = ac.eval(world);
-                                        match ret {
-                                            Ok(true) => {
-                                                refined = true;
-                                            }
-                                            _ => {}
-                                        }
-                                    }
-                                }
-                            }
-                            return Ok(refined);
+                            //any satisfied refinement matches the party
+                            let mut refinement_world = world.refinement_world(candidate.get_attributes());
+                            let refined = ConstraintInference::infer_any(&mut refinement_world, refinement);
+                            world.add_indeterminate(refinement_world.take_indeterminate());
+                            return refined;
                         }
                         return Ok(true);
                     }
@@ -19,6 +19,7 @@ use lombok::{Builder,Setter,GetterMut,Getter};
 use crate::model::action::Action;
 use crate::model::asset::{AssetUnion};
 use crate::model::constraint::{ConstraintUnion};
+use crate::model::duty::Duty;
 use crate::model::party::{PartyUnion};
 use crate::model::rule::Rule;
 
@@ -27,6 +28,8 @@ use crate::model::rule::Rule;
 #[derive(Debug,Default,Builder,Getter,GetterMut,Setter, Clone)]
 pub struct Permission {
     pub duty: Rule,
+    //duties to be discharged before the permission can be exercised
+    pub duties: Option<Vec<Duty>>,
 }
 
 impl Permission {
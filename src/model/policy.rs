@@ -16,19 +16,29 @@
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
 
+use std::collections::HashMap;
+use anyhow::anyhow;
 use iref::IriBuf;
 use lombok::{Builder, Getter, GetterMut, Setter};
 use crate::model::action::{Action, ActionInferencer, ActionType};
 use crate::model::asset::{Asset, AssetInferencer, AssetUnion};
 use crate::model::conflict_strategy::ConflictStrategy;
 use crate::model::constraint::{ConstraintInference, ConstraintUnion};
-use crate::model::duty::Duty;
+use crate::model::constraint_left_operand::ConstraintLeftOperand;
+use crate::model::decision::{Decision, DecisionEffect, EvalStep, RuleKind};
+use crate::model::duty::{Duty, DutyInferencer};
+use crate::model::error::OdrlError;
 use crate::model::metadata::Metadata;
+use crate::model::operand_provider::GLOBAL_OPERAND_PROVIDERS;
 use crate::model::eval::Evaluator;
 use crate::model::party::{Party, PartyInferencer, PartyUnion};
 use crate::model::permission::Permission;
+use crate::model::policy_store::GLOBAL_POLICY_STORE;
+use crate::model::profile::GLOBAL_PROFILE_REGISTRY;
 use crate::model::prohibition::Prohibition;
-use crate::model::stateworld::StateWorld;
+use crate::model::rule::Rule;
+use crate::model::stateworld::{StateWorld, UsageKey, Violation};
+use crate::reference::types::{OperandValue, OperandValueType};
 
 //Identifier:	http://www.w3.org/ns/odrl/2/Policy
 #[derive(Debug,Default,Builder,Setter,Getter,GetterMut,Clone)]
@@ -57,6 +67,39 @@ impl Policy {
     pub fn new() -> Self {
        Self::default()
     }
+
+    //Find a duty by its key among permission duties, obligations and their consequences
+    pub fn find_duty(&self, key: &str) -> Option<Duty> {
+        fn search(duties: &Vec<Duty>, key: &str) -> Option<Duty> {
+            for duty in duties {
+                if DutyInferencer::duty_key(duty) == key {
+                    return Some(duty.clone());
+                }
+                if let Some(consequences) = duty.get_consequence() {
+                    if let Some(found) = search(consequences, key) {
+                        return Some(found);
+                    }
+                }
+            }
+            None
+        }
+
+        if let Some(permissions) = self.get_permission() {
+            for permission in permissions {
+                if let Some(duties) = permission.get_duties() {
+                    if let Some(found) = search(duties, key) {
+                        return Some(found);
+                    }
+                }
+            }
+        }
+        self.get_obligation().as_ref().and_then(|obligations| search(obligations, key))
+    }
+
+    pub fn find_prohibition(&self, uid: &str) -> Option<&Prohibition> {
+        self.get_prohibition().as_ref()?.iter()
+            .find(|prohibit| prohibit.get_rule().get_uid().as_ref().is_some_and(|iri| iri.as_str() == uid))
+    }
 }
 
 //http://www.w3.org/ns/odrl/2/Agreement
@@ -112,35 +155,121 @@ pub enum PolicyUnion {
     Ticket(Ticket),
 }
 
+//Left operands may be given by name or iri, both are stored under the full iri
+pub(crate) fn attribute_key(left_operand: &str) -> String {
+    match ConstraintLeftOperand::try_from(left_operand).and_then(|operand| operand.to_iri()) {
+        Ok(iri) => iri,
+        Err(_) => left_operand.to_string(),
+    }
+}
+
+fn string_value(value: &str) -> OperandValue {
+    let mut val = OperandValue::default();
+    val.set_ty(OperandValueType::string);
+    val.set_sval(Some(value.to_string()));
+    val
+}
+
+fn set_value(values: Vec<String>) -> OperandValue {
+    let mut val = OperandValue::default();
+    val.set_ty(OperandValueType::set);
+    val.set_sets(Some(values));
+    val
+}
+
+//What the requester states about a candidate asset or party beyond its uid
+#[derive(Debug,Default,Builder,Setter,Getter,GetterMut,Clone)]
+pub struct CandidateProfile {
+    //collections the candidate is a member of
+    pub partOf: Vec<IriBuf>,
+    pub ty: Option<IriBuf>,
+    //values for refinements keyed by left operand iri
+    pub attributes: HashMap<String, OperandValue>,
+}
+
+impl CandidateProfile {
+    pub fn add_part_of(&mut self, collection: &str) {
+        if let Ok(iri) = IriBuf::new(collection.to_string()) {
+            self.partOf.push(iri);
+        }
+    }
+
+    pub fn add_attribute(&mut self, left_operand: &str, value: &str) {
+        self.attributes.insert(attribute_key(left_operand), string_value(value));
+    }
+
+    pub fn add_attribute_set(&mut self, left_operand: &str, values: Vec<String>) {
+        self.attributes.insert(attribute_key(left_operand), set_value(values));
+    }
+
+    pub fn get_attribute(&self, left_operand: &str) -> Option<&OperandValue> {
+        self.attributes.get(&attribute_key(left_operand))
+    }
+}
+
 #[derive(Debug,Default,Builder,Setter,GetterMut,Clone)]
 pub struct OdrlRequest{
     pub action: Option<IriBuf>,
     pub assignee: Option<IriBuf>,
     pub assigner: Option<IriBuf>,
     pub target: Option<IriBuf>,
+    //context values of this request keyed by left operand iri, they take precedence over world state
+    pub attributes: HashMap<String, OperandValue>,
+    pub assignee_profile: CandidateProfile,
+    pub assigner_profile: CandidateProfile,
+    pub target_profile: CandidateProfile,
 }
 
 impl OdrlRequest {
-    pub fn get_assignee(&self) -> Option<Party> {
-        if self.assignee.is_some() {
-            Some(Party::builder().uid(self.assignee.clone()).build())
-        } else {
-            None
+    pub fn add_attribute(&mut self, left_operand: &str, value: &str) {
+        self.attributes.insert(attribute_key(left_operand), string_value(value));
+    }
+
+    pub fn add_attribute_set(&mut self, left_operand: &str, values: Vec<String>) {
+        self.attributes.insert(attribute_key(left_operand), set_value(values));
+    }
+
+    pub fn get_attribute(&self, left_operand: &str) -> Option<&OperandValue> {
+        self.attributes.get(&attribute_key(left_operand))
+    }
+
+    //Usage counter of the request, keyed by assignee, action and target
+    pub fn usage_key(&self) -> UsageKey {
+        let part = |iri: &Option<IriBuf>| iri.as_ref().map(|iri| iri.as_str().to_string()).unwrap_or_default();
+        UsageKey {
+            assignee: part(&self.assignee),
+            action: part(&self.action),
+            target: part(&self.target),
         }
     }
+
+    pub fn remove_attribute(&mut self, left_operand: &str) {
+        self.attributes.remove(&attribute_key(left_operand));
+    }
+
+    fn candidate_party(uid: &Option<IriBuf>, profile: &CandidateProfile) -> Option<Party> {
+        uid.as_ref()?;
+        Some(Party::builder()
+            .uid(uid.clone())
+            .partOf(profile.partOf.clone())
+            .ty(profile.ty.clone())
+            .attributes(profile.attributes.clone())
+            .build())
+    }
+
+    pub fn get_assignee(&self) -> Option<Party> {
+        OdrlRequest::candidate_party(&self.assignee, &self.assignee_profile)
+    }
     pub fn get_assigner(&self) -> Option<Party> {
-        if self.assigner.is_some() {
-            Some(Party::builder().uid(self.assigner.clone()).build())
-        } else {
-            None
-        }
+        OdrlRequest::candidate_party(&self.assigner, &self.assigner_profile)
     }
 
     pub fn get_action(&self) -> Option<Action> {
         if self.action.is_some() {
             let ty = self.action.clone().unwrap();
             let ty = ty.as_str();
-            let action = Action::builder().actionType(ActionType::try_from(ty).unwrap()).build();
+            //an action the engine does not know makes the request indeterminate
+            let action = Action::builder().actionType(ActionType::try_from(ty).ok()?).build();
             Some(action)
         }else {
             None
@@ -149,1704 +278,781 @@ impl OdrlRequest {
 
     pub fn get_target(&self) -> Option<Asset> {
         if self.target.is_some() {
-            Some(Asset::builder().uid(self.target.clone()).build())
+            let profile = &self.target_profile;
+            let partOf = if profile.partOf.is_empty() { None } else { Some(profile.partOf.clone()) };
+            Some(Asset::builder()
+                .uid(self.target.clone())
+                .partOf(partOf)
+                .ty(profile.ty.clone())
+                .attributes(profile.attributes.clone())
+                .build())
         }else {
             None
         }
     }
 }
 
-impl Evaluator for Agreement  {
-    fn eval(&self,world: &mut StateWorld,req: &OdrlRequest) -> Result<bool, anyhow::Error> {
-       let policy = &self.policy;
-       let mut conflict = policy.get_conflict().clone();
-       if conflict.is_none() {
-           conflict = Some(ConflictStrategy::perm);
-       }
-       let conflict = conflict.unwrap();
-
-       let candidate = req.get_action().clone();
-       if candidate.is_none() {
-           return Ok(false);
-       }
-
-       //check allow permissions
-       let permissions = policy.get_permission();
-       if permissions.is_none() {
-          return Ok(false);
-       }
-       let prohibitions = policy.get_prohibition();
-
-       /*
-        * 1. Check Assigner and Assignee match in each permission
-        * 2. Check Target match in each permission
-        * 3. Check Action match in each permission
-        * 4. Check Constraint match in each permission
-        * 5. Check Obligation match in each permission
-        * 6. Check Above match in each prohibition
-        * 7. Check Conflict Strategy
-        */
-        let candidate_assignee = req.get_assignee();
-        let candidate_assigner = req.get_assigner();
-        let candidate_action = req.get_action();
+//How the parties of a rule are matched against the request
+#[derive(Debug,Clone,PartialEq)]
+pub enum PartyMatch {
+    //assignee and assigner must both match
+    Both,
+    //the assigner is checked, a rule without assignee is open to any assignee,
+    //one declaring an assignee is matched as Both
+    Assigner,
+    //at least one of assignee or assigner must match
+    Either,
+}
 
-        let mut permitted = false;
-        if let Some(permissions) = permissions {
-            for permission in permissions {
-                let candidate_assignee = candidate_assignee.clone();
-                let policy_assignee = permission.get_assignee().clone();
-                let mut assignee_verified = false;
-                if candidate_assignee.is_some() && policy_assignee.is_some() {
-                    let union = policy_assignee.unwrap();
-                    let ret = PartyInferencer::infer_party(world,&union,&candidate_assignee.unwrap());
-                    if let Ok(true) = ret {
-                        assignee_verified = true;
-                    }
-                }
-                if !assignee_verified {
-                    //no need to check other parts, must have exact one assignee
-                    continue;
-                }
+//Semantics each policy type adds on top of the shared rule matcher
+pub trait PolicySemantics {
+    fn policy(&self) -> &Policy;
 
-                //do assigner verification
-                let candidate_assigner = candidate_assigner.clone();
-                let mut assigner_verified = false;
-                let assigner = permission.get_assigner();
-                if candidate_assigner.is_some() && assigner.is_some() {
-                    let union = assigner.clone().unwrap();
-                    let ret = PartyInferencer::infer_party(world,&union,&candidate_assigner.unwrap());
-                    if let Ok(true) = ret {
-                        assigner_verified = true;
-                    }
-                }
-                if !assigner_verified {
-                    //no need to check other parts, must have exact one assigner
-                    continue;
-                }
+    fn party_match(&self) -> PartyMatch {
+        PartyMatch::Both
+    }
 
-                //do action verification
-                let candidate_action = candidate_action.clone().unwrap();
-                let policy_action = permission.get_action().clone().unwrap();
-                let result = ActionInferencer::infer(world,policy_action,candidate_action);
-                let mut action_verified = false;
-                if let Ok(true) = result {
-                    action_verified = true;
-                }
-                if !action_verified {
-                    //no need to check other parts, must have exact one action
-                    continue;
-                }
+    //structural requirements of the policy type, checked before any rule is matched
+    fn validate(&self) -> Result<(), OdrlError> {
+        Ok(())
+    }
+}
 
-                //do target verification
-                let candidate_target = req.get_target().clone().unwrap();
-                let policy_target = permission.get_target().clone().unwrap();
-                let result = AssetInferencer::infer(world,policy_target,candidate_target);
-                let mut target_verified = false;
-                if let Ok(true) = result {
-                    target_verified = true;
-                }
-                if !target_verified {
-                    //no need to check other parts, must have exact one action
-                    continue;
-                }
+impl<T: PolicySemantics> Evaluator for T {
+    fn decide(&self,world: &mut StateWorld,req: &OdrlRequest) -> Result<Decision, anyhow::Error> {
+        RuleMatcher::decide(self, world, req)
+    }
+}
 
-                //check constraint
-                let policy_constraint = permission.get_constraint();
-                let mut constraint_verified = false;
-                if let Some(constraint) = policy_constraint {
-                    let ret = ConstraintInference::infer(world,constraint);
-                    if let Ok(true) = ret {
-                        constraint_verified = true;
-                    }
-                } else {
-                    constraint_verified = true;
-                }
+impl PolicySemantics for Agreement {
+    fn policy(&self) -> &Policy {
+        &self.policy
+    }
 
-                if constraint_verified {
-                    //every thing ok here, a permission is matched already
-                    permitted = true;
-                    break;
-                }
+    //an agreement is granted by an assigner to a specific assignee on a target
+    fn validate(&self) -> Result<(), OdrlError> {
+        for rule in RuleMatcher::rules(&self.policy) {
+            if rule.get_assigner().is_none() {
+                return Err(OdrlError::MissingAgreementAssigner);
+            }
+            if rule.get_assignee().is_none() {
+                return Err(OdrlError::MissingAgreementAssignee);
+            }
+            if rule.get_target().is_none() {
+                return Err(OdrlError::MissingAgreementTarget);
             }
         }
-
-       //check prohibition
-       if permitted {
-           let mut prohibited = false;
-           if let Some(prohibits) = prohibitions {
-               for prohibit in prohibits {
-                   let candidate_assignee = candidate_assignee.clone();
-                   let policy_assignee = prohibit.get_assignee().clone();
-
-                   //do assignee verification
-                   let mut assignee_verified = false;
-                   if candidate_assignee.is_some() && policy_assignee.is_some() {
-                       let union = policy_assignee.unwrap();
-                       let ret = PartyInferencer::infer_party(world,&union,&candidate_assignee.unwrap());
-                       if let Ok(true) = ret {
-                           assignee_verified = true;
-                       }
-                   }
-                   if !assignee_verified {
-                       //no need to check other parts, must have exact one assignee
-                       continue;
-                   }
-
-                   //do assigner verification
-                   let candidate_assigner = candidate_assigner.clone();
-                   let policy_assigner = prohibit.get_assigner().clone();
-                   let mut assigner_verified = false;
-                   let assigner = prohibit.get_assigner();
-                   if candidate_assigner.is_some() && assigner.is_some() {
-                       let union = policy_assigner.unwrap();
-                       let ret = PartyInferencer::infer_party(world,&union,&candidate_assigner.unwrap());
-                       if let Ok(true) = ret {
-                           assigner_verified = true;
-                       }
-                   }
-                   if !assigner_verified {
-                       //no need to check other parts, must have exact one assigner
-                       continue;
-                   }
-
-                   //do action verification
-                   let candidate_action = candidate_action.clone().unwrap();
-                   let policy_action = prohibit.get_action().clone().unwrap();
-                   let result = ActionInferencer::infer(world,policy_action,candidate_action);
-                   let mut action_verified = false;
-                   if let Ok(true) = result {
-                       action_verified = true;
-                   }
-
-                   if !action_verified {
-                       //no need to check other parts, must have exact one action
-                       continue;
-                   }
-
-                   //do target verification
-                   let candidate_target = req.get_target().clone().unwrap();
-                   let policy_target = prohibit.get_target().clone().unwrap();
-                   let result = AssetInferencer::infer(world,policy_target,candidate_target);
-                   let mut target_verified = false;
-                   if let Ok(true) = result {
-                       target_verified = true;
-                   }
-                   if !target_verified {
-                       continue;
-                   }
-
-                   //check constraint
-                   let policy_constraint = prohibit.get_constraint();
-                   let mut constraint_verified = false;
-                   if let Some(constraint) = policy_constraint {
-                       let ret = ConstraintInference::infer(world,constraint);
-                       if let Ok(true) = ret {
-                           constraint_verified = true;
-                       }
-                   } else {
-                       constraint_verified = true;
-                   }
-
-                   if constraint_verified {
-                       prohibited = true;
-                       break;
-                   }
-               }
-           }
-           if prohibited {
-               //already permitted, need to check conflict strategy
-               return match conflict {
-                   ConflictStrategy::perm => {
-                       Ok(true)
-                   }
-                   ConflictStrategy::prohibit => {
-                       Ok(false)
-                   }
-                   ConflictStrategy::invalid => {
-                       Ok(false)
-                   }
-               }
-           }
-           return Ok(true);
-       }
-
-       //here not matched any permission at this level, need to check inheritFrom
-       let inheritFrom = policy.get_inheritFrom().clone();
-       let inheritFrom = inheritFrom.unwrap();
-       for inherit in inheritFrom {
-           let inherit_policy = world.get_policy(inherit.to_string());
-           if let Some(inherit_policy) = inherit_policy {
-               let result = PolicyEngine::eval(world,&inherit_policy,req);
-               if let Ok(true) = result {
-                   return Ok(true);
-               }
-           }
-       }
-
-       Ok(false)
+        Ok(())
     }
 }
 
-impl Evaluator for Offer {
-    fn eval(&self,world: &mut StateWorld,req: &OdrlRequest) -> Result<bool, anyhow::Error> {
-        let policy = &self.policy;
-        let mut conflict = policy.get_conflict().clone();
-        if conflict.is_none() {
-            conflict = Some(ConflictStrategy::perm);
-        }
-        let conflict = conflict.unwrap();
-
-        let candidate = req.get_action().clone();
-        if candidate.is_none() {
-            return Ok(false);
-        }
-
-        //check allow permissions
-        let permissions = policy.get_permission();
-        if permissions.is_none() {
-            return Ok(false);
-        }
-        let prohibitions = policy.get_prohibition();
-
-        /*
-         * 1. Check Assigner and Assignee match in each permission
-         * 2. Check Target match in each permission
-         * 3. Check Action match in each permission
-         * 4. Check Constraint match in each permission
-         * 5. Check Obligation match in each permission
-         * 6. Check Above match in each prohibition
-         * 7. Check Conflict Strategy
-         */
-        let candidate_assignee = req.get_assignee();
-        let candidate_assigner = req.get_assigner();
-        let candidate_action = req.get_action();
-
-        let mut permitted = false;
-        if let Some(permissions) = permissions {
-            for permission in permissions {
-                // Note: No need to check assignee and assigner here, because offer is not for specific assignee
-                // let candidate_assignee = candidate_assignee.clone();
-                // let policy_assignee = permission.get_assignee().clone();
-                // let mut assignee_verified = false;
-                // if candidate_assignee.is_some() && policy_assignee.is_some() {
-                //     let union = PartyUnion::Party(policy_assignee.unwrap());
-                //     let ret = PartyInferencer::infer_party(world,&union,&candidate_assignee.unwrap());
-                //     if let Ok(true) = ret {
-                //         assignee_verified = true;
-                //     }
-                // }
-                // if !assignee_verified {
-                //     //no need to check other parts, must have exact one assignee
-                //     continue;
-                // }
-
-                //do assigner verification
-                let candidate_assigner = candidate_assigner.clone();
-                let policy_assigner = permission.get_assigner().clone();
-                let mut assigner_verified = false;
-                let assigner = permission.get_assigner();
-                if candidate_assigner.is_some() && assigner.is_some() {
-                    let union = policy_assigner.unwrap();
-                    let ret = PartyInferencer::infer_party(world,&union,&candidate_assigner.unwrap());
-                    if let Ok(true) = ret {
-                        assigner_verified = true;
-                    }
-                }
-                if !assigner_verified {
-                    //no need to check other parts, must have exact one assigner
-                    continue;
-                }
-
-                //do action verification
-                let candidate_action = candidate_action.clone().unwrap();
-                let policy_action = permission.get_action().clone().unwrap();
-                let result = ActionInferencer::infer(world,policy_action,candidate_action);
-                let mut action_verified = false;
-                if let Ok(true) = result {
-                    action_verified = true;
-                }
-                if !action_verified {
-                    //no need to check other parts, must have exact one action
-                    continue;
-                }
-
-                //do target verification
-                let candidate_target = req.get_target().clone().unwrap();
-                let policy_target = permission.get_target().clone().unwrap();
-                let result = AssetInferencer::infer(world,policy_target,candidate_target);
-                let mut target_verified = false;
-                if let Ok(true) = result {
-                    target_verified = true;
-                }
-                if !target_verified {
-                    //no need to check other parts, must have exact one action
-                    continue;
-                }
+impl PolicySemantics for Offer {
+    fn policy(&self) -> &Policy {
+        &self.policy
+    }
 
-                //check constraint
-                let policy_constraint = permission.get_constraint();
-                let mut constraint_verified = false;
-                if let Some(constraint) = policy_constraint {
-                    let ret = ConstraintInference::infer(world,constraint);
-                    if let Ok(true) = ret {
-                        constraint_verified = true;
-                    }
-                } else {
-                    constraint_verified = true;
-                }
+    //offer is not for specific assignee, unless it declares one
+    fn party_match(&self) -> PartyMatch {
+        PartyMatch::Assigner
+    }
 
-                if constraint_verified {
-                    //every thing ok here, a permission is matched already
-                    permitted = true;
-                    break;
-                }
+    fn validate(&self) -> Result<(), OdrlError> {
+        for rule in RuleMatcher::rules(&self.policy) {
+            if rule.get_assigner().is_none() {
+                return Err(OdrlError::MissingOfferAssigner);
+            }
+            if rule.get_target().is_none() {
+                return Err(OdrlError::MissingOfferTarget);
             }
         }
+        Ok(())
+    }
+}
 
-        //check prohibition
-        if permitted {
-            let mut prohibited = false;
-            if let Some(prohibits) = prohibitions {
-                for prohibit in prohibits {
-                    let candidate_assignee = candidate_assignee.clone();
-                    let policy_assignee = prohibit.get_assignee().clone();
-
-                    //do assignee verification
-                    let mut assignee_verified = false;
-                    if candidate_assignee.is_some() && policy_assignee.is_some() {
-                        let union = policy_assignee.unwrap();
-                        let ret = PartyInferencer::infer_party(world,&union,&candidate_assignee.unwrap());
-                        if let Ok(true) = ret {
-                            assignee_verified = true;
-                        }
-                    }
-                    if !assignee_verified {
-                        //no need to check other parts, must have exact one assignee
-                        continue;
-                    }
+impl PolicySemantics for Set {
+    fn policy(&self) -> &Policy {
+        &self.policy
+    }
+}
 
-                    //do assigner verification
-                    let candidate_assigner = candidate_assigner.clone();
-                    let policy_assigner = prohibit.get_assigner().clone();
-                    let mut assigner_verified = false;
-                    let assigner = prohibit.get_assigner();
-                    if candidate_assigner.is_some() && assigner.is_some() {
-                        let union = policy_assigner.unwrap();
-                        let ret = PartyInferencer::infer_party(world,&union,&candidate_assigner.unwrap());
-                        if let Ok(true) = ret {
-                            assigner_verified = true;
-                        }
-                    }
-                    if !assigner_verified {
-                        //no need to check other parts, must have exact one assigner
-                        continue;
-                    }
+impl PolicySemantics for Privacy {
+    fn policy(&self) -> &Policy {
+        &self.policy
+    }
+}
 
-                    //do action verification
-                    let candidate_action = candidate_action.clone().unwrap();
-                    let policy_action = prohibit.get_action().clone().unwrap();
-                    let result = ActionInferencer::infer(world,policy_action,candidate_action);
-                    let mut action_verified = false;
-                    if let Ok(true) = result {
-                        action_verified = true;
-                    }
+impl PolicySemantics for Request {
+    fn policy(&self) -> &Policy {
+        &self.policy
+    }
+}
 
-                    if !action_verified {
-                        //no need to check other parts, must have exact one action
-                        continue;
-                    }
+impl PolicySemantics for Assert {
+    fn policy(&self) -> &Policy {
+        &self.policy
+    }
 
-                    //do target verification
-                    let candidate_target = req.get_target().clone().unwrap();
-                    let policy_target = prohibit.get_target().clone().unwrap();
-                    let result = AssetInferencer::infer(world,policy_target,candidate_target);
-                    let mut target_verified = false;
-                    if let Ok(true) = result {
-                        target_verified = true;
-                    }
-                    if !target_verified {
-                        continue;
-                    }
+    fn party_match(&self) -> PartyMatch {
+        PartyMatch::Either
+    }
+}
 
-                    //check constraint
-                    let policy_constraint = prohibit.get_constraint();
-                    let mut constraint_verified = false;
-                    if let Some(constraint) = policy_constraint {
-                        let ret = ConstraintInference::infer(world,constraint);
-                        if let Ok(true) = ret {
-                            constraint_verified = true;
-                        }
-                    } else {
-                        constraint_verified = true;
-                    }
+impl PolicySemantics for Ticket {
+    fn policy(&self) -> &Policy {
+        &self.policy
+    }
 
-                    if constraint_verified {
-                        prohibited = true;
-                        break;
-                    }
-                }
-            }
-            if prohibited {
-                //already permitted, need to check conflict strategy
-                return match conflict {
-                    ConflictStrategy::perm => {
-                        Ok(true)
-                    }
-                    ConflictStrategy::prohibit => {
-                        Ok(false)
-                    }
-                    ConflictStrategy::invalid => {
-                        Ok(false)
-                    }
-                }
-            }
-            return Ok(true);
-        }
+    //no assignee in ticket, whoever holds it may exercise it, unless it declares one
+    fn party_match(&self) -> PartyMatch {
+        PartyMatch::Assigner
+    }
+}
 
-        //here not matched any permission at this level, need to check inheritFrom
-        let inheritFrom = policy.get_inheritFrom().clone();
-        let inheritFrom = inheritFrom.unwrap();
-        for inherit in inheritFrom {
-            let inherit_policy = world.get_policy(inherit.to_string());
-            if let Some(inherit_policy) = inherit_policy {
-                let result = PolicyEngine::eval(world,&inherit_policy,req);
-                if let Ok(true) = result {
-                    return Ok(true);
-                }
-            }
+//Rule matching shared by every policy type
+pub struct RuleMatcher;
+
+impl RuleMatcher {
+    /*
+     * 1. Check policy constraints and obligations
+     * 2. Check parties, action, target and constraint of each permission
+     * 3. Check duties of the matched permission
+     * 4. Check each prohibition, whether or not a permission matched
+     * 5. Check Conflict Strategy
+     */
+    pub fn decide<T: PolicySemantics + ?Sized>(semantics: &T, world: &mut StateWorld, req: &OdrlRequest) -> Result<Decision, anyhow::Error> {
+        //request attributes are only visible to the constraints of this evaluation
+        let outer = world.enter_request(req.attributes.clone());
+        let outer_usage = world.enter_usage(Some(req.usage_key()));
+        let outer_hits = world.enter_rate_limits();
+        let outer_steps = world.enter_sequences();
+        let outer_indeterminate = world.take_indeterminate();
+        let decision = RuleMatcher::decide_rules(semantics, world, req);
+        world.take_indeterminate();
+        world.add_indeterminate(outer_indeterminate);
+        let hits = world.leave_rate_limits(outer_hits);
+        let steps = world.leave_sequences(outer_steps);
+        world.leave_usage(outer_usage);
+        world.leave_request(outer);
+
+        //every permitted request counts as one use of the action on the target
+        if matches!(&decision, Ok(decision) if decision.is_permitted()) {
+            world.record_usage(req.usage_key());
+            world.record_rate_limit_hits(hits);
         }
-
-        Ok(false)
+        //sequences advance with every evaluated request, not only the permitted ones
+        if decision.is_ok() {
+            world.record_sequence_steps(steps);
+        }
+        decision
     }
-}
 
-impl Evaluator for Set {
-    fn eval(&self,world: &mut StateWorld,req: &OdrlRequest) -> Result<bool, anyhow::Error> {
-        let policy = &self.policy;
-        let mut conflict = policy.get_conflict().clone();
-        if conflict.is_none() {
-            conflict = Some(ConflictStrategy::perm);
+    fn decide_rules<T: PolicySemantics + ?Sized>(semantics: &T, world: &mut StateWorld, req: &OdrlRequest) -> Result<Decision, anyhow::Error> {
+        semantics.validate()?;
+
+        let policy = semantics.policy();
+        GLOBAL_PROFILE_REGISTRY.validate(policy)?;
+        let conflict = policy.get_conflict().clone().unwrap_or(ConflictStrategy::perm);
+        let mut decision = Decision::default();
+        decision.set_conflict(Some(conflict.clone()));
+
+        if req.get_action().is_none() {
+            return Ok(decision.with_effect(DecisionEffect::Indeterminate).with_reason("request without action"));
         }
-        let conflict = conflict.unwrap();
 
-        let candidate = req.get_action().clone();
-        if candidate.is_none() {
-            return Ok(false);
+        //check policy constraints, they gate every rule of the policy
+        let verified = PolicyEngine::policy_constraint_verified(world, policy, &mut decision)?;
+        if let Some(reason) = RuleMatcher::strict_reason(world, RuleKind::Policy, policy.get_uid(), &mut decision) {
+            return Ok(decision.with_effect(DecisionEffect::Deny).with_reason(&reason));
+        }
+        if !verified {
+            return Ok(decision.with_effect(DecisionEffect::Deny).with_reason("policy constraint not satisfied"));
         }
 
-        //check allow permissions
-        let permissions = policy.get_permission();
-        if permissions.is_none() {
-            return Ok(false);
+        //check policy obligations, a violated obligation blocks every permission
+        if let Some(obligations) = policy.get_obligation() {
+            let violated = PolicyEngine::obligation_violated(world, obligations, req, &mut decision)?;
+            if let Some(reason) = RuleMatcher::strict_reason(world, RuleKind::Obligation, &None, &mut decision) {
+                return Ok(decision.with_effect(DecisionEffect::Deny).with_reason(&reason));
+            }
+            if violated {
+                return Ok(decision.with_effect(DecisionEffect::Deny).with_reason("policy obligation violated"));
+            }
         }
-        let prohibitions = policy.get_prohibition();
-
-        /*
-         * 1. Check Assigner and Assignee match in each permission
-         * 2. Check Target match in each permission
-         * 3. Check Action match in each permission
-         * 4. Check Constraint match in each permission
-         * 5. Check Obligation match in each permission
-         * 6. Check Above match in each prohibition
-         * 7. Check Conflict Strategy
-         */
-        let candidate_assignee = req.get_assignee();
-        let candidate_assigner = req.get_assigner();
-        let candidate_action = req.get_action();
 
+        let party_match = semantics.party_match();
         let mut permitted = false;
-        if let Some(permissions) = permissions {
-            for permission in permissions {
-                let candidate_assignee = candidate_assignee.clone();
-                let policy_assignee = permission.get_assignee().clone();
-                let mut assignee_verified = false;
-                if candidate_assignee.is_some() && policy_assignee.is_some() {
-                    let union = policy_assignee.unwrap();
-                    let ret = PartyInferencer::infer_party(world,&union,&candidate_assignee.unwrap());
-                    if let Ok(true) = ret {
-                        assignee_verified = true;
-                    }
-                }
-                if !assignee_verified {
-                    //no need to check other parts, must have exact one assignee
-                    continue;
-                }
-
-                //do assigner verification
-                let candidate_assigner = candidate_assigner.clone();
-                let policy_assigner = permission.get_assigner().clone();
-                let mut assigner_verified = false;
-                let assigner = permission.get_assigner();
-                if candidate_assigner.is_some() && assigner.is_some() {
-                    let union = policy_assigner.unwrap();
-                    let ret = PartyInferencer::infer_party(world,&union,&candidate_assigner.unwrap());
-                    if let Ok(true) = ret {
-                        assigner_verified = true;
-                    }
-                }
-                if !assigner_verified {
-                    //no need to check other parts, must have exact one assigner
-                    continue;
-                }
-
-                //do action verification
-                let candidate_action = candidate_action.clone().unwrap();
-                let policy_action = permission.get_action().clone().unwrap();
-                let result = ActionInferencer::infer(world,policy_action,candidate_action);
-                let mut action_verified = false;
-                if let Ok(true) = result {
-                    action_verified = true;
-                }
-                if !action_verified {
-                    //no need to check other parts, must have exact one action
-                    continue;
-                }
+        let mut matched = false;
+        for permission in policy.get_permission().iter().flatten() {
+            let rule = permission.get_duty();
+            let rule_matched = RuleMatcher::match_rule(world, &party_match, rule, RuleKind::Permission, req, &mut decision);
+            if let Some(reason) = RuleMatcher::strict_reason(world, RuleKind::Permission, rule.get_uid(), &mut decision) {
+                return Ok(decision.with_effect(DecisionEffect::Deny).with_reason(&reason));
+            }
+            if !rule_matched {
+                continue;
+            }
 
-                //do target verification
-                let candidate_target = req.get_target().clone().unwrap();
-                let policy_target = permission.get_target().clone().unwrap();
-                let result = AssetInferencer::infer(world,policy_target,candidate_target);
-                let mut target_verified = false;
-                if let Ok(true) = result {
-                    target_verified = true;
-                }
-                if !target_verified {
-                    //no need to check other parts, must have exact one action
-                    continue;
-                }
+            matched = true;
+            //time windows of a permission which is not exercised are not hit
+            let mark = world.rate_limit_mark();
+            let constraint_verified = RuleMatcher::match_constraint(world, rule, RuleKind::Permission, &mut decision)?;
+            if let Some(reason) = RuleMatcher::strict_reason(world, RuleKind::Permission, rule.get_uid(), &mut decision) {
+                return Ok(decision.with_effect(DecisionEffect::Deny).with_reason(&reason));
+            }
+            if !constraint_verified {
+                world.discard_rate_limit_hits(mark);
+                continue;
+            }
 
-                //check constraint
-                let policy_constraint = permission.get_constraint();
-                let mut constraint_verified = false;
-                if let Some(constraint) = policy_constraint {
-                    let ret = ConstraintInference::infer(world,constraint);
-                    if let Ok(true) = ret {
-                        constraint_verified = true;
-                    }
-                } else {
-                    constraint_verified = true;
-                }
+            //check duties, the permission is only exercisable once they are discharged
+            let mut duty_verified = true;
+            if let Some(duties) = permission.get_duties() {
+                duty_verified = DutyInferencer::infer_all(world, duties, &req.get_assignee())?;
+            }
+            if let Some(reason) = RuleMatcher::strict_reason(world, RuleKind::Permission, rule.get_uid(), &mut decision) {
+                return Ok(decision.with_effect(DecisionEffect::Deny).with_reason(&reason));
+            }
+            decision.record(RuleKind::Permission, rule.get_uid(), EvalStep::Duty, duty_verified);
+            if duty_verified {
+                //every thing ok here, a permission is matched already
+                decision.set_permission(rule.get_uid().clone());
+                permitted = true;
+                break;
+            }
+            world.discard_rate_limit_hits(mark);
+        }
 
-                if constraint_verified {
-                    //every thing ok here, a permission is matched already
-                    permitted = true;
+        //check prohibition, whether or not a permission covers the request
+        let mut prohibited = false;
+        let mark = world.rate_limit_mark();
+        if let Some(prohibits) = policy.get_prohibition() {
+            for prohibit in prohibits {
+                let rule = prohibit.get_rule();
+                let rule_matched = RuleMatcher::match_rule(world, &party_match, rule, RuleKind::Prohibition, req, &mut decision)
+                    && RuleMatcher::match_constraint(world, rule, RuleKind::Prohibition, &mut decision)?;
+                if let Some(reason) = RuleMatcher::strict_reason(world, RuleKind::Prohibition, rule.get_uid(), &mut decision) {
+                    return Ok(decision.with_effect(DecisionEffect::Deny).with_reason(&reason));
+                }
+                if rule_matched {
+                    decision.set_prohibition(rule.get_uid().clone());
+                    prohibited = true;
                     break;
                 }
             }
         }
 
-        //check prohibition
-        if permitted {
-            let mut prohibited = false;
-            if let Some(prohibits) = prohibitions {
-                for prohibit in prohibits {
-                    let candidate_assignee = candidate_assignee.clone();
-                    let policy_assignee = prohibit.get_assignee().clone();
-
-                    //do assignee verification
-                    let mut assignee_verified = false;
-                    if candidate_assignee.is_some() && policy_assignee.is_some() {
-                        let union = policy_assignee.unwrap();
-                        let ret = PartyInferencer::infer_party(world,&union,&candidate_assignee.unwrap());
-                        if let Ok(true) = ret {
-                            assignee_verified = true;
-                        }
-                    }
-                    if !assignee_verified {
-                        //no need to check other parts, must have exact one assignee
-                        continue;
-                    }
-
-                    //do assigner verification
-                    let candidate_assigner = candidate_assigner.clone();
-                    let policy_assigner = prohibit.get_assigner().clone();
-                    let mut assigner_verified = false;
-                    let assigner = prohibit.get_assigner();
-                    if candidate_assigner.is_some() && assigner.is_some() {
-                        let union = policy_assigner.unwrap();
-                        let ret = PartyInferencer::infer_party(world,&union,&candidate_assigner.unwrap());
-                        if let Ok(true) = ret {
-                            assigner_verified = true;
-                        }
-                    }
-                    if !assigner_verified {
-                        //no need to check other parts, must have exact one assigner
-                        continue;
-                    }
-
-                    //do action verification
-                    let candidate_action = candidate_action.clone().unwrap();
-                    let policy_action = prohibit.get_action().clone().unwrap();
-                    let result = ActionInferencer::infer(world,policy_action,candidate_action);
-                    let mut action_verified = false;
-                    if let Ok(true) = result {
-                        action_verified = true;
-                    }
-
-                    if !action_verified {
-                        //no need to check other parts, must have exact one action
-                        continue;
-                    }
-
-                    //do target verification
-                    let candidate_target = req.get_target().clone().unwrap();
-                    let policy_target = prohibit.get_target().clone().unwrap();
-                    let result = AssetInferencer::infer(world,policy_target,candidate_target);
-                    let mut target_verified = false;
-                    if let Ok(true) = result {
-                        target_verified = true;
-                    }
-                    if !target_verified {
-                        continue;
-                    }
+        world.discard_rate_limit_hits(mark);
 
-                    //check constraint
-                    let policy_constraint = prohibit.get_constraint();
-                    let mut constraint_verified = false;
-                    if let Some(constraint) = policy_constraint {
-                        let ret = ConstraintInference::infer(world,constraint);
-                        if let Ok(true) = ret {
-                            constraint_verified = true;
-                        }
-                    }
-                    if constraint_verified {
-                        prohibited = true;
-                        break;
-                    }
-                }
-            }
+        if !permitted {
             if prohibited {
-                //already permitted, need to check conflict strategy
-                return match conflict {
-                    ConflictStrategy::perm => {
-                        Ok(true)
-                    }
-                    ConflictStrategy::prohibit => {
-                        Ok(false)
-                    }
-                    ConflictStrategy::invalid => {
-                        Ok(false)
-                    }
-                }
+                //no permission to weigh against, the prohibition applies as is
+                return Ok(decision.with_effect(DecisionEffect::Deny).with_reason("action is prohibited"));
+            }
+            if matched {
+                //a permission applies to the request, but its constraint or duties are not satisfied
+                let reason = PolicyEngine::unsatisfied_reason(&decision);
+                return Ok(decision.with_effect(DecisionEffect::Deny).with_reason(reason));
             }
-            return Ok(true);
+            if policy.get_permission().is_none() {
+                return Ok(decision.with_reason("policy without permission"));
+            }
+            return Ok(decision);
         }
 
-        //here not matched any permission at this level, need to check inheritFrom
-        let inheritFrom = policy.get_inheritFrom().clone();
-        let inheritFrom = inheritFrom.unwrap();
-        for inherit in inheritFrom {
-            let inherit_policy = world.get_policy(inherit.to_string());
-            if let Some(inherit_policy) = inherit_policy {
-                let result = PolicyEngine::eval(world,&inherit_policy,req);
-                if let Ok(true) = result {
-                    return Ok(true);
+        if prohibited {
+            //already permitted, need to check conflict strategy
+            return match conflict {
+                ConflictStrategy::perm => {
+                    Ok(decision.with_effect(DecisionEffect::Permit))
+                }
+                ConflictStrategy::prohibit => {
+                    Ok(decision.with_effect(DecisionEffect::Deny))
+                }
+                ConflictStrategy::invalid => {
+                    Ok(decision.with_effect(DecisionEffect::Deny).with_reason("policy is void, permission conflicts with prohibition"))
                 }
             }
         }
+        Ok(decision.with_effect(DecisionEffect::Permit))
+    }
 
-        Ok(false)
+    //Every permission and prohibition rule of the policy
+    pub fn rules(policy: &Policy) -> Vec<&Rule> {
+        let mut rules: Vec<&Rule> = vec![];
+        if let Some(permissions) = policy.get_permission() {
+            rules.extend(permissions.iter().map(|permission| permission.get_duty()));
+        }
+        if let Some(prohibitions) = policy.get_prohibition() {
+            rules.extend(prohibitions.iter().map(|prohibit| prohibit.get_rule()));
+        }
+        rules
     }
-}
 
-impl Evaluator for Privacy {
-    fn eval(&self,world: &mut StateWorld,req: &OdrlRequest) -> Result<bool, anyhow::Error> {
-        let policy = &self.policy;
-        let mut conflict = policy.get_conflict().clone();
-        if conflict.is_none() {
-            conflict = Some(ConflictStrategy::perm);
+    //Check parties, action and target of the rule, the constraint is checked separately
+    fn match_rule(world: &mut StateWorld, party_match: &PartyMatch, rule: &Rule, kind: RuleKind, req: &OdrlRequest, decision: &mut Decision) -> bool {
+        if !RuleMatcher::match_parties(world, party_match, rule, kind.clone(), req, decision) {
+            return false;
         }
-        let conflict = conflict.unwrap();
 
-        let candidate = req.get_action().clone();
-        if candidate.is_none() {
-            return Ok(false);
+        //do action verification
+        let mut action_verified = false;
+        if let (Some(policy_action), Some(candidate_action)) = (rule.get_action(), req.get_action()) {
+            let ret = ActionInferencer::infer(world, policy_action.clone(), candidate_action, &kind);
+            action_verified = matches!(ret, Ok(true));
+        }
+        decision.record(kind.clone(), rule.get_uid(), EvalStep::Action, action_verified);
+        if !action_verified {
+            //no need to check other parts, must have exact one action
+            return false;
         }
 
-        //check allow permissions
-        let permissions = policy.get_permission();
-        if permissions.is_none() {
-            return Ok(false);
+        //do target verification
+        let mut target_verified = false;
+        if let (Some(policy_target), Some(candidate_target)) = (rule.get_target(), req.get_target()) {
+            let ret = AssetInferencer::infer(world, policy_target.clone(), candidate_target);
+            target_verified = matches!(ret, Ok(true));
         }
-        let prohibitions = policy.get_prohibition();
-
-        /*
-         * 1. Check Assigner and Assignee match in each permission
-         * 2. Check Target match in each permission
-         * 3. Check Action match in each permission
-         * 4. Check Constraint match in each permission
-         * 5. Check Obligation match in each permission
-         * 6. Check Above match in each prohibition
-         * 7. Check Conflict Strategy
-         */
-        let candidate_assignee = req.get_assignee();
-        let candidate_assigner = req.get_assigner();
-        let candidate_action = req.get_action();
+        decision.record(kind, rule.get_uid(), EvalStep::Target, target_verified);
+        target_verified
+    }
 
-        let mut permitted = false;
-        if let Some(permissions) = permissions {
-            for permission in permissions {
-                let candidate_assignee = candidate_assignee.clone();
-                let policy_assignee = permission.get_assignee().clone();
-                let mut assignee_verified = false;
-                if candidate_assignee.is_some() && policy_assignee.is_some() {
-                    let union = policy_assignee.unwrap();
-                    let ret = PartyInferencer::infer_party(world,&union,&candidate_assignee.unwrap());
-                    if let Ok(true) = ret {
-                        assignee_verified = true;
-                    }
-                }
+    fn match_parties(world: &mut StateWorld, party_match: &PartyMatch, rule: &Rule, kind: RuleKind, req: &OdrlRequest, decision: &mut Decision) -> bool {
+        if *party_match == PartyMatch::Assigner && rule.get_assignee().is_some() {
+            return RuleMatcher::match_parties(world, &PartyMatch::Both, rule, kind, req, decision);
+        }
+        let assigner_verified = RuleMatcher::match_party(world, rule.get_assigner(), req.get_assigner());
+        match party_match {
+            PartyMatch::Both => {
+                let assignee_verified = RuleMatcher::match_party(world, rule.get_assignee(), req.get_assignee());
+                decision.record(kind.clone(), rule.get_uid(), EvalStep::Assignee, assignee_verified);
                 if !assignee_verified {
                     //no need to check other parts, must have exact one assignee
-                    continue;
-                }
-
-                //do assigner verification
-                let candidate_assigner = candidate_assigner.clone();
-                let policy_assigner = permission.get_assigner().clone();
-                let mut assigner_verified = false;
-                let assigner = permission.get_assigner();
-                if candidate_assigner.is_some() && assigner.is_some() {
-                    let union = policy_assigner.unwrap();
-                    let ret = PartyInferencer::infer_party(world,&union,&candidate_assigner.unwrap());
-                    if let Ok(true) = ret {
-                        assigner_verified = true;
-                    }
-                }
-                if !assigner_verified {
-                    //no need to check other parts, must have exact one assigner
-                    continue;
-                }
-
-                //do action verification
-                let candidate_action = candidate_action.clone().unwrap();
-                let policy_action = permission.get_action().clone().unwrap();
-                let result = ActionInferencer::infer(world,policy_action,candidate_action);
-                let mut action_verified = false;
-                if let Ok(true) = result {
-                    action_verified = true;
-                }
-                if !action_verified {
-                    //no need to check other parts, must have exact one action
-                    continue;
+                    return false;
                 }
+                decision.record(kind, rule.get_uid(), EvalStep::Assigner, assigner_verified);
+                assigner_verified
+            }
+            PartyMatch::Assigner => {
+                decision.record(kind, rule.get_uid(), EvalStep::Assigner, assigner_verified);
+                assigner_verified
+            }
+            PartyMatch::Either => {
+                let assignee_verified = RuleMatcher::match_party(world, rule.get_assignee(), req.get_assignee());
+                decision.record(kind.clone(), rule.get_uid(), EvalStep::Assignee, assignee_verified);
+                decision.record(kind, rule.get_uid(), EvalStep::Assigner, assigner_verified);
+                //at least one of assignee or assigner must be verified
+                assignee_verified || assigner_verified
+            }
+        }
+    }
 
-                //do target verification
-                let candidate_target = req.get_target().clone().unwrap();
-                let policy_target = permission.get_target().clone().unwrap();
-                let result = AssetInferencer::infer(world,policy_target,candidate_target);
-                let mut target_verified = false;
-                if let Ok(true) = result {
-                    target_verified = true;
-                }
-                if !target_verified {
-                    //no need to check other parts, must have exact one action
-                    continue;
-                }
+    fn match_party(world: &mut StateWorld, party: &Option<PartyUnion>, candidate: Option<Party>) -> bool {
+        match (party, candidate) {
+            (Some(party), Some(candidate)) => matches!(PartyInferencer::infer_party(world, party, &candidate), Ok(true)),
+            _ => false,
+        }
+    }
 
-                //check constraint
-                let policy_constraint = permission.get_constraint();
-                let mut constraint_verified = false;
-                if let Some(constraint) = policy_constraint {
-                    let ret = ConstraintInference::infer(world,constraint);
-                    if let Ok(true) = ret {
-                        constraint_verified = true;
-                    }
-                }else {
-                    //for no constraint, it is always permitted
-                    constraint_verified = true;
-                }
+    //Constraints found indeterminate while checking the rule are kept in the decision,
+    //in strict mode they deny the request
+    fn strict_reason(world: &mut StateWorld, kind: RuleKind, rule: &Option<IriBuf>, decision: &mut Decision) -> Option<String> {
+        let found = world.take_indeterminate();
+        if found.is_empty() {
+            return None;
+        }
+        let reasons = found.iter().map(|i| i.reason.clone()).collect::<Vec<_>>().join("; ");
+        decision.add_indeterminate(kind, rule, found);
+        if world.strict {
+            return Some(format!("constraint indeterminate: {}", reasons));
+        }
+        None
+    }
 
-                if constraint_verified {
-                    //every thing ok here, a permission is matched already
-                    permitted = true;
-                    break;
-                }
-            }
+    fn match_constraint(world: &mut StateWorld, rule: &Rule, kind: RuleKind, decision: &mut Decision) -> Result<bool, anyhow::Error> {
+        let mut constraint_verified = true;
+        if let Some(constraint) = rule.get_constraint() {
+            constraint_verified = ConstraintInference::infer(world, constraint)?;
         }
+        decision.record(kind, rule.get_uid(), EvalStep::Constraint, constraint_verified);
+        Ok(constraint_verified)
+    }
+}
 
-        //check prohibition
-        if permitted {
-            let mut prohibited = false;
-            if let Some(prohibits) = prohibitions {
-                for prohibit in prohibits {
-                    let candidate_assignee = candidate_assignee.clone();
-                    let policy_assignee = prohibit.get_assignee().clone();
-
-                    //do assignee verification
-                    let mut assignee_verified = false;
-                    if candidate_assignee.is_some() && policy_assignee.is_some() {
-                        let union = policy_assignee.unwrap();
-                        let ret = PartyInferencer::infer_party(world,&union,&candidate_assignee.unwrap());
-                        if let Ok(true) = ret {
-                            assignee_verified = true;
-                        }
-                    }
-                    if !assignee_verified {
-                        //no need to check other parts, must have exact one assignee
-                        continue;
-                    }
-
-                    //do assigner verification
-                    let candidate_assigner = candidate_assigner.clone();
-                    let policy_assigner = prohibit.get_assigner().clone();
-                    let mut assigner_verified = false;
-                    let assigner = prohibit.get_assigner();
-                    if candidate_assigner.is_some() && assigner.is_some() {
-                        let union = policy_assigner.unwrap();
-                        let ret = PartyInferencer::infer_party(world,&union,&candidate_assigner.unwrap());
-                        if let Ok(true) = ret {
-                            assigner_verified = true;
-                        }
-                    }
-                    if !assigner_verified {
-                        //no need to check other parts, must have exact one assigner
-                        continue;
-                    }
-
-                    //do action verification
-                    let candidate_action = candidate_action.clone().unwrap();
-                    let policy_action = prohibit.get_action().clone().unwrap();
-                    let result = ActionInferencer::infer(world,policy_action,candidate_action);
-                    let mut action_verified = false;
-                    if let Ok(true) = result {
-                        action_verified = true;
-                    }
-
-                    if !action_verified {
-                        //no need to check other parts, must have exact one action
-                        continue;
-                    }
-
-                    //do target verification
-                    let candidate_target = req.get_target().clone().unwrap();
-                    let policy_target = prohibit.get_target().clone().unwrap();
-                    let result = AssetInferencer::infer(world,policy_target,candidate_target);
-                    let mut target_verified = false;
-                    if let Ok(true) = result {
-                        target_verified = true;
-                    }
-                    if !target_verified {
-                        continue;
-                    }
-
-                    //check constraint
-                    let policy_constraint = prohibit.get_constraint();
-                    let mut constraint_verified = false;
-                    if let Some(constraint) = policy_constraint {
-                        let ret = ConstraintInference::infer(world,constraint);
-                        if let Ok(true) = ret {
-                            constraint_verified = true;
-                        }
-                    } else {
-                        constraint_verified = true;
-                    }
+pub struct PolicyEngine;
 
-                    if constraint_verified {
-                        prohibited = true;
-                        break;
-                    }
-                }
+impl PolicyEngine {
+    pub fn find_world_key(policy: &PolicyUnion) -> Option<IriBuf> {
+        match policy {
+            PolicyUnion::Privacy(p) => {
+                return p.policy.get_uid().clone();
             }
-            if prohibited {
-                //already permitted, need to check conflict strategy
-                return match conflict {
-                    ConflictStrategy::perm => {
-                        Ok(true)
-                    }
-                    ConflictStrategy::prohibit => {
-                        Ok(false)
-                    }
-                    ConflictStrategy::invalid => {
-                        Ok(false)
-                    }
-                }
+            PolicyUnion::Request(r) => {
+                return  r.policy.get_uid().clone();
             }
-            return Ok(true);
-        }
-
-        //here not matched any permission at this level, need to check inheritFrom
-        let inheritFrom = policy.get_inheritFrom().clone();
-        let inheritFrom = inheritFrom.unwrap();
-        for inherit in inheritFrom {
-            let inherit_policy = world.get_policy(inherit.to_string());
-            if let Some(inherit_policy) = inherit_policy {
-                let result = PolicyEngine::eval(world,&inherit_policy,req);
-                if let Ok(true) = result {
-                    return Ok(true);
-                }
+            PolicyUnion::Assert(a) => {
+                return  a.policy.get_uid().clone();
+            }
+            PolicyUnion::Set(s) => {
+                return  s.policy.get_uid().clone();
+            }
+            PolicyUnion::Agreement(p) => {
+                return  p.policy.get_uid().clone();
+            }
+            PolicyUnion::Offer(o) => {
+                return  o.policy.get_uid().clone();
+            }
+            PolicyUnion::Ticket(s) => {
+                return  s.policy.get_uid().clone();
             }
         }
-
-        Ok(false)
     }
-}
-
-impl Evaluator for Request {
-    fn eval(&self,world: &mut StateWorld,req: &OdrlRequest) -> Result<bool, anyhow::Error> {
-        let policy = &self.policy;
-        let mut conflict = policy.get_conflict().clone();
-        if conflict.is_none() {
-            conflict = Some(ConflictStrategy::perm);
-        }
-        let conflict = conflict.unwrap();
-
-        let candidate = req.get_action().clone();
-        if candidate.is_none() {
-            return Ok(false);
-        }
 
-        //check allow permissions
-        let permissions = policy.get_permission();
-        if permissions.is_none() {
-            return Ok(false);
+    pub fn decide(world: &mut StateWorld, policy: &PolicyUnion,req: &OdrlRequest) -> Result<Decision, anyhow::Error> {
+        //merge inherited rules first, the combined policy is evaluated as a whole
+        let resolved = match GLOBAL_POLICY_STORE.resolve(policy) {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                return Ok(Decision::new(DecisionEffect::Indeterminate).with_reason(&e.to_string()));
+            }
+        };
+
+        let decision = match &resolved {
+            PolicyUnion::Privacy(p) => p.decide(world,req)?,
+            PolicyUnion::Request(r) => r.decide(world,req)?,
+            PolicyUnion::Assert(a) => a.decide(world,req)?,
+            PolicyUnion::Set(s) => s.decide(world,req)?,
+            PolicyUnion::Agreement(p) => p.decide(world,req)?,
+            PolicyUnion::Offer(o) => o.decide(world,req)?,
+            PolicyUnion::Ticket(s) => s.decide(world,req)?,
+        };
+
+        //a denied prohibited action is a violation, its remedies become due
+        if decision.effect == DecisionEffect::Deny && decision.prohibition.is_some() {
+            PolicyEngine::record_violation(world, &resolved, req, &decision);
         }
-        let prohibitions = policy.get_prohibition();
-
-        /*
-         * 1. Check Assigner and Assignee match in each permission
-         * 2. Check Target match in each permission
-         * 3. Check Action match in each permission
-         * 4. Check Constraint match in each permission
-         * 5. Check Obligation match in each permission
-         * 6. Check Above match in each prohibition
-         * 7. Check Conflict Strategy
-         */
-        let candidate_assignee = req.get_assignee();
-        let candidate_assigner = req.get_assigner();
-        let candidate_action = req.get_action();
-
-        let mut permitted = false;
-        if let Some(permissions) = permissions {
-            for permission in permissions {
-                let candidate_assignee = candidate_assignee.clone();
-                let policy_assignee = permission.get_assignee().clone();
-                let mut assignee_verified = false;
-                if candidate_assignee.is_some() && policy_assignee.is_some() {
-                    let union = policy_assignee.unwrap();
-                    let ret = PartyInferencer::infer_party(world,&union,&candidate_assignee.unwrap());
-                    if let Ok(true) = ret {
-                        assignee_verified = true;
-                    }
-                }
-                if !assignee_verified {
-                    //no need to check other parts, must have exact one assignee
-                    continue;
-                }
-
-                //do assigner verification
-                let candidate_assigner = candidate_assigner.clone();
-                let policy_assigner = permission.get_assigner().clone();
-                let mut assigner_verified = false;
-                let assigner = permission.get_assigner();
-                if candidate_assigner.is_some() && assigner.is_some() {
-                    let union = policy_assigner.unwrap();
-                    let ret = PartyInferencer::infer_party(world,&union,&candidate_assigner.unwrap());
-                    if let Ok(true) = ret {
-                        assigner_verified = true;
-                    }
-                }
-                if !assigner_verified {
-                    //no need to check other parts, must have exact one assigner
-                    continue;
-                }
-
-                //do action verification
-                let candidate_action = candidate_action.clone().unwrap();
-                let policy_action = permission.get_action().clone().unwrap();
-                let result = ActionInferencer::infer(world,policy_action,candidate_action);
-                let mut action_verified = false;
-                if let Ok(true) = result {
-                    action_verified = true;
-                }
-                if !action_verified {
-                    //no need to check other parts, must have exact one action
-                    continue;
-                }
-
-                //do target verification
-                let candidate_target = req.get_target().clone().unwrap();
-                let policy_target = permission.get_target().clone().unwrap();
-                let result = AssetInferencer::infer(world,policy_target,candidate_target);
-                let mut target_verified = false;
-                if let Ok(true) = result {
-                    target_verified = true;
-                }
-                if !target_verified {
-                    //no need to check other parts, must have exact one action
-                    continue;
-                }
+        Ok(decision)
+    }
 
-                //check constraint
-                let policy_constraint = permission.get_constraint();
-                let mut constraint_verified = false;
-                if let Some(constraint) = policy_constraint {
-                    let ret = ConstraintInference::infer(world,constraint);
-                    if let Ok(true) = ret {
-                        constraint_verified = true;
-                    }
-                }else {
-                    //for no constraint, just match
-                    constraint_verified = true;
-                }
+    //Resolve the async left operand providers of the policy first, then decide on the prefetched request
+    pub async fn decide_async(world: &mut StateWorld, policy: &PolicyUnion, req: &OdrlRequest) -> Result<Decision, anyhow::Error> {
+        let req = match GLOBAL_POLICY_STORE.resolve(policy) {
+            Ok(resolved) => GLOBAL_OPERAND_PROVIDERS.prefetch(PolicyEngine::find_policy(&resolved), req).await?,
+            Err(_) => req.clone(),
+        };
+        PolicyEngine::decide(world, policy, &req)
+    }
 
-                if constraint_verified {
-                    //every thing ok here, a permission is matched already
-                    permitted = true;
-                    break;
-                }
+    fn record_violation(world: &mut StateWorld, policy: &PolicyUnion, req: &OdrlRequest, decision: &Decision) {
+        let party = req.assignee.as_ref().map(|iri| iri.as_str().to_string()).unwrap_or_default();
+        let prohibition = decision.prohibition.as_ref().map(|iri| iri.as_str().to_string());
+        world.add_violation(Violation {
+            party: party.clone(),
+            prohibition: prohibition.clone(),
+            action: req.action.as_ref().map(|iri| iri.as_str().to_string()),
+            target: req.target.as_ref().map(|iri| iri.as_str().to_string()),
+            time: world.now(),
+        });
+
+        let prohibit = prohibition.and_then(|uid| PolicyEngine::find_policy(policy).find_prohibition(&uid));
+        if let Some(remedies) = prohibit.as_ref().and_then(|prohibit| prohibit.get_remedy().as_ref()) {
+            for remedy in remedies {
+                world.activate_remedy(&party, &DutyInferencer::duty_key(remedy));
             }
         }
+    }
 
-        //check prohibition
-        if permitted {
-            let mut prohibited = false;
-            if let Some(prohibits) = prohibitions {
-                for prohibit in prohibits {
-                    let candidate_assignee = candidate_assignee.clone();
-                    let policy_assignee = prohibit.get_assignee().clone();
-
-                    //do assignee verification
-                    let mut assignee_verified = false;
-                    if candidate_assignee.is_some() && policy_assignee.is_some() {
-                        let union = policy_assignee.unwrap();
-                        let ret = PartyInferencer::infer_party(world,&union,&candidate_assignee.unwrap());
-                        if let Ok(true) = ret {
-                            assignee_verified = true;
-                        }
-                    }
-                    if !assignee_verified {
-                        //no need to check other parts, must have exact one assignee
-                        continue;
-                    }
-
-                    //do assigner verification
-                    let candidate_assigner = candidate_assigner.clone();
-                    let policy_assigner = prohibit.get_assigner().clone();
-                    let mut assigner_verified = false;
-                    let assigner = prohibit.get_assigner();
-                    if candidate_assigner.is_some() && assigner.is_some() {
-                        let union = policy_assigner.unwrap();
-                        let ret = PartyInferencer::infer_party(world,&union,&candidate_assigner.unwrap());
-                        if let Ok(true) = ret {
-                            assigner_verified = true;
-                        }
-                    }
-                    if !assigner_verified {
-                        //no need to check other parts, must have exact one assigner
-                        continue;
-                    }
-
-                    //do action verification
-                    let candidate_action = candidate_action.clone().unwrap();
-                    let policy_action = prohibit.get_action().clone().unwrap();
-                    let result = ActionInferencer::infer(world,policy_action,candidate_action);
-                    let mut action_verified = false;
-                    if let Ok(true) = result {
-                        action_verified = true;
-                    }
-
-                    if !action_verified {
-                        //no need to check other parts, must have exact one action
-                        continue;
-                    }
-
-                    //do target verification
-                    let candidate_target = req.get_target().clone().unwrap();
-                    let policy_target = prohibit.get_target().clone().unwrap();
-                    let result = AssetInferencer::infer(world,policy_target,candidate_target);
-                    let mut target_verified = false;
-                    if let Ok(true) = result {
-                        target_verified = true;
-                    }
-                    if !target_verified {
-                        continue;
-                    }
-
-                    //check constraint
-                    let policy_constraint = prohibit.get_constraint();
-                    let mut constraint_verified = false;
-                    if let Some(constraint) = policy_constraint {
-                        let ret = ConstraintInference::infer(world,constraint);
-                        if let Ok(true) = ret {
-                            constraint_verified = true;
-                        }
-                    } else {
-                        constraint_verified = true;
-                    }
+    pub fn eval(world: &mut StateWorld, policy: &PolicyUnion,req: &OdrlRequest) -> Result<bool, anyhow::Error> {
+        let decision = PolicyEngine::decide(world,policy,req)?;
+        Ok(decision.is_permitted())
+    }
 
-                    if constraint_verified {
-                        prohibited = true;
-                        break;
-                    }
-                }
-            }
-            if prohibited {
-                //already permitted, need to check conflict strategy
-                return match conflict {
-                    ConflictStrategy::perm => {
-                        Ok(true)
-                    }
-                    ConflictStrategy::prohibit => {
-                        Ok(false)
-                    }
-                    ConflictStrategy::invalid => {
-                        Ok(false)
-                    }
-                }
-            }
-            return Ok(true);
+    pub fn find_policy(policy: &PolicyUnion) -> &Policy {
+        match policy {
+            PolicyUnion::Privacy(p) => &p.policy,
+            PolicyUnion::Request(r) => &r.policy,
+            PolicyUnion::Assert(a) => &a.policy,
+            PolicyUnion::Set(s) => &s.policy,
+            PolicyUnion::Agreement(p) => &p.policy,
+            PolicyUnion::Offer(o) => &o.policy,
+            PolicyUnion::Ticket(s) => &s.policy,
         }
+    }
 
-        //here not matched any permission at this level, need to check inheritFrom
-        let inheritFrom = policy.get_inheritFrom().clone();
-        let inheritFrom = inheritFrom.unwrap();
-        for inherit in inheritFrom {
-            let inherit_policy = world.get_policy(inherit.to_string());
-            if let Some(inherit_policy) = inherit_policy {
-                let result = PolicyEngine::eval(world,&inherit_policy,req);
-                if let Ok(true) = result {
-                    return Ok(true);
-                }
-            }
+    pub fn find_policy_mut(policy: &mut PolicyUnion) -> &mut Policy {
+        match policy {
+            PolicyUnion::Privacy(p) => &mut p.policy,
+            PolicyUnion::Request(r) => &mut r.policy,
+            PolicyUnion::Assert(a) => &mut a.policy,
+            PolicyUnion::Set(s) => &mut s.policy,
+            PolicyUnion::Agreement(p) => &mut p.policy,
+            PolicyUnion::Offer(o) => &mut o.policy,
+            PolicyUnion::Ticket(s) => &mut s.policy,
         }
-
-        Ok(false)
     }
-}
 
-impl Evaluator for Assert {
-    fn eval(&self,world: &mut StateWorld,req: &OdrlRequest) -> Result<bool, anyhow::Error> {
-        let policy = &self.policy;
-        let mut conflict = policy.get_conflict().clone();
-        if conflict.is_none() {
-            conflict = Some(ConflictStrategy::perm);
+    //Mark a duty of the policy as violated by the party, its consequences become pending
+    pub fn violate_duty(world: &mut StateWorld, policy: &PolicyUnion, party: &str, duty: &str) -> Result<(), anyhow::Error> {
+        let found = PolicyEngine::find_policy(policy).find_duty(duty);
+        match found {
+            Some(found) => {
+                DutyInferencer::violate(world, &found, party);
+                Ok(())
+            }
+            None => Err(anyhow!("Duty {} not found in policy", duty)),
         }
-        let conflict = conflict.unwrap();
+    }
 
-        let candidate = req.get_action().clone();
-        if candidate.is_none() {
-            return Ok(false);
+    //Errors of the engine, like comparing mismatched units, fail the evaluation
+    pub(crate) fn policy_constraint_verified(world: &mut StateWorld, policy: &Policy, decision: &mut Decision) -> Result<bool, anyhow::Error> {
+        let mut verified = true;
+        if let Some(constraint) = policy.get_constraint() {
+            verified = ConstraintInference::infer(world, constraint)?;
+            decision.record(RuleKind::Policy, policy.get_uid(), EvalStep::Constraint, verified);
         }
+        Ok(verified)
+    }
 
-        //check allow permissions
-        let permissions = policy.get_permission();
-        if permissions.is_none() {
-            return Ok(false);
-        }
-        let prohibitions = policy.get_prohibition();
-
-        /*
-         * 1. Check Assigner and Assignee match in each permission
-         * 2. Check Target match in each permission
-         * 3. Check Action match in each permission
-         * 4. Check Constraint match in each permission
-         * 5. Check Obligation match in each permission
-         * 6. Check Above match in each prohibition
-         * 7. Check Conflict Strategy
-         */
+    //Check the obligations owed by the requesting party, obligations of other parties are skipped
+    pub(crate) fn obligation_violated(world: &mut StateWorld, obligations: &Vec<Duty>, req: &OdrlRequest, decision: &mut Decision) -> Result<bool, anyhow::Error> {
         let candidate_assignee = req.get_assignee();
-        let candidate_assigner = req.get_assigner();
-        let candidate_action = req.get_action();
-
-        let mut permitted = false;
-        if let Some(permissions) = permissions {
-            for permission in permissions {
-                let candidate_assignee = candidate_assignee.clone();
-                let policy_assignee = permission.get_assignee().clone();
-                let mut assignee_verified = false;
-                if candidate_assignee.is_some() && policy_assignee.is_some() {
-                    let union = policy_assignee.unwrap();
-                    let ret = PartyInferencer::infer_party(world,&union,&candidate_assignee.unwrap());
-                    if let Ok(true) = ret {
-                        assignee_verified = true;
-                    }
-                }
-                // if !assignee_verified {
-                //     //no need to check other parts, must have exact one assignee
-                //     continue;
-                // }
-
-                //do assigner verification
-                let candidate_assigner = candidate_assigner.clone();
-                let policy_assigner = permission.get_assigner().clone();
-                let mut assigner_verified = false;
-                let assigner = permission.get_assigner();
-                if candidate_assigner.is_some() && assigner.is_some() {
-                    let union = policy_assigner.unwrap();
-                    let ret = PartyInferencer::infer_party(world,&union,&candidate_assigner.unwrap());
-                    if let Ok(true) = ret {
-                        assigner_verified = true;
-                    }
-                }
-                // if !assigner_verified {
-                //     //no need to check other parts, must have exact one assigner
-                //     continue;
-                // }
-
-                //at least one of assignee or assigner must be verified
-                if !assignee_verified && !assigner_verified {
-                    //both assignee and assigner not verified
+        let mut violated = false;
+        for obligation in obligations {
+            if let Some(assignee) = obligation.get_assignee() {
+                let applies = match &candidate_assignee {
+                    Some(candidate) => matches!(PartyInferencer::infer_party(world, assignee, candidate), Ok(true)),
+                    None => false,
+                };
+                if !applies {
                     continue;
                 }
-
-                //do action verification
-                let candidate_action = candidate_action.clone().unwrap();
-                let policy_action = permission.get_action().clone().unwrap();
-                let result = ActionInferencer::infer(world,policy_action,candidate_action);
-                let mut action_verified = false;
-                if let Ok(true) = result {
-                    action_verified = true;
-                }
-                if !action_verified {
-                    //no need to check other parts, must have exact one action
-                    continue;
-                }
-
-                //do target verification
-                let candidate_target = req.get_target().clone().unwrap();
-                let policy_target = permission.get_target().clone().unwrap();
-                let result = AssetInferencer::infer(world,policy_target,candidate_target);
-                let mut target_verified = false;
-                if let Ok(true) = result {
-                    target_verified = true;
-                }
-                if !target_verified {
-                    //no need to check other parts, must have exact one action
-                    continue;
-                }
-
-                //check constraint
-                let policy_constraint = permission.get_constraint();
-                let mut constraint_verified = false;
-                if let Some(constraint) = policy_constraint {
-                    let ret = ConstraintInference::infer(world,constraint);
-                    if let Ok(true) = ret {
-                        constraint_verified = true;
-                    }
-                } else {
-                    constraint_verified = true;
-                }
-
-                if constraint_verified {
-                    //every thing ok here, a permission is matched already
-                    permitted = true;
-                    break;
-                }
             }
-        }
-
-        //check prohibition
-        if permitted {
-            let mut prohibited = false;
-            if let Some(prohibits) = prohibitions {
-                for prohibit in prohibits {
-                    let candidate_assignee = candidate_assignee.clone();
-                    let policy_assignee = prohibit.get_assignee().clone();
-
-                    //do assignee verification
-                    let mut assignee_verified = false;
-                    if candidate_assignee.is_some() && policy_assignee.is_some() {
-                        let union = policy_assignee.unwrap();
-                        let ret = PartyInferencer::infer_party(world,&union,&candidate_assignee.unwrap());
-                        if let Ok(true) = ret {
-                            assignee_verified = true;
-                        }
-                    }
-                    // if !assignee_verified {
-                    //     //no need to check other parts, must have exact one assignee
-                    //     continue;
-                    // }
-
-                    //do assigner verification
-                    let candidate_assigner = candidate_assigner.clone();
-                    let policy_assigner = prohibit.get_assigner().clone();
-                    let mut assigner_verified = false;
-                    let assigner = prohibit.get_assigner();
-                    if candidate_assigner.is_some() && assigner.is_some() {
-                        let union = policy_assigner.unwrap();
-                        let ret = PartyInferencer::infer_party(world,&union,&candidate_assigner.unwrap());
-                        if let Ok(true) = ret {
-                            assigner_verified = true;
-                        }
-                    }
-                    // if !assigner_verified {
-                    //     //no need to check other parts, must have exact one assigner
-                    //     continue;
-                    // }
-
-                    //at least one of assignee or assigner must be verified
-                    if !assignee_verified && !assigner_verified {
-                        continue;
-                    }
-
-                    //do action verification
-                    let candidate_action = candidate_action.clone().unwrap();
-                    let policy_action = prohibit.get_action().clone().unwrap();
-                    let result = ActionInferencer::infer(world,policy_action,candidate_action);
-                    let mut action_verified = false;
-                    if let Ok(true) = result {
-                        action_verified = true;
-                    }
-
-                    if !action_verified {
-                        //no need to check other parts, must have exact one action
-                        continue;
-                    }
-
-                    //do target verification
-                    let candidate_target = req.get_target().clone().unwrap();
-                    let policy_target = prohibit.get_target().clone().unwrap();
-                    let result = AssetInferencer::infer(world,policy_target,candidate_target);
-                    let mut target_verified = false;
-                    if let Ok(true) = result {
-                        target_verified = true;
-                    }
-                    if !target_verified {
-                        continue;
-                    }
-
-                    //check constraint
-                    let policy_constraint = prohibit.get_constraint();
-                    let mut constraint_verified = false;
-                    if let Some(constraint) = policy_constraint {
-                        let ret = ConstraintInference::infer(world,constraint);
-                        if let Ok(true) = ret {
-                            constraint_verified = true;
-                        }
-                    } else {
-                        //this is for no constraint in policy
-                        constraint_verified = true;
-                    }
 
-                    if constraint_verified {
-                        prohibited = true;
-                        break;
-                    }
-                }
+            let failed = DutyInferencer::is_violated(world, obligation, &candidate_assignee)?;
+            decision.record(RuleKind::Obligation, obligation.get_rule().get_uid(), EvalStep::Duty, !failed);
+            if failed {
+                violated = true;
             }
-            if prohibited {
-                //already permitted, need to check conflict strategy
-                return match conflict {
-                    ConflictStrategy::perm => {
-                        Ok(true)
-                    }
-                    ConflictStrategy::prohibit => {
-                        Ok(false)
-                    }
-                    ConflictStrategy::invalid => {
-                        Ok(false)
-                    }
-                }
-            }
-            return Ok(true);
         }
+        Ok(violated)
+    }
 
-        //here not matched any permission at this level, need to check inheritFrom
-        let inheritFrom = policy.get_inheritFrom().clone();
-        let inheritFrom = inheritFrom.unwrap();
-        for inherit in inheritFrom {
-            let inherit_policy = world.get_policy(inherit.to_string());
-            if let Some(inherit_policy) = inherit_policy {
-                let result = PolicyEngine::eval(world,&inherit_policy,req);
-                if let Ok(true) = result {
-                    return Ok(true);
-                }
-            }
+    pub(crate) fn unsatisfied_reason(decision: &Decision) -> &'static str {
+        let duty_failed = decision.get_trace().iter()
+            .any(|entry| entry.step == EvalStep::Duty && !entry.passed);
+        if duty_failed {
+            "permission duty not fulfilled"
+        } else {
+            "permission constraint not satisfied"
         }
-
-        Ok(false)
     }
 }
+#[cfg(test)]
+mod tests {
+    use iref::IriBuf;
+    use crate::model::action::{Action, ActionType};
+    use crate::model::asset::{Asset, AssetUnion};
+    use crate::model::decision::DecisionEffect;
+    use crate::model::error::OdrlError;
+    use crate::model::eval::Evaluator;
+    use crate::model::party::{Party, PartyUnion};
+    use crate::model::permission::Permission;
+    use crate::model::prohibition::Prohibition;
+    use crate::model::rule::Rule;
+    use crate::model::stateworld::StateWorld;
+    use super::*;
+
+    fn iri(s: &str) -> Option<IriBuf> {
+        IriBuf::new(s.to_string()).ok()
+    }
 
-impl Evaluator for Ticket {
-    fn eval(&self,world: &mut StateWorld,req: &OdrlRequest) -> Result<bool, anyhow::Error> {
-        let policy = &self.policy;
-        let mut conflict = policy.get_conflict().clone();
-        if conflict.is_none() {
-            conflict = Some(ConflictStrategy::perm);
-        }
-        let conflict = conflict.unwrap();
-
-        let candidate = req.get_action().clone();
-        if candidate.is_none() {
-            return Ok(false);
-        }
-
-        //check allow permissions
-        let permissions = policy.get_permission();
-        if permissions.is_none() {
-            return Ok(false);
-        }
-        let prohibitions = policy.get_prohibition();
-
-        /*
-         * 1. Check Assigner and Assignee match in each permission
-         * 2. Check Target match in each permission
-         * 3. Check Action match in each permission
-         * 4. Check Constraint match in each permission
-         * 5. Check Obligation match in each permission
-         * 6. Check Above match in each prohibition
-         * 7. Check Conflict Strategy
-         */
-        let candidate_assignee = req.get_assignee();
-        let candidate_assigner = req.get_assigner();
-        let candidate_action = req.get_action();
-
-        let mut permitted = false;
-        if let Some(permissions) = permissions {
-            for permission in permissions {
-                // no assignee in ticket
-                // let candidate_assignee = candidate_assignee.clone();
-                // let policy_assignee = permission.get_assignee().clone();
-                // let mut assignee_verified = false;
-                // if candidate_assignee.is_some() && policy_assignee.is_some() {
-                //     let union = PartyUnion::Party(policy_assignee.unwrap());
-                //     let ret = PartyInferencer::infer_party(world,&union,&candidate_assignee.unwrap());
-                //     if let Ok(true) = ret {
-                //         assignee_verified = true;
-                //     }
-                // }
-                // if !assignee_verified {
-                //     //no need to check other parts, must have exact one assignee
-                //     continue;
-                // }
-
-                //do assigner verification
-                let candidate_assigner = candidate_assigner.clone();
-                let policy_assigner = permission.get_assigner().clone();
-                let mut assigner_verified = false;
-                let assigner = permission.get_assigner();
-                if candidate_assigner.is_some() && assigner.is_some() {
-                    let union = policy_assigner.unwrap();
-                    let ret = PartyInferencer::infer_party(world,&union,&candidate_assigner.unwrap());
-                    if let Ok(true) = ret {
-                        assigner_verified = true;
-                    }
-                }
-                if !assigner_verified {
-                    //no need to check other parts, must have exact one assigner
-                    continue;
-                }
-
-                //do action verification
-                let candidate_action = candidate_action.clone().unwrap();
-                let policy_action = permission.get_action().clone().unwrap();
-                let result = ActionInferencer::infer(world,policy_action,candidate_action);
-                let mut action_verified = false;
-                if let Ok(true) = result {
-                    action_verified = true;
-                }
-                if !action_verified {
-                    //no need to check other parts, must have exact one action
-                    continue;
-                }
+    fn party(s: &str) -> Option<PartyUnion> {
+        Some(PartyUnion::Party(Party::builder().uid(iri(s)).build()))
+    }
 
-                //do target verification
-                let candidate_target = req.get_target().clone().unwrap();
-                let policy_target = permission.get_target().clone().unwrap();
-                let result = AssetInferencer::infer(world,policy_target,candidate_target);
-                let mut target_verified = false;
-                if let Ok(true) = result {
-                    target_verified = true;
-                }
-                if !target_verified {
-                    //no need to check other parts, must have exact one action
-                    continue;
-                }
+    fn rule(assigner: Option<&str>, assignee: Option<&str>) -> Rule {
+        Rule::builder()
+            .action(Some(Action::builder().actionType(ActionType::Play).build()))
+            .target(Some(AssetUnion::Asset(Asset::builder().uid(iri("https://example.com/media/video1.mp4")).build())))
+            .assigner(assigner.and_then(party))
+            .assignee(assignee.and_then(party))
+            .build()
+    }
 
-                //check constraint
-                let policy_constraint = permission.get_constraint();
-                let mut constraint_verified = false;
-                if let Some(constraint) = policy_constraint {
-                    let ret = ConstraintInference::infer(world,constraint);
-                    if let Ok(true) = ret {
-                        constraint_verified = true;
-                    }
-                } else {
-                    constraint_verified = true;
-                }
+    fn policy(assigner: Option<&str>, assignee: Option<&str>) -> Policy {
+        let permission = Permission::builder().duty(rule(assigner, assignee)).build();
+        Policy::builder().uid(iri("http://abc.tds/policy/matcher")).permission(Some(vec![permission])).build()
+    }
 
-                if constraint_verified {
-                    //every thing ok here, a permission is matched already
-                    permitted = true;
-                    break;
-                }
-            }
-        }
+    fn request(assignee: &str) -> OdrlRequest {
+        let mut req = OdrlRequest::default();
+        req.set_action(iri("http://www.w3.org/ns/odrl/2/play"));
+        req.set_assigner(iri("https://aa/bb"));
+        req.set_assignee(iri(assignee));
+        req.set_target(iri("https://example.com/media/video1.mp4"));
+        req
+    }
 
-        //check prohibition
-        if permitted {
-            let mut prohibited = false;
-            if let Some(prohibits) = prohibitions {
-                for prohibit in prohibits {
-                    let candidate_assignee = candidate_assignee.clone();
-                    let policy_assignee = prohibit.get_assignee().clone();
-
-                    //do assignee verification
-                    let mut assignee_verified = false;
-                    if candidate_assignee.is_some() && policy_assignee.is_some() {
-                        let union = policy_assignee.unwrap();
-                        let ret = PartyInferencer::infer_party(world,&union,&candidate_assignee.unwrap());
-                        if let Ok(true) = ret {
-                            assignee_verified = true;
-                        }
-                    }
-                    if !assignee_verified {
-                        //no need to check other parts, must have exact one assignee
-                        continue;
-                    }
+    fn error_of(result: Result<Decision, anyhow::Error>) -> OdrlError {
+        result.unwrap_err().downcast::<OdrlError>().unwrap()
+    }
 
-                    //do assigner verification
-                    let candidate_assigner = candidate_assigner.clone();
-                    let policy_assigner = prohibit.get_assigner().clone();
-                    let mut assigner_verified = false;
-                    let assigner = prohibit.get_assigner();
-                    if candidate_assigner.is_some() && assigner.is_some() {
-                        let union = policy_assigner.unwrap();
-                        let ret = PartyInferencer::infer_party(world,&union,&candidate_assigner.unwrap());
-                        if let Ok(true) = ret {
-                            assigner_verified = true;
-                        }
-                    }
-                    if !assigner_verified {
-                        //no need to check other parts, must have exact one assigner
-                        continue;
-                    }
+    #[test]
+    fn test_policy_type_requirements() {
+        let mut world = StateWorld::new("http://abc.tds/policy/matcher");
+        let req = request("https://aa/cc");
+
+        let agreement = Agreement::builder().policy(policy(Some("https://aa/bb"), None)).build();
+        assert!(matches!(error_of(agreement.decide(&mut world, &req)), OdrlError::MissingAgreementAssignee));
+        let agreement = Agreement::builder().policy(policy(None, Some("https://aa/cc"))).build();
+        assert!(matches!(error_of(agreement.decide(&mut world, &req)), OdrlError::MissingAgreementAssigner));
+
+        let offer = Offer::builder().policy(policy(None, None)).build();
+        assert!(matches!(error_of(offer.decide(&mut world, &req)), OdrlError::MissingOfferAssigner));
+        let mut untargeted = policy(Some("https://aa/bb"), None);
+        untargeted.get_permission_mut().as_mut().unwrap()[0].get_duty_mut().set_target(None);
+        let offer = Offer::builder().policy(untargeted).build();
+        assert!(matches!(error_of(offer.decide(&mut world, &req)), OdrlError::MissingOfferTarget));
+    }
 
-                    //do action verification
-                    let candidate_action = candidate_action.clone().unwrap();
-                    let policy_action = prohibit.get_action().clone().unwrap();
-                    let result = ActionInferencer::infer(world,policy_action,candidate_action);
-                    let mut action_verified = false;
-                    if let Ok(true) = result {
-                        action_verified = true;
-                    }
+    #[test]
+    fn test_policy_type_party_match() {
+        let mut world = StateWorld::new("http://abc.tds/policy/matcher");
+        let req = request("https://aa/dd");
+
+        //offer and ticket are open to any assignee when they declare none
+        let offer = Offer::builder().policy(policy(Some("https://aa/bb"), None)).build();
+        assert!(offer.eval(&mut world, &req).unwrap());
+        let ticket = Ticket::builder().policy(policy(Some("https://aa/bb"), None)).build();
+        assert!(ticket.eval(&mut world, &req).unwrap());
+
+        //a declared assignee must match as well
+        let offer = Offer::builder().policy(policy(Some("https://aa/bb"), Some("https://aa/cc"))).build();
+        assert_eq!(offer.decide(&mut world, &req).unwrap().effect, DecisionEffect::NotApplicable);
+        assert!(offer.eval(&mut world, &request("https://aa/cc")).unwrap());
+        let ticket = Ticket::builder().policy(policy(Some("https://aa/bb"), Some("https://aa/cc"))).build();
+        assert_eq!(ticket.decide(&mut world, &req).unwrap().effect, DecisionEffect::NotApplicable);
+        assert!(ticket.eval(&mut world, &request("https://aa/cc")).unwrap());
+
+        //set requires both parties
+        let set = Set::builder().policy(policy(Some("https://aa/bb"), Some("https://aa/cc"))).build();
+        assert_eq!(set.decide(&mut world, &req).unwrap().effect, DecisionEffect::NotApplicable);
+        assert!(set.eval(&mut world, &request("https://aa/cc")).unwrap());
+
+        //assertion needs one of them
+        let assert = Assert::builder().policy(policy(Some("https://aa/bb"), Some("https://aa/cc"))).build();
+        assert!(assert.eval(&mut world, &req).unwrap());
+    }
 
-                    if !action_verified {
-                        //no need to check other parts, must have exact one action
-                        continue;
-                    }
+    fn refinement(left: &str, right: &str) -> Option<Vec<ConstraintUnion>> {
+        use crate::model::constraint::Constraint;
+        use crate::model::constraint_operator::ConstraintOperator;
+        use crate::model::constraint_right_operand::{ConstraintRightOperand, RightOperandType};
+
+        let mut constraint = Constraint::new("http://www.w3.org/ns/odrl/2/Constraint");
+        constraint.set_operator(Some(ConstraintOperator::try_from("eq").unwrap()));
+        constraint.set_dataType("string".to_string());
+        constraint.set_leftOperand(Some(ConstraintLeftOperand::try_from(left).unwrap()));
+        constraint.set_rightOperand(Some(ConstraintRightOperand::builder().value(Some(right.to_string())).ty(RightOperandType::Literal).build()));
+        Some(vec![ConstraintUnion::Constraint(constraint)])
+    }
 
-                    //do target verification
-                    let candidate_target = req.get_target().clone().unwrap();
-                    let policy_target = prohibit.get_target().clone().unwrap();
-                    let result = AssetInferencer::infer(world,policy_target,candidate_target);
-                    let mut target_verified = false;
-                    if let Ok(true) = result {
-                        target_verified = true;
-                    }
-                    if !target_verified {
-                        continue;
-                    }
+    #[test]
+    fn test_candidate_profile() {
+        use crate::model::asset::AssetCollection;
+        use crate::model::party::PartyCollection;
+
+        let mut world = StateWorld::new("http://abc.tds/policy/matcher");
+        //world state must not satisfy refinements on behalf of the candidate
+        world.add_state("http://www.w3.org/ns/odrl/2/language", "en");
+
+        let mut collection_rule = rule(Some("https://aa/bb"), None);
+        collection_rule.set_assignee(Some(PartyUnion::PartyCollection(PartyCollection::builder().source(iri("https://aa/team")).build())));
+        collection_rule.set_target(Some(AssetUnion::AssetCollection(AssetCollection::builder()
+            .source(iri("https://example.com/media"))
+            .refinement(refinement("language", "en"))
+            .build())));
+        let permission = Permission::builder().duty(collection_rule).build();
+        let set = Set::builder().policy(Policy::builder().uid(iri("http://abc.tds/policy/matcher")).permission(Some(vec![permission])).build()).build();
+
+        //uid only requests never match a collection
+        let mut req = request("https://aa/cc");
+        assert_eq!(set.decide(&mut world, &req).unwrap().effect, DecisionEffect::NotApplicable);
+
+        req.get_assignee_profile_mut().add_part_of("https://aa/team");
+        req.get_target_profile_mut().add_part_of("https://example.com/media");
+        assert_eq!(set.decide(&mut world, &req).unwrap().effect, DecisionEffect::NotApplicable);
+
+        req.get_target_profile_mut().add_attribute("language", "de");
+        assert_eq!(set.decide(&mut world, &req).unwrap().effect, DecisionEffect::NotApplicable);
+
+        req.get_target_profile_mut().add_attribute("language", "en");
+        assert!(set.eval(&mut world, &req).unwrap());
+    }
 
-                    //check constraint
-                    let policy_constraint = prohibit.get_constraint();
-                    let mut constraint_verified = false;
-                    if let Some(constraint) = policy_constraint {
-                        let ret = ConstraintInference::infer(world,constraint);
-                        if let Ok(true) = ret {
-                            constraint_verified = true;
-                        }
-                    } else {
-                        constraint_verified = true;
-                    }
+    #[test]
+    fn test_action_hierarchy() {
+        let mut world = StateWorld::new("http://abc.tds/policy/matcher");
+        let permitted = |action: ActionType, requested: &str, world: &mut StateWorld| {
+            let mut policy = policy(Some("https://aa/bb"), Some("https://aa/cc"));
+            policy.get_permission_mut().as_mut().unwrap()[0].get_duty_mut().set_action(Some(Action::builder().actionType(action).build()));
+            let mut req = request("https://aa/cc");
+            req.set_action(iri(requested));
+            Set::builder().policy(policy).build().eval(world, &req).unwrap()
+        };
+
+        assert!(permitted(ActionType::Use, "http://www.w3.org/ns/odrl/2/read", &mut world));
+        assert!(permitted(ActionType::Use, "http://www.w3.org/ns/odrl/2/print", &mut world));
+        assert!(permitted(ActionType::Present, "http://www.w3.org/ns/odrl/2/display", &mut world));
+        assert!(permitted(ActionType::Reproduce, "http://www.w3.org/ns/odrl/2/extract", &mut world));
+
+        //the hierarchy is never walked downwards
+        assert!(!permitted(ActionType::Print, "http://www.w3.org/ns/odrl/2/present", &mut world));
+        assert!(!permitted(ActionType::Use, "http://www.w3.org/ns/odrl/2/sell", &mut world));
+        assert!(permitted(ActionType::Transfer, "http://www.w3.org/ns/odrl/2/sell", &mut world));
+    }
 
-                    if constraint_verified {
-                        prohibited = true;
-                        break;
-                    }
-                }
+    #[test]
+    fn test_action_implies() {
+        let mut world = StateWorld::new("http://abc.tds/policy/matcher");
+        let decide = |permitted: Option<ActionType>, prohibited: Option<ActionType>, requested: &str, world: &mut StateWorld| {
+            let mut policy = policy(Some("https://aa/bb"), Some("https://aa/cc"));
+            match permitted {
+                Some(action) => policy.get_permission_mut().as_mut().unwrap()[0].get_duty_mut().set_action(Some(Action::builder().actionType(action).build())),
+                None => policy.set_permission(None),
             }
-            if prohibited {
-                //already permitted, need to check conflict strategy
-                return match conflict {
-                    ConflictStrategy::perm => {
-                        Ok(true)
-                    }
-                    ConflictStrategy::prohibit => {
-                        Ok(false)
-                    }
-                    ConflictStrategy::invalid => {
-                        Ok(false)
-                    }
-                }
+            if let Some(action) = prohibited {
+                let mut rule = rule(Some("https://aa/bb"), Some("https://aa/cc"));
+                rule.set_action(Some(Action::builder().actionType(action).build()));
+                policy.set_prohibition(Some(vec![Prohibition::builder().rule(rule).build()]));
             }
-            return Ok(true);
-        }
-
-        //here not matched any permission at this level, need to check inheritFrom
-        let inheritFrom = policy.get_inheritFrom().clone();
-        let inheritFrom = inheritFrom.unwrap();
-        for inherit in inheritFrom {
-            let inherit_policy = world.get_policy(inherit.to_string());
-            if let Some(inherit_policy) = inherit_policy {
-                let result = PolicyEngine::eval(world,&inherit_policy,req);
-                if let Ok(true) = result {
-                    return Ok(true);
-                }
-            }
-        }
-
-        Ok(false)
+            let mut req = request("https://aa/cc");
+            req.set_action(iri(requested));
+            Set::builder().policy(policy).build().decide(world, &req).unwrap()
+        };
+
+        //'move' implies 'delete', a permission on 'move' does not grant a standalone 'delete'
+        assert_eq!(decide(Some(ActionType::Move), None, "http://www.w3.org/ns/odrl/2/delete", &mut world).effect, DecisionEffect::NotApplicable);
+        assert_eq!(decide(Some(ActionType::Move), None, "http://www.w3.org/ns/odrl/2/move", &mut world).effect, DecisionEffect::Permit);
+        assert_eq!(decide(Some(ActionType::Delete), None, "http://www.w3.org/ns/odrl/2/move", &mut world).effect, DecisionEffect::NotApplicable);
+
+        //a prohibition on 'delete' covers 'move', a prohibition on 'move' does not cover 'delete'
+        let decision = decide(None, Some(ActionType::Delete), "http://www.w3.org/ns/odrl/2/move", &mut world);
+        assert_eq!(decision.effect, DecisionEffect::Deny);
+        assert_eq!(decision.reason.as_deref(), Some("action is prohibited"));
+        assert_eq!(decide(None, Some(ActionType::Move), "http://www.w3.org/ns/odrl/2/delete", &mut world).effect, DecisionEffect::NotApplicable);
     }
-}
-
 
+    #[test]
+    fn test_unconstrained_prohibition() {
+        let mut world = StateWorld::new("http://abc.tds/policy/matcher");
+        let req = request("https://aa/cc");
 
+        let mut policy = policy(Some("https://aa/bb"), Some("https://aa/cc"));
+        let prohibit = Prohibition::builder().rule(rule(Some("https://aa/bb"), Some("https://aa/cc"))).build();
+        policy.set_prohibition(Some(vec![prohibit]));
+        policy.set_conflict(Some(ConflictStrategy::prohibit));
 
-pub struct PolicyEngine;
-
-impl PolicyEngine {
-    pub fn find_world_key(policy: &PolicyUnion) -> Option<IriBuf> {
-        match policy {
-            PolicyUnion::Privacy(p) => {
-                return p.policy.get_uid().clone();
-            }
-            PolicyUnion::Request(r) => {
-                return  r.policy.get_uid().clone();
-            }
-            PolicyUnion::Assert(a) => {
-                return  a.policy.get_uid().clone();
-            }
-            PolicyUnion::Set(s) => {
-                return  s.policy.get_uid().clone();
-            }
-            PolicyUnion::Agreement(p) => {
-                return  p.policy.get_uid().clone();
-            }
-            PolicyUnion::Offer(o) => {
-                return  o.policy.get_uid().clone();
-            }
-            PolicyUnion::Ticket(s) => {
-                return  s.policy.get_uid().clone();
-            }
-        }
-    }
-
-    pub fn eval(world: &mut StateWorld, policy: &PolicyUnion,req: &OdrlRequest) -> Result<bool, anyhow::Error> {
-        match policy {
-            PolicyUnion::Privacy(p) => {
-                return  p.eval(world,req);
-            }
-            PolicyUnion::Request(r) => {
-                return  r.eval(world,req);
-            }
-            PolicyUnion::Assert(a) => {
-                return  a.eval(world,req);
-            }
-            PolicyUnion::Set(s) => {
-                return  s.eval(world,req);
-            }
-            PolicyUnion::Agreement(p) => {
-                return  p.eval(world,req);
-            }
-            PolicyUnion::Offer(o) => {
-                return  o.eval(world,req);
-            }
-            PolicyUnion::Ticket(s) => {
-                return  s.eval(world,req);
-            }
-        }
+        let set = Set::builder().policy(policy).build();
+        assert_eq!(set.decide(&mut world, &req).unwrap().effect, DecisionEffect::Deny);
     }
 }
@@ -0,0 +1,140 @@
+// Copyright 2024 meduo <gao.brian@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(dead_code)]
+
+use std::sync::Arc;
+use dashmap::DashMap;
+use lombok::{Builder, Getter};
+use once_cell::sync::Lazy;
+use crate::model::constraint::ConstraintUnion;
+use crate::model::error::OdrlError;
+use crate::model::policy::{Policy, PolicyEngine, PolicyUnion};
+use crate::model::rule::Rule;
+
+//Loaded policies by uid, used to resolve inheritFrom
+#[derive(Builder,Clone,Getter)]
+pub struct PolicyStore {
+    policies: DashMap<String, PolicyUnion>,
+}
+
+impl PolicyStore {
+    pub fn add_policy(&self, policy: PolicyUnion) {
+        if let Some(uid) = PolicyEngine::find_world_key(&policy) {
+            self.policies.insert(uid.as_str().to_string(), policy);
+        }
+    }
+
+    pub fn get_policy(&self, iri: &str) -> Option<PolicyUnion> {
+        self.policies.get(iri).map(|policy| policy.value().clone())
+    }
+
+    pub fn remove_policy(&self, iri: &str) {
+        self.policies.remove(iri);
+    }
+
+    pub fn clear_policy(&self) {
+        self.policies.clear();
+    }
+
+    /*
+     * Merge every inherited policy into the given one, following ODRL inheritance:
+     * 1. the child gets all rules of its parents, parents resolved recursively
+     * 2. parent policy constraints gate the rules inherited from that parent
+     * 3. the parent conflict strategy applies to the combined set of rules
+     */
+    pub fn resolve(&self, policy: &PolicyUnion) -> Result<PolicyUnion, anyhow::Error> {
+        let mut resolved = policy.clone();
+        let merged = self.merge(PolicyEngine::find_policy(policy), &mut vec![])?;
+        *PolicyEngine::find_policy_mut(&mut resolved) = merged;
+        Ok(resolved)
+    }
+
+    fn merge(&self, policy: &Policy, visiting: &mut Vec<String>) -> Result<Policy, anyhow::Error> {
+        let mut merged = policy.clone();
+        let parents = match policy.get_inheritFrom() {
+            Some(parents) if !parents.is_empty() => parents.clone(),
+            _ => return Ok(merged),
+        };
+
+        let uid = policy.get_uid().as_ref().map(|uid| uid.as_str().to_string()).unwrap_or_default();
+        if visiting.contains(&uid) {
+            return Err(OdrlError::InheritanceCycle(uid).into());
+        }
+        visiting.push(uid.clone());
+
+        let mut conflict = None;
+        for parent in parents {
+            let parent = match self.get_policy(parent.as_str()) {
+                Some(parent) => parent,
+                None => return Err(OdrlError::MissingInheritedPolicy(parent.as_str().to_string()).into()),
+            };
+            let parent = self.merge(PolicyEngine::find_policy(&parent), visiting)?;
+
+            if let Some(strategy) = parent.get_conflict() {
+                if conflict.as_ref().is_some_and(|conflict| conflict != strategy) {
+                    return Err(OdrlError::InheritedConflictMismatch(uid).into());
+                }
+                conflict = Some(strategy.clone());
+            }
+            PolicyStore::inherit_rules(&mut merged, parent);
+        }
+
+        if conflict.is_some() {
+            merged.set_conflict(conflict);
+        }
+        merged.set_inheritFrom(None);
+        visiting.pop();
+        Ok(merged)
+    }
+
+    fn inherit_rules(child: &mut Policy, parent: Policy) {
+        let gate = parent.get_constraint().clone();
+
+        if let Some(permissions) = parent.permission {
+            let inherited = child.permission.get_or_insert_with(Vec::new);
+            for mut permission in permissions {
+                PolicyStore::gate_rule(permission.get_duty_mut(), &gate);
+                inherited.push(permission);
+            }
+        }
+        if let Some(prohibitions) = parent.prohibition {
+            let inherited = child.prohibition.get_or_insert_with(Vec::new);
+            for mut prohibition in prohibitions {
+                PolicyStore::gate_rule(prohibition.get_rule_mut(), &gate);
+                inherited.push(prohibition);
+            }
+        }
+        if let Some(obligations) = parent.obligation {
+            let inherited = child.obligation.get_or_insert_with(Vec::new);
+            for mut obligation in obligations {
+                PolicyStore::gate_rule(obligation.get_rule_mut(), &gate);
+                inherited.push(obligation);
+            }
+        }
+    }
+
+    fn gate_rule(rule: &mut Rule, gate: &Option<Vec<ConstraintUnion>>) {
+        if let Some(gate) = gate {
+            rule.constraint.get_or_insert_with(Vec::new).extend(gate.iter().cloned());
+        }
+    }
+}
+
+pub static GLOBAL_POLICY_STORE: Lazy<Arc<PolicyStore>> = Lazy::new(|| {
+    Arc::new(
+        PolicyStore {
+            policies: DashMap::new(),
+    })
+});
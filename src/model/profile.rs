@@ -0,0 +1,178 @@
+// Copyright 2024 meduo <gao.brian@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use crate::model::action_registry::{ActionDefinition, GLOBAL_ACTION_REGISTRY};
+use crate::model::data_type::DataType;
+use crate::model::error::OdrlError;
+use crate::model::policy::Policy;
+use crate::model::stateworld::StateWorld;
+use crate::reference::types::OperandValue;
+
+//Resolves the value of a profile left operand from the world under evaluation
+pub type LeftOperandResolver = Arc<dyn Fn(&StateWorld) -> Result<OperandValue, anyhow::Error> + Send + Sync>;
+
+//Compares the left and right operand of a profile operator
+pub type OperatorFunction = Arc<dyn Fn(&DataType, &OperandValue, &OperandValue) -> Result<bool, anyhow::Error> + Send + Sync>;
+
+//Structural check of a policy declaring the profile, run at load and evaluation time
+pub type PolicyValidator = Arc<dyn Fn(&Policy) -> Result<(), OdrlError> + Send + Sync>;
+
+//Vocabulary an ODRL profile adds on top of the core model, every term is keyed by its iri
+#[derive(Clone)]
+pub struct Profile {
+    pub uid: String,
+    pub actions: HashMap<String, ActionDefinition>,
+    pub left_operands: HashMap<String, LeftOperandResolver>,
+    pub operators: HashMap<String, OperatorFunction>,
+    pub validators: Vec<PolicyValidator>,
+}
+
+impl Profile {
+    pub fn new(iri: &str) -> Self {
+        Profile {
+            uid: iri.to_string(),
+            actions: HashMap::new(),
+            left_operands: HashMap::new(),
+            operators: HashMap::new(),
+            validators: vec![],
+        }
+    }
+
+    pub fn with_action(mut self, iri: &str, definition: ActionDefinition) -> Self {
+        self.actions.insert(iri.to_string(), definition);
+        self
+    }
+
+    pub fn with_left_operand(mut self, iri: &str, resolver: LeftOperandResolver) -> Self {
+        self.left_operands.insert(iri.to_string(), resolver);
+        self
+    }
+
+    pub fn with_operator(mut self, iri: &str, operator: OperatorFunction) -> Self {
+        self.operators.insert(iri.to_string(), operator);
+        self
+    }
+
+    pub fn with_validator(mut self, validator: PolicyValidator) -> Self {
+        self.validators.push(validator);
+        self
+    }
+}
+
+//Profiles known to the engine, with their left operands and operators indexed by iri
+pub struct ProfileRegistry {
+    profiles: DashMap<String, Profile>,
+    left_operands: DashMap<String, LeftOperandResolver>,
+    operators: DashMap<String, OperatorFunction>,
+}
+
+impl ProfileRegistry {
+    pub fn new() -> Self {
+        ProfileRegistry {
+            profiles: DashMap::new(),
+            left_operands: DashMap::new(),
+            operators: DashMap::new(),
+        }
+    }
+
+    //Register a profile, its actions are declared in the action registry, a profile with the same iri is replaced
+    pub fn register(&self, profile: Profile) -> Result<(), OdrlError> {
+        for (iri, definition) in &profile.actions {
+            GLOBAL_ACTION_REGISTRY.register(iri, definition.clone())?;
+        }
+        for (iri, resolver) in &profile.left_operands {
+            self.left_operands.insert(iri.clone(), resolver.clone());
+        }
+        for (iri, operator) in &profile.operators {
+            self.operators.insert(iri.clone(), operator.clone());
+        }
+
+        //terms the replaced profile declared but the new one does not are dropped
+        if let Some(previous) = self.profiles.insert(profile.uid.clone(), profile.clone()) {
+            self.drop_terms(&previous, Some(&profile));
+        }
+        Ok(())
+    }
+
+    fn drop_terms(&self, profile: &Profile, kept: Option<&Profile>) {
+        for action in profile.actions.keys() {
+            if !kept.is_some_and(|kept| kept.actions.contains_key(action)) {
+                GLOBAL_ACTION_REGISTRY.unregister(action);
+            }
+        }
+        for operand in profile.left_operands.keys() {
+            if !kept.is_some_and(|kept| kept.left_operands.contains_key(operand)) {
+                self.left_operands.remove(operand);
+            }
+        }
+        for operator in profile.operators.keys() {
+            if !kept.is_some_and(|kept| kept.operators.contains_key(operator)) {
+                self.operators.remove(operator);
+            }
+        }
+    }
+
+    pub fn unregister(&self, iri: &str) {
+        if let Some((_, profile)) = self.profiles.remove(iri) {
+            self.drop_terms(&profile, None);
+        }
+    }
+
+    pub fn is_registered(&self, iri: &str) -> bool {
+        self.profiles.contains_key(iri)
+    }
+
+    pub fn get_left_operand(&self, iri: &str) -> Option<LeftOperandResolver> {
+        self.left_operands.get(iri).map(|resolver| resolver.value().clone())
+    }
+
+    pub fn get_operator(&self, iri: &str) -> Option<OperatorFunction> {
+        self.operators.get(iri).map(|operator| operator.value().clone())
+    }
+
+    //Every profile declared by the policy must be registered and accept the policy
+    pub fn validate(&self, policy: &Policy) -> Result<(), OdrlError> {
+        let Some(profiles) = policy.get_profile() else {
+            return Ok(());
+        };
+
+        for iri in profiles {
+            let validators = match self.profiles.get(iri.as_str()) {
+                Some(profile) => profile.validators.clone(),
+                None => return Err(OdrlError::UnknownProfile(iri.as_str().to_string())),
+            };
+            for validator in validators {
+                validator(policy)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for ProfileRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub static GLOBAL_PROFILE_REGISTRY: Lazy<Arc<ProfileRegistry>> = Lazy::new(|| {
+    Arc::new(ProfileRegistry::new())
+});
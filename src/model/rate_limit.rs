@@ -0,0 +1,331 @@
+// Copyright 2024 meduo <gao.brian@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+use std::fmt;
+use anyhow::anyhow;
+use chrono::{DateTime, Duration};
+use crate::model::policy::attribute_key;
+use crate::model::stateworld::StateWorld;
+use crate::reference::temporal::XsdDuration;
+
+#[derive(Debug,Default,Clone,PartialEq)]
+pub enum RateLimitAlgorithm {
+    //every hit of the last window is kept, the window slides with the clock
+    #[default]
+    SlidingLog,
+    //hits are counted in windows aligned to the epoch
+    FixedWindow,
+    //a bucket of limit tokens refilled evenly over the window
+    TokenBucket,
+}
+
+impl TryFrom<&str> for RateLimitAlgorithm {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().replace(['-', '_'], "").as_str() {
+            "sliding" | "slidinglog" | "slidingwindow" => Ok(RateLimitAlgorithm::SlidingLog),
+            "fixed" | "fixedwindow" => Ok(RateLimitAlgorithm::FixedWindow),
+            "token" | "tokenbucket" => Ok(RateLimitAlgorithm::TokenBucket),
+            _ => Err(anyhow!("unknown rate limit algorithm: {}", value)),
+        }
+    }
+}
+
+impl fmt::Display for RateLimitAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RateLimitAlgorithm::SlidingLog => write!(f, "slidingLog"),
+            RateLimitAlgorithm::FixedWindow => write!(f, "fixedWindow"),
+            RateLimitAlgorithm::TokenBucket => write!(f, "tokenBucket"),
+        }
+    }
+}
+
+//Part of the request a limit is kept apart for
+#[derive(Debug,Clone,PartialEq)]
+pub enum RateLimitScope {
+    Assignee,
+    Action,
+    Target,
+    //a request attribute, keyed by left operand iri
+    Attribute(String),
+}
+
+impl From<&str> for RateLimitScope {
+    fn from(value: &str) -> Self {
+        match value {
+            "assignee" => RateLimitScope::Assignee,
+            "action" => RateLimitScope::Action,
+            "target" => RateLimitScope::Target,
+            //left operands may be given by name
+            attribute => RateLimitScope::Attribute(attribute_key(attribute)),
+        }
+    }
+}
+
+impl fmt::Display for RateLimitScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RateLimitScope::Assignee => write!(f, "assignee"),
+            RateLimitScope::Action => write!(f, "action"),
+            RateLimitScope::Target => write!(f, "target"),
+            RateLimitScope::Attribute(iri) => write!(f, "{}", iri),
+        }
+    }
+}
+
+//Counters of one rate limit for one scope, kept in the world
+#[derive(Debug,Clone,PartialEq)]
+pub enum RateLimitState {
+    SlidingLog(VecDeque<i64>),
+    FixedWindow { start: i64, count: u64 },
+    TokenBucket { tokens: f64, updated: i64 },
+}
+
+//A timeWindow limit such as 10/PT1H;algorithm=tokenBucket;scope=assignee,target
+#[derive(Debug,Clone,PartialEq)]
+pub struct RateLimit {
+    pub limit: u64,
+    //window length, calendar months and years are measured at the instant the window ends
+    pub period: XsdDuration,
+    pub algorithm: RateLimitAlgorithm,
+    //parts of the request which get their own counters, none is one counter for every request
+    pub scope: Vec<RateLimitScope>,
+}
+
+impl RateLimit {
+    pub fn new(limit: u64, window: i64) -> Self {
+        RateLimit {
+            limit,
+            period: XsdDuration::from_millis(window),
+            algorithm: RateLimitAlgorithm::default(),
+            scope: vec![RateLimitScope::Assignee],
+        }
+    }
+
+    pub fn with_algorithm(mut self, algorithm: RateLimitAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    pub fn with_scope(mut self, scope: Vec<RateLimitScope>) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    //count/duration, then optional algorithm= and scope= parameters separated by ';'
+    pub fn parse(value: &str) -> Result<Self, anyhow::Error> {
+        let mut parts = value.split(';').map(str::trim);
+        let window = parts.next().unwrap_or_default();
+        let (limit, duration) = window.split_once('/').ok_or_else(|| anyhow!("invalid time window: {}", value))?;
+        let limit = limit.trim().parse::<u64>().map_err(|_| anyhow!("invalid time window count: {}", value))?;
+        let period = XsdDuration::parse(duration.trim())
+            .map_err(|e| anyhow!("invalid time window duration: {}, {}", value, e))?;
+        if period.months < 0 || period.duration < Duration::zero() || (period.months == 0 && period.duration.is_zero()) {
+            return Err(anyhow!("invalid time window duration: {}", value));
+        }
+
+        let mut rate_limit = RateLimit::new(limit, 0);
+        rate_limit.period = period;
+        for part in parts.filter(|part| !part.is_empty()) {
+            match part.split_once('=') {
+                Some(("algorithm", algorithm)) => {
+                    rate_limit.algorithm = RateLimitAlgorithm::try_from(algorithm.trim())?;
+                }
+                Some(("scope", scope)) => {
+                    rate_limit.scope = scope.split(',').map(str::trim).filter(|s| !s.is_empty() && *s != "global").map(RateLimitScope::from).collect();
+                }
+                _ => return Err(anyhow!("invalid time window parameter: {}", part)),
+            }
+        }
+        Ok(rate_limit)
+    }
+
+    //Key of the counters for the request under evaluation, the scope values follow the limit
+    pub fn key(&self, id: &str, world: &StateWorld) -> String {
+        let usage = world.request_usage.clone().unwrap_or_default();
+        let values = self.scope.iter().map(|scope| match scope {
+            RateLimitScope::Assignee => usage.assignee.clone(),
+            RateLimitScope::Action => usage.action.clone(),
+            RateLimitScope::Target => usage.target.clone(),
+            RateLimitScope::Attribute(iri) => world.get_request_attribute(iri)
+                .and_then(|attribute| attribute.sval.clone())
+                .unwrap_or_default(),
+        }).collect::<Vec<String>>();
+        format!("{}#{}#{}", id, self, values.join("|"))
+    }
+
+    //Hits still allowed at the instant
+    pub fn remaining(&self, state: Option<&RateLimitState>, now: i64) -> u64 {
+        match (&self.algorithm, state) {
+            (_, None) => self.limit,
+            (RateLimitAlgorithm::SlidingLog, Some(RateLimitState::SlidingLog(hits))) => {
+                let window = self.window(now);
+                let live = hits.iter().filter(|hit| now - **hit < window).count() as u64;
+                self.limit.saturating_sub(live)
+            }
+            (RateLimitAlgorithm::FixedWindow, Some(RateLimitState::FixedWindow { start, count })) => {
+                if *start == self.window_start(now) { self.limit.saturating_sub(*count) } else { self.limit }
+            }
+            (RateLimitAlgorithm::TokenBucket, Some(RateLimitState::TokenBucket { tokens, updated })) => {
+                self.refill(*tokens, *updated, now).floor() as u64
+            }
+            //the algorithm changed, the old counters do not apply
+            _ => self.limit,
+        }
+    }
+
+    //Count one hit at the instant
+    pub fn hit(&self, state: Option<RateLimitState>, now: i64) -> RateLimitState {
+        match (&self.algorithm, state) {
+            (RateLimitAlgorithm::SlidingLog, Some(RateLimitState::SlidingLog(mut hits))) => {
+                let window = self.window(now);
+                while hits.front().is_some_and(|hit| now - *hit >= window) {
+                    hits.pop_front();
+                }
+                hits.push_back(now);
+                RateLimitState::SlidingLog(hits)
+            }
+            (RateLimitAlgorithm::SlidingLog, _) => RateLimitState::SlidingLog(VecDeque::from([now])),
+            (RateLimitAlgorithm::FixedWindow, state) => {
+                let start = self.window_start(now);
+                let count = match state {
+                    Some(RateLimitState::FixedWindow { start: current, count }) if current == start => count,
+                    _ => 0,
+                };
+                RateLimitState::FixedWindow { start, count: count + 1 }
+            }
+            (RateLimitAlgorithm::TokenBucket, state) => {
+                let tokens = match state {
+                    Some(RateLimitState::TokenBucket { tokens, updated }) => self.refill(tokens, updated, now),
+                    _ => self.limit as f64,
+                };
+                RateLimitState::TokenBucket { tokens: (tokens - 1.0).max(0.0), updated: now }
+            }
+        }
+    }
+
+    //Window length in milliseconds for the window ending at the instant
+    pub fn window(&self, now: i64) -> i64 {
+        DateTime::from_timestamp_millis(now)
+            .and_then(|instant| self.period.before(instant.fixed_offset()))
+            .map(|window| window.num_milliseconds())
+            .unwrap_or_default()
+            .max(1)
+    }
+
+    fn window_start(&self, now: i64) -> i64 {
+        now - now.rem_euclid(self.window(now))
+    }
+
+    fn refill(&self, tokens: f64, updated: i64, now: i64) -> f64 {
+        let elapsed = (now - updated).max(0) as f64;
+        (tokens + elapsed * self.limit as f64 / self.window(now) as f64).min(self.limit as f64)
+    }
+}
+
+impl fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scope = self.scope.iter().map(|scope| scope.to_string()).collect::<Vec<String>>();
+        write!(f, "{}/{};algorithm={};scope={}", self.limit, self.period, self.algorithm, scope.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::model::stateworld::UsageKey;
+    use crate::reference::types::OperandValue;
+    use super::*;
+
+    fn run(rate_limit: &RateLimit, times: &[i64]) -> Vec<bool> {
+        let mut state = None;
+        times.iter().map(|now| {
+            let allowed = rate_limit.remaining(state.as_ref(), *now) > 0;
+            if allowed {
+                state = Some(rate_limit.hit(state.take(), *now));
+            }
+            allowed
+        }).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let rate_limit = RateLimit::parse("10/PT1H").unwrap();
+        assert_eq!(rate_limit, RateLimit::new(10, 3_600_000));
+
+        let rate_limit = RateLimit::parse("3/PT10S; algorithm=tokenBucket; scope=assignee,target").unwrap();
+        assert_eq!(rate_limit.algorithm, RateLimitAlgorithm::TokenBucket);
+        assert_eq!(rate_limit.scope, vec![RateLimitScope::Assignee, RateLimitScope::Target]);
+        assert!(RateLimit::parse("3/PT10S;scope=global").unwrap().scope.is_empty());
+
+        //a month long window is measured at the instant it ends
+        let monthly = RateLimit::parse("100/P1M").unwrap();
+        let march = DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z").unwrap().timestamp_millis();
+        let april = DateTime::parse_from_rfc3339("2024-04-01T00:00:00Z").unwrap().timestamp_millis();
+        assert_eq!(monthly.window(march), 29 * 86_400_000);
+        assert_eq!(monthly.window(april), 31 * 86_400_000);
+        //counters are keyed by the period, they do not move with the length of the month
+        assert!(monthly.to_string().starts_with("100/P1M;"));
+
+        assert!(RateLimit::parse("3").is_err());
+        assert!(RateLimit::parse("x/PT10S").is_err());
+        assert!(RateLimit::parse("3/PT10S;algorithm=leaky").is_err());
+        assert!(RateLimit::parse("3/1H").is_err());
+        assert!(RateLimit::parse("3/-PT1H").is_err());
+    }
+
+    #[test]
+    fn test_key() {
+        let mut world = StateWorld::default();
+        world.enter_usage(Some(UsageKey::new("https://aa/cc", "http://www.w3.org/ns/odrl/2/play", "https://aa/t1")));
+        let mut attributes = HashMap::new();
+        let mut purpose = OperandValue::default();
+        purpose.set_sval(Some("research".to_string()));
+        attributes.insert(attribute_key("purpose"), purpose);
+        world.enter_request(attributes);
+
+        //assignees get their own counters unless the scope says otherwise
+        let rate_limit = RateLimit::parse("2/PT10S").unwrap();
+        let key = rate_limit.key("c1", &world);
+        assert!(key.ends_with("#https://aa/cc"));
+        world.enter_usage(Some(UsageKey::new("https://aa/dd", "http://www.w3.org/ns/odrl/2/play", "https://aa/t1")));
+        assert_ne!(rate_limit.key("c1", &world), key);
+
+        let rate_limit = RateLimit::parse("2/PT10S;scope=target,purpose").unwrap();
+        assert!(rate_limit.key("c1", &world).ends_with("#https://aa/t1|research"));
+        let global = RateLimit::parse("2/PT10S;scope=global").unwrap();
+        assert!(global.key("c1", &world).ends_with("#"));
+    }
+
+    #[test]
+    fn test_algorithms() {
+        //the sliding log frees a hit exactly one window after it
+        let sliding = RateLimit::new(2, 10_000);
+        assert_eq!(run(&sliding, &[0, 1_000, 2_000, 10_000, 10_500, 11_000]), vec![true, true, false, true, false, true]);
+
+        //the fixed window resets at every multiple of the window
+        let fixed = RateLimit::new(2, 10_000).with_algorithm(RateLimitAlgorithm::FixedWindow);
+        assert_eq!(run(&fixed, &[8_000, 9_000, 9_500, 10_000, 10_500, 11_000]), vec![true, true, false, true, true, false]);
+
+        //the token bucket refills one token every 5 seconds
+        let bucket = RateLimit::new(2, 10_000).with_algorithm(RateLimitAlgorithm::TokenBucket);
+        assert_eq!(run(&bucket, &[0, 0, 0, 4_000, 5_000, 6_000, 20_000, 20_000, 20_000]),
+                   vec![true, true, false, false, true, false, true, true, false]);
+    }
+}
@@ -0,0 +1,631 @@
+// Copyright 2024 meduo <gao.brian@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(dead_code)]
+
+use std::sync::Arc;
+use anyhow::anyhow;
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday};
+use crate::model::constraint_left_operand::ConstraintLeftOperand;
+use crate::model::constraint_operator::ConstraintOperator;
+use crate::model::error::OdrlError;
+use crate::model::profile::Profile;
+use crate::model::stateworld::StateWorld;
+use crate::reference::temporal::XsdDuration;
+use crate::reference::timezone::Zone;
+use crate::reference::types::{OperandValue, OperandValueType, TypedValue};
+
+//Profile of recurring time windows, its left operand is the world clock
+pub const SCHEDULE_PROFILE: &str = "https://datasafe.io/ds/1.1/schedule";
+pub const RECURRENCE: &str = "https://datasafe.io/ds/1.1/schedule/recurrence";
+
+//The recurring windows a right operand describes, as an iCalendar recurrence or a cron expression
+#[derive(Debug, Clone)]
+pub enum Schedule {
+    Recurrence(Recurrence),
+    Cron(CronSchedule),
+}
+
+impl Schedule {
+    pub fn parse(value: &str) -> Result<Self, anyhow::Error> {
+        let value = value.trim();
+        if value.starts_with("DTSTART") || value.starts_with("RRULE") || value.starts_with("FREQ") {
+            return Ok(Schedule::Recurrence(Recurrence::parse(value)?));
+        }
+        Ok(Schedule::Cron(CronSchedule::parse(value)?))
+    }
+
+    pub fn contains(&self, instant: &DateTime<FixedOffset>) -> bool {
+        match self {
+            Schedule::Recurrence(recurrence) => recurrence.contains(instant),
+            Schedule::Cron(cron) => cron.contains(instant),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+//An RRULE with its DTSTART and DURATION (RFC 5545), each occurrence opens a window of the duration;
+//times are wall clock times in the zone of DTSTART, UTC when it has none
+#[derive(Debug, Clone)]
+pub struct Recurrence {
+    pub zone: Zone,
+    pub start: NaiveDateTime,
+    pub duration: Duration,
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub until: Option<NaiveDateTime>,
+    pub count: Option<u32>,
+    pub by_month: Vec<u32>,
+    pub by_month_day: Vec<i32>,
+    pub by_year_day: Vec<i32>,
+    pub by_day: Vec<(Option<i32>, Weekday)>,
+    pub by_hour: Vec<u32>,
+    pub by_minute: Vec<u32>,
+    pub by_second: Vec<u32>,
+    pub week_start: Weekday,
+}
+
+impl Recurrence {
+    //Content lines separated by newlines or spaces:
+    //DTSTART;TZID=Europe/Berlin:20250106T090000 RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR DURATION:PT8H
+    pub fn parse(value: &str) -> Result<Self, anyhow::Error> {
+        let mut zone = Zone::utc();
+        let mut start = None;
+        let mut timed = false;
+        let mut rule = None;
+        let mut duration = None;
+        let mut end = None;
+        for line in value.split_whitespace() {
+            let (name, content) = line.split_once(':').unwrap_or(("RRULE", line));
+            let mut params = name.split(';');
+            match params.next().unwrap_or_default().to_uppercase().as_str() {
+                "DTSTART" => {
+                    for param in params {
+                        if let Some(tzid) = param.strip_prefix("TZID=") {
+                            zone = Zone::parse(tzid)?;
+                        }
+                    }
+                    if content.ends_with('Z') {
+                        zone = Zone::utc();
+                    }
+                    let (datetime, has_time) = parse_ical_datetime(content)?;
+                    start = Some(datetime);
+                    timed = has_time;
+                }
+                "DTEND" => end = Some(parse_ical_datetime(content)?.0),
+                "DURATION" => duration = Some(XsdDuration::parse(content)?),
+                "RRULE" => rule = Some(content),
+                other => return Err(anyhow!("unsupported recurrence property: {}", other)),
+            }
+        }
+
+        let start = start.unwrap_or_default();
+        //all day occurrences unless the window is given, a timed start without it is an instant
+        let duration = match (duration, end) {
+            (Some(duration), _) => duration.add_to(start.and_utc()).map(|until| until.naive_utc() - start)
+                .ok_or_else(|| anyhow!("invalid recurrence duration"))?,
+            (None, Some(end)) => end - start,
+            (None, None) if !timed => Duration::days(1),
+            (None, None) => return Err(anyhow!("a recurrence with a start time needs a DURATION or DTEND")),
+        };
+        if duration <= Duration::zero() {
+            return Err(anyhow!("recurrence window must not be empty"));
+        }
+
+        let rule = rule.ok_or_else(|| anyhow!("recurrence without RRULE"))?;
+        let mut recurrence = Recurrence {
+            zone,
+            start,
+            duration,
+            frequency: Frequency::Daily,
+            interval: 1,
+            until: None,
+            count: None,
+            by_month: vec![],
+            by_month_day: vec![],
+            by_year_day: vec![],
+            by_day: vec![],
+            by_hour: vec![],
+            by_minute: vec![],
+            by_second: vec![],
+            week_start: Weekday::Mon,
+        };
+        let mut frequency = None;
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or_else(|| anyhow!("invalid recurrence rule part: {}", part))?;
+            match key.to_uppercase().as_str() {
+                "FREQ" => frequency = Some(match value.to_uppercase().as_str() {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "YEARLY" => Frequency::Yearly,
+                    other => return Err(anyhow!("unsupported recurrence frequency: {}", other)),
+                }),
+                "INTERVAL" => recurrence.interval = value.parse()?,
+                "COUNT" => recurrence.count = Some(value.parse()?),
+                "UNTIL" => {
+                    let (until, _) = parse_ical_datetime(value)?;
+                    //an UTC until is seen in the zone of the start
+                    recurrence.until = Some(match value.ends_with('Z') {
+                        true => recurrence.zone.local(&until.and_utc().fixed_offset()),
+                        false => until,
+                    });
+                }
+                "BYMONTH" => recurrence.by_month = numbers(value, 1, 12)?,
+                "BYMONTHDAY" => recurrence.by_month_day = signed_numbers(value, 31)?,
+                "BYYEARDAY" => recurrence.by_year_day = signed_numbers(value, 366)?,
+                "BYDAY" => recurrence.by_day = value.split(',').map(parse_by_day).collect::<Result<_, _>>()?,
+                "BYHOUR" => recurrence.by_hour = numbers(value, 0, 23)?,
+                "BYMINUTE" => recurrence.by_minute = numbers(value, 0, 59)?,
+                "BYSECOND" => recurrence.by_second = numbers(value, 0, 59)?,
+                "WKST" => recurrence.week_start = parse_weekday(value)?,
+                other => return Err(anyhow!("unsupported recurrence rule part: {}", other)),
+            }
+        }
+        recurrence.frequency = frequency.ok_or_else(|| anyhow!("recurrence rule without FREQ"))?;
+        if recurrence.interval == 0 {
+            return Err(anyhow!("recurrence interval must be positive"));
+        }
+        //an ordinal weekday only has a meaning within a month or a year
+        if recurrence.by_day.iter().any(|(ordinal, _)| ordinal.is_some())
+            && matches!(recurrence.frequency, Frequency::Daily | Frequency::Weekly) {
+            return Err(anyhow!("ordinal BYDAY needs a MONTHLY or YEARLY frequency"));
+        }
+        Ok(recurrence)
+    }
+
+    pub fn contains(&self, instant: &DateTime<FixedOffset>) -> bool {
+        let now = self.zone.local(instant);
+        //occurrences starting on one of the days the window reaches back to
+        let mut day = (now - self.duration).date();
+        while day <= now.date() {
+            if self.matches_date(&day) {
+                for time in self.times() {
+                    let occurrence = day.and_time(time);
+                    if occurrence <= now && now < occurrence + self.duration && self.is_occurrence(&occurrence) {
+                        return true;
+                    }
+                }
+            }
+            day = match day.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        false
+    }
+
+    fn is_occurrence(&self, occurrence: &NaiveDateTime) -> bool {
+        if *occurrence < self.start || self.until.is_some_and(|until| *occurrence > until) {
+            return false;
+        }
+        match self.count {
+            Some(count) => self.occurrences_before(occurrence) < count as usize,
+            None => true,
+        }
+    }
+
+    //Occurrences from the start up to the given one, for COUNT
+    fn occurrences_before(&self, occurrence: &NaiveDateTime) -> usize {
+        let mut count = 0;
+        let mut day = self.start.date();
+        while day <= occurrence.date() {
+            if self.matches_date(&day) {
+                count += self.times().iter()
+                    .map(|time| day.and_time(*time))
+                    .filter(|candidate| *candidate >= self.start && candidate < occurrence)
+                    .count();
+            }
+            day = match day.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        count
+    }
+
+    //Times of day of the occurrences, from BYHOUR, BYMINUTE and BYSECOND or else the start
+    fn times(&self) -> Vec<NaiveTime> {
+        let or_start = |values: &Vec<u32>, start: u32| if values.is_empty() { vec![start] } else { values.clone() };
+        let mut times = vec![];
+        for hour in or_start(&self.by_hour, self.start.hour()) {
+            for minute in or_start(&self.by_minute, self.start.minute()) {
+                for second in or_start(&self.by_second, self.start.second()) {
+                    if let Some(time) = NaiveTime::from_hms_opt(hour, minute, second) {
+                        times.push(time);
+                    }
+                }
+            }
+        }
+        times
+    }
+
+    fn matches_date(&self, day: &NaiveDate) -> bool {
+        let start = self.start.date();
+        if *day < start {
+            return false;
+        }
+        let interval = self.interval as i64;
+        let period = match self.frequency {
+            Frequency::Daily => (*day - start).num_days(),
+            Frequency::Weekly => (week_of(day, self.week_start) - week_of(&start, self.week_start)).num_weeks(),
+            Frequency::Monthly => (day.year() as i64 * 12 + day.month0() as i64) - (start.year() as i64 * 12 + start.month0() as i64),
+            Frequency::Yearly => (day.year() - start.year()) as i64,
+        };
+        if period % interval != 0 {
+            return false;
+        }
+
+        if !self.by_month.is_empty() && !self.by_month.contains(&day.month()) {
+            return false;
+        }
+        if !self.by_month_day.is_empty() && !self.by_month_day.iter().any(|nth| nth_matches(*nth, day.day(), days_in_month(day))) {
+            return false;
+        }
+        if !self.by_year_day.is_empty() && !self.by_year_day.iter().any(|nth| nth_matches(*nth, day.ordinal(), days_in_year(day))) {
+            return false;
+        }
+        if !self.by_day.is_empty() && !self.by_day.iter().any(|by_day| self.matches_weekday(by_day, day)) {
+            return false;
+        }
+
+        //without a day rule the occurrences fall on the day of the start
+        let day_rules = !self.by_month_day.is_empty() || !self.by_year_day.is_empty() || !self.by_day.is_empty();
+        match self.frequency {
+            Frequency::Weekly if self.by_day.is_empty() => day.weekday() == start.weekday(),
+            Frequency::Monthly if !day_rules => day.day() == start.day(),
+            Frequency::Yearly if !day_rules && self.by_month.is_empty() => day.month() == start.month() && day.day() == start.day(),
+            Frequency::Yearly if !day_rules => day.day() == start.day(),
+            _ => true,
+        }
+    }
+
+    //1MO is the first Monday, -1FR the last Friday, of the month or of the year without BYMONTH
+    fn matches_weekday(&self, (ordinal, weekday): &(Option<i32>, Weekday), day: &NaiveDate) -> bool {
+        if day.weekday() != *weekday {
+            return false;
+        }
+        let Some(ordinal) = ordinal else {
+            return true;
+        };
+        let (position, length) = match self.frequency {
+            Frequency::Yearly if self.by_month.is_empty() => (day.ordinal(), days_in_year(day)),
+            _ => (day.day(), days_in_month(day)),
+        };
+        let nth = if *ordinal > 0 { (position as i32 - 1) / 7 + 1 } else { -((length - position) as i32 / 7 + 1) };
+        nth == *ordinal
+    }
+}
+
+//A cron expression of minute, hour, day of month, month and day of week, optionally prefixed by
+//CRON_TZ=<zone>; a minute the expression matches is inside the schedule as a whole
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    pub zone: Zone,
+    pub minutes: Vec<bool>,
+    pub hours: Vec<bool>,
+    pub days: Vec<bool>,
+    pub months: Vec<bool>,
+    pub weekdays: Vec<bool>,
+    //when both days of month and of week are restricted either one matches, as cron does
+    pub any_day: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(value: &str) -> Result<Self, anyhow::Error> {
+        let mut fields = value.split_whitespace().collect::<Vec<&str>>();
+        let mut zone = Zone::utc();
+        if let Some(first) = fields.first() {
+            if let Some(name) = first.strip_prefix("CRON_TZ=").or_else(|| first.strip_prefix("TZ=")) {
+                zone = Zone::parse(name)?;
+                fields.remove(0);
+            }
+        }
+        if fields.len() != 5 {
+            return Err(anyhow!("cron expression needs 5 fields: {}", value));
+        }
+
+        const MONTHS: [&str; 12] = ["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+        const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+        let mut weekdays = cron_field(fields[4], 0, 7, &WEEKDAYS)?;
+        //both 0 and 7 are Sunday
+        weekdays[0] |= weekdays[7];
+        weekdays.truncate(7);
+        Ok(CronSchedule {
+            zone,
+            minutes: cron_field(fields[0], 0, 59, &[])?,
+            hours: cron_field(fields[1], 0, 23, &[])?,
+            days: cron_field(fields[2], 1, 31, &[])?,
+            months: cron_field(fields[3], 1, 12, &MONTHS)?,
+            weekdays,
+            any_day: !is_wildcard(fields[2]) && !is_wildcard(fields[4]),
+        })
+    }
+
+    pub fn contains(&self, instant: &DateTime<FixedOffset>) -> bool {
+        let now = self.zone.local(instant);
+        let day = self.days[now.day() as usize];
+        let weekday = self.weekdays[now.weekday().num_days_from_sunday() as usize];
+        let day_matches = if self.any_day { day || weekday } else { day && weekday };
+        self.minutes[now.minute() as usize] && self.hours[now.hour() as usize] && self.months[now.month() as usize] && day_matches
+    }
+}
+
+fn is_wildcard(field: &str) -> bool {
+    field == "*" || field == "?"
+}
+
+//The values a cron field selects, indexed by value; names count from the lowest value
+fn cron_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<Vec<bool>, anyhow::Error> {
+    let value = |text: &str| -> Result<u32, anyhow::Error> {
+        let value = match names.iter().position(|name| name.eq_ignore_ascii_case(text)) {
+            Some(position) => position as u32 + min,
+            None => text.parse().map_err(|_| anyhow!("invalid cron value: {}", text))?,
+        };
+        if value < min || value > max {
+            return Err(anyhow!("cron value out of range: {}", text));
+        }
+        Ok(value)
+    };
+
+    let mut selected = vec![false; max as usize + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| anyhow!("invalid cron step: {}", step))?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(anyhow!("cron step must be positive"));
+        }
+        let (first, last) = match range {
+            "*" | "?" => (min, max),
+            range => match range.split_once('-') {
+                Some((first, last)) => (value(first)?, value(last)?),
+                //a single value with a step runs to the end of the range
+                None if part.contains('/') => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if first > last {
+            return Err(anyhow!("invalid cron range: {}", range));
+        }
+        for value in (first..=last).step_by(step as usize) {
+            selected[value as usize] = true;
+        }
+    }
+    Ok(selected)
+}
+
+//DATE or DATE-TIME of iCalendar, 20250106 or 20250106T090000 with an optional Z
+fn parse_ical_datetime(value: &str) -> Result<(NaiveDateTime, bool), anyhow::Error> {
+    let value = value.trim_end_matches('Z');
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        return Ok((datetime, true));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y%m%d").map_err(|_| anyhow!("invalid recurrence date: {}", value))?;
+    Ok((date.and_time(NaiveTime::MIN), false))
+}
+
+fn parse_weekday(value: &str) -> Result<Weekday, anyhow::Error> {
+    match value.to_uppercase().as_str() {
+        "MO" => Ok(Weekday::Mon),
+        "TU" => Ok(Weekday::Tue),
+        "WE" => Ok(Weekday::Wed),
+        "TH" => Ok(Weekday::Thu),
+        "FR" => Ok(Weekday::Fri),
+        "SA" => Ok(Weekday::Sat),
+        "SU" => Ok(Weekday::Sun),
+        _ => Err(anyhow!("invalid weekday: {}", value)),
+    }
+}
+
+//A weekday with an optional ordinal, as MO, 1MO or -1FR
+fn parse_by_day(value: &str) -> Result<(Option<i32>, Weekday), anyhow::Error> {
+    let split = value.len().checked_sub(2).filter(|split| value.is_char_boundary(*split))
+        .ok_or_else(|| anyhow!("invalid weekday: {}", value))?;
+    let (ordinal, weekday) = value.split_at(split);
+    let ordinal = match ordinal {
+        "" => None,
+        ordinal => {
+            let ordinal = ordinal.parse::<i32>().map_err(|_| anyhow!("invalid weekday ordinal: {}", value))?;
+            if ordinal == 0 || ordinal.abs() > 53 {
+                return Err(anyhow!("invalid weekday ordinal: {}", value));
+            }
+            Some(ordinal)
+        }
+    };
+    Ok((ordinal, parse_weekday(weekday)?))
+}
+
+fn numbers(value: &str, min: u32, max: u32) -> Result<Vec<u32>, anyhow::Error> {
+    value.split(',').map(|number| {
+        let number = number.parse::<u32>().map_err(|_| anyhow!("invalid number: {}", number))?;
+        if number < min || number > max {
+            return Err(anyhow!("number out of range: {}", number));
+        }
+        Ok(number)
+    }).collect()
+}
+
+//Non zero numbers up to max, negative ones count from the end
+fn signed_numbers(value: &str, max: i32) -> Result<Vec<i32>, anyhow::Error> {
+    value.split(',').map(|number| {
+        let number = number.parse::<i32>().map_err(|_| anyhow!("invalid number: {}", number))?;
+        if number == 0 || number.abs() > max {
+            return Err(anyhow!("number out of range: {}", number));
+        }
+        Ok(number)
+    }).collect()
+}
+
+fn nth_matches(nth: i32, position: u32, length: u32) -> bool {
+    if nth > 0 { nth as u32 == position } else { (length as i32 + nth + 1) as u32 == position }
+}
+
+fn days_in_month(day: &NaiveDate) -> u32 {
+    let (year, month) = if day.month() == 12 { (day.year() + 1, 1) } else { (day.year(), day.month() + 1) };
+    NaiveDate::from_ymd_opt(year, month, 1).and_then(|next| next.pred_opt()).map(|last| last.day()).unwrap_or(31)
+}
+
+fn days_in_year(day: &NaiveDate) -> u32 {
+    if NaiveDate::from_ymd_opt(day.year(), 2, 29).is_some() { 366 } else { 365 }
+}
+
+//The first day of the week the day is in
+fn week_of(day: &NaiveDate, week_start: Weekday) -> NaiveDate {
+    let offset = (day.weekday().num_days_from_monday() + 7 - week_start.num_days_from_monday()) % 7;
+    *day - Duration::days(offset as i64)
+}
+
+pub struct ScheduleInferencer;
+
+impl ScheduleInferencer {
+    pub fn applies(left: &ConstraintLeftOperand) -> bool {
+        matches!(left, ConstraintLeftOperand::Custom(iri) if iri.as_str() == RECURRENCE)
+    }
+
+    //The clock is inside the schedule for eq and isPartOf when one of the right schedules contains it,
+    //and outside of all of them for neq
+    pub fn eval(operator: &ConstraintOperator, left: &OperandValue, right: &OperandValue) -> Result<bool, anyhow::Error> {
+        let instant = match (&left.typed, left.get_sval()) {
+            (Some(TypedValue::DateTime(instant)), _) => *instant,
+            (_, Some(millis)) => millis.parse::<i64>().ok().and_then(DateTime::from_timestamp_millis)
+                .map(|instant| instant.fixed_offset())
+                .ok_or_else(|| anyhow!("invalid recurrence instant: {}", millis))?,
+            _ => return Err(anyhow!("recurrence instant is None")),
+        };
+        let schedules = match (right.get_set(), right.get_sval()) {
+            (Some(set), _) => set,
+            (None, Some(value)) => vec![value],
+            (None, None) => return Err(anyhow!("schedule is None")),
+        };
+
+        let mut within = false;
+        for schedule in schedules {
+            //a schedule that does not parse is a fault of the policy, not a verdict
+            let schedule = Schedule::parse(&schedule)
+                .map_err(|e| OdrlError::InvalidSchedule(schedule.clone(), e.to_string()))?;
+            within |= schedule.contains(&instant);
+        }
+        match operator {
+            ConstraintOperator::eq | ConstraintOperator::isPartOf | ConstraintOperator::isA => Ok(within),
+            ConstraintOperator::neq => Ok(!within),
+            _ => Err(anyhow!("operator not supported for schedules")),
+        }
+    }
+}
+
+//The schedule profile, its recurrence operand reads the clock of the world
+pub fn schedule_profile() -> Profile {
+    Profile::new(SCHEDULE_PROFILE)
+        .with_left_operand(RECURRENCE, Arc::new(|world: &StateWorld| {
+            let now = world.now();
+            let instant = DateTime::from_timestamp_millis(now).ok_or_else(|| anyhow!("invalid world clock: {}", now))?;
+            let mut value = OperandValue::default();
+            value.set_ty(OperandValueType::string);
+            value.set_sval(Some(now.to_string()));
+            value.set_typed(Some(TypedValue::DateTime(instant.fixed_offset())));
+            Ok(value)
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+    use crate::model::schedule::{CronSchedule, Recurrence, Schedule};
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<FixedOffset> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap().fixed_offset()
+    }
+
+    #[test]
+    fn test_recurrence() {
+        //business hours in UTC, 2025-03-03 is a Monday
+        let hours = Recurrence::parse("DTSTART:20250101T090000Z RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR DURATION:PT8H").unwrap();
+        assert!(hours.contains(&at(2025, 3, 3, 9, 0)));
+        assert!(hours.contains(&at(2025, 3, 7, 16, 59)));
+        assert!(!hours.contains(&at(2025, 3, 7, 17, 0)));
+        assert!(!hours.contains(&at(2025, 3, 8, 10, 0)));
+        assert!(!hours.contains(&at(2024, 12, 30, 10, 0)));
+
+        //the first Monday of each month, all day
+        let first_monday = Recurrence::parse("FREQ=MONTHLY;BYDAY=1MO").unwrap();
+        assert!(first_monday.contains(&at(2025, 3, 3, 23, 0)));
+        assert!(!first_monday.contains(&at(2025, 3, 10, 12, 0)));
+        assert!(first_monday.contains(&at(2025, 4, 7, 0, 0)));
+
+        //the last day of the month, for three months
+        let closing = Recurrence::parse("DTSTART:20250101 RRULE:FREQ=MONTHLY;BYMONTHDAY=-1;COUNT=3").unwrap();
+        assert!(closing.contains(&at(2025, 1, 31, 12, 0)));
+        assert!(closing.contains(&at(2025, 2, 28, 12, 0)));
+        assert!(!closing.contains(&at(2025, 2, 27, 12, 0)));
+        assert!(closing.contains(&at(2025, 3, 31, 12, 0)));
+        assert!(!closing.contains(&at(2025, 4, 30, 12, 0)));
+
+        //day rules narrow each other, Friday the 13th
+        let friday = Recurrence::parse("FREQ=MONTHLY;BYDAY=FR;BYMONTHDAY=13").unwrap();
+        assert!(friday.contains(&at(2025, 6, 13, 12, 0)));
+        assert!(!friday.contains(&at(2025, 5, 13, 12, 0)));
+
+        //a window running over midnight
+        let night = Recurrence::parse("DTSTART:20250101T220000 RRULE:FREQ=DAILY;INTERVAL=2 DURATION:PT4H").unwrap();
+        assert!(night.contains(&at(2025, 1, 2, 1, 0)));
+        assert!(!night.contains(&at(2025, 1, 3, 1, 0)));
+
+        for invalid in ["FREQ=HOURLY", "DTSTART:20250101T090000 RRULE:FREQ=DAILY", "FREQ=WEEKLY;BYDAY=1MO", "FREQ=DAILY;BYSETPOS=1", "RRULE:BYDAY=MO"] {
+            assert!(Recurrence::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_cron() {
+        let hours = CronSchedule::parse("* 9-16 * * MON-FRI").unwrap();
+        assert!(hours.contains(&at(2025, 3, 3, 9, 0)));
+        assert!(hours.contains(&at(2025, 3, 3, 16, 59)));
+        assert!(!hours.contains(&at(2025, 3, 3, 17, 0)));
+        assert!(!hours.contains(&at(2025, 3, 9, 10, 0)));
+
+        //either the first of the month or a Sunday
+        let days = CronSchedule::parse("*/15 * 1 * 0").unwrap();
+        assert!(days.contains(&at(2025, 3, 1, 10, 30)));
+        assert!(days.contains(&at(2025, 3, 9, 10, 45)));
+        assert!(!days.contains(&at(2025, 3, 9, 10, 50)));
+        assert!(!days.contains(&at(2025, 3, 10, 10, 45)));
+
+        for invalid in ["* * * *", "60 * * * *", "* * * * MON-", "*/0 * * * *", "CRON_TZ=Nowhere/Else * * * * *"] {
+            assert!(CronSchedule::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_schedule_timezone() {
+        let berlin = Schedule::parse("DTSTART;TZID=Europe/Berlin:20250101T090000 RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR DURATION:PT8H").unwrap();
+        //09:00 in Berlin is 08:00 UTC in winter and 07:00 UTC in summer
+        assert!(berlin.contains(&at(2025, 1, 6, 8, 0)));
+        assert!(!berlin.contains(&at(2025, 1, 6, 7, 59)));
+        assert!(berlin.contains(&at(2025, 7, 7, 7, 0)));
+        assert!(!berlin.contains(&at(2025, 7, 7, 15, 0)));
+
+        let cron = Schedule::parse("CRON_TZ=Europe/Berlin * 9-16 * * 1-5").unwrap();
+        assert!(cron.contains(&at(2025, 7, 7, 7, 0)));
+        assert!(!cron.contains(&at(2025, 7, 7, 6, 59)));
+    }
+}
@@ -25,20 +25,91 @@ use iref::IriBuf;
 use lombok::{Builder, Getter, GetterMut, Setter};
 use once_cell::sync::Lazy;
 use crate::model::asset::AssetCollection;
+use crate::model::clock::{Clock, SystemClock};
 use crate::model::constraint_right_operand::ConstraintRightOperand;
+use crate::model::decision::Indeterminate;
 use crate::model::policy::PolicyUnion;
+use crate::model::policy_store::GLOBAL_POLICY_STORE;
+use crate::model::rate_limit::{RateLimit, RateLimitState};
+use crate::reference::types::OperandValue;
 use crate::traits::definions::WorldCallBack;
 
+//Lifecycle of a duty owed by a party
+#[derive(Debug,Clone,PartialEq)]
+pub enum DutyState {
+    //the duty is known but not yet discharged
+    Pending,
+    //the party agreed to discharge the duty later
+    Accepted,
+    Fulfilled,
+    //the duty was not discharged, its consequences become due
+    Violated,
+}
+
+//A prohibited action attempted by a party
+#[derive(Debug,Clone,PartialEq)]
+pub struct Violation {
+    pub party: String,
+    //uid of the prohibition which was violated
+    pub prohibition: Option<String>,
+    pub action: Option<String>,
+    pub target: Option<String>,
+    pub time: i64,
+}
+
+//Usage counter of an action on a target by an assignee, absent parts are empty
+#[derive(Debug,Default,Clone,PartialEq,Eq,Hash)]
+pub struct UsageKey {
+    pub assignee: String,
+    pub action: String,
+    pub target: String,
+}
+
+impl UsageKey {
+    pub fn new(assignee: &str, action: &str, target: &str) -> Self {
+        UsageKey {
+            assignee: assignee.to_string(),
+            action: action.to_string(),
+            target: target.to_string(),
+        }
+    }
+}
+
 #[derive(Default,Builder,Clone)]
 pub struct StateWorld {
     pub uid: Option<IriBuf>,
     pub state: HashMap<String, String>,
+    //attributes of the request under evaluation, empty outside of an evaluation
+    pub request_attributes: HashMap<String, OperandValue>,
+    //usage counter of the request under evaluation, count constraints are evaluated against it
+    pub request_usage: Option<UsageKey>,
+    //permitted requests per assignee, action and target
+    pub usage: HashMap<UsageKey, u64>,
+    //timeWindow counters keyed by constraint and scope values
+    pub rate_limits: HashMap<String, RateLimitState>,
+    //timeWindow hits of the request under evaluation, counted once it is permitted
+    pub rate_limit_hits: Vec<(String, RateLimit)>,
+    //operands of an andSequence satisfied so far, keyed by sequence and assignee
+    pub sequences: HashMap<String, usize>,
+    //andSequence progress of the request under evaluation, kept once it is decided
+    pub sequence_steps: Vec<(String, usize)>,
+    //deny when a constraint can not be evaluated, instead of only failing that constraint
+    pub strict: bool,
+    //constraints found indeterminate since the last check of a rule
+    pub indeterminate: Vec<Indeterminate>,
     pub worldInitialTime: i64,
     pub last_executeTime: i64,
     pub meteredTime: i64,
     pub operand_referred: HashMap<String,ConstraintRightOperand>,
     pub assets: HashMap<String, AssetCollection>,
-    pub global_policies: HashMap<String, PolicyUnion>,
+    //duty states per party, keyed by party uid and then duty key
+    pub duties: HashMap<String, HashMap<String, DutyState>>,
+    //violations per party, oldest first
+    pub violations: HashMap<String, Vec<Violation>>,
+    //remedy duty keys activated per party by violations
+    pub remedies: HashMap<String, Vec<String>>,
+    //time source of the world, system clock when not set
+    pub clock: Option<Arc<dyn Clock>>,
     pub success_callback: Option<Arc<Mutex<Vec<Box<dyn WorldCallBack>>>>>,
     pub failure_callback: Option<Arc<Mutex<Vec<Box<dyn WorldCallBack>>>>>,
 }
@@ -55,10 +126,22 @@ impl StateWorld {
                 println!("{}", e);
             }
         }
-        me.worldInitialTime = chrono::Utc::now().timestamp_millis();
+        me.worldInitialTime = me.now();
         me.meteredTime = 0;
         me
     }
+
+    pub fn new_with_clock(iri: &str, clock: Arc<dyn Clock>) -> Self {
+        let mut me = Self::new(iri);
+        me.set_clock(clock);
+        me
+    }
+
+    //Swap the time source, the world timeline restarts at the clock's instant
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = Some(clock);
+        self.worldInitialTime = self.now();
+    }
     pub fn add_state(&mut self, state: &str, value: &str) {
         self.state.insert(state.to_string(), value.to_string());
     }
@@ -73,14 +156,176 @@ impl StateWorld {
         self.state.insert(state.to_string(), value.to_string());
     }
 
+    //Install the attributes of a request, the previous ones are returned to be restored later
+    pub fn enter_request(&mut self, attributes: HashMap<String, OperandValue>) -> HashMap<String, OperandValue> {
+        std::mem::replace(&mut self.request_attributes, attributes)
+    }
+
+    pub fn leave_request(&mut self, previous: HashMap<String, OperandValue>) {
+        self.request_attributes = previous;
+    }
+
+    pub fn get_request_attribute(&self, iri: &str) -> Option<&OperandValue> {
+        self.request_attributes.get(iri)
+    }
+
+    //Install the usage counter of a request, the previous one is returned to be restored later
+    pub fn enter_usage(&mut self, usage: Option<UsageKey>) -> Option<UsageKey> {
+        std::mem::replace(&mut self.request_usage, usage)
+    }
+
+    pub fn leave_usage(&mut self, previous: Option<UsageKey>) {
+        self.request_usage = previous;
+    }
+
+    //Usage count of the request under evaluation, None outside of an evaluation
+    pub fn request_usage_count(&self) -> Option<u64> {
+        self.request_usage.as_ref().map(|usage| self.usage_count(usage))
+    }
+
+    pub fn usage_count(&self, usage: &UsageKey) -> u64 {
+        self.usage.get(usage).copied().unwrap_or(0)
+    }
+
+    //Count one more use, the new count is returned
+    pub fn record_usage(&mut self, usage: UsageKey) -> u64 {
+        let count = self.usage.entry(usage).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    pub fn reset_usage(&mut self, usage: &UsageKey) {
+        self.usage.remove(usage);
+    }
+
+    //Reset every counter of the assignee
+    pub fn reset_assignee_usage(&mut self, assignee: &str) {
+        self.usage.retain(|usage, _| usage.assignee != assignee);
+    }
+
+    pub fn clear_usage(&mut self) {
+        self.usage.clear();
+    }
+
+    //Hits still allowed by the limit for the key at the current instant
+    pub fn rate_limit_remaining(&self, key: &str, rate_limit: &RateLimit) -> u64 {
+        rate_limit.remaining(self.rate_limits.get(key), self.now())
+    }
+
+    //Hold a hit until the request under evaluation is decided
+    pub fn add_rate_limit_hit(&mut self, key: String, rate_limit: RateLimit) {
+        self.rate_limit_hits.push((key, rate_limit));
+    }
+
+    //Mark to discard the hits of a rule which did not apply
+    pub fn rate_limit_mark(&self) -> usize {
+        self.rate_limit_hits.len()
+    }
+
+    pub fn discard_rate_limit_hits(&mut self, mark: usize) {
+        self.rate_limit_hits.truncate(mark);
+    }
+
+    //Start collecting the hits of a request, the previous ones are returned to be restored later
+    pub fn enter_rate_limits(&mut self) -> Vec<(String, RateLimit)> {
+        std::mem::take(&mut self.rate_limit_hits)
+    }
+
+    //Restore the previous hits, the ones collected for the request are returned
+    pub fn leave_rate_limits(&mut self, previous: Vec<(String, RateLimit)>) -> Vec<(String, RateLimit)> {
+        std::mem::replace(&mut self.rate_limit_hits, previous)
+    }
+
+    pub fn record_rate_limit_hits(&mut self, hits: Vec<(String, RateLimit)>) {
+        let now = self.now();
+        for (key, rate_limit) in hits {
+            let state = self.rate_limits.remove(&key);
+            self.rate_limits.insert(key, rate_limit.hit(state, now));
+        }
+    }
+
+    pub fn clear_rate_limits(&mut self) {
+        self.rate_limits.clear();
+    }
+
+    //Progress of the sequence, including the steps held for the request under evaluation
+    pub fn sequence_progress(&self, key: &str) -> usize {
+        self.sequence_steps.iter().rev()
+            .find(|(step, _)| step == key)
+            .map(|(_, progress)| *progress)
+            .unwrap_or_else(|| self.sequences.get(key).copied().unwrap_or(0))
+    }
+
+    pub fn set_sequence_progress(&mut self, key: &str, progress: usize) {
+        self.sequences.insert(key.to_string(), progress);
+    }
+
+    //Hold a step until the request under evaluation is decided
+    pub fn add_sequence_step(&mut self, key: String, progress: usize) {
+        self.sequence_steps.push((key, progress));
+    }
+
+    //Start collecting the steps of a request, the previous ones are returned to be restored later
+    pub fn enter_sequences(&mut self) -> Vec<(String, usize)> {
+        std::mem::take(&mut self.sequence_steps)
+    }
+
+    //Restore the previous steps, the ones collected for the request are returned
+    pub fn leave_sequences(&mut self, previous: Vec<(String, usize)>) -> Vec<(String, usize)> {
+        std::mem::replace(&mut self.sequence_steps, previous)
+    }
+
+    pub fn record_sequence_steps(&mut self, steps: Vec<(String, usize)>) {
+        for (key, progress) in steps {
+            self.sequences.insert(key, progress);
+        }
+    }
+
+    //Start the sequences of the assignee over
+    pub fn reset_sequences(&mut self, assignee: &str) {
+        let suffix = format!("#{}", assignee);
+        self.sequences.retain(|key, _| !key.ends_with(&suffix));
+    }
+
+    pub fn clear_sequences(&mut self) {
+        self.sequences.clear();
+    }
+
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn add_indeterminate(&mut self, found: Vec<Indeterminate>) {
+        self.indeterminate.extend(found);
+    }
+
+    pub fn take_indeterminate(&mut self) -> Vec<Indeterminate> {
+        std::mem::take(&mut self.indeterminate)
+    }
+
+    //A detached world sharing the timeline, operands resolve from the given attributes only
+    pub fn refinement_world(&self, attributes: &HashMap<String, OperandValue>) -> StateWorld {
+        StateWorld {
+            uid: self.uid.clone(),
+            worldInitialTime: self.worldInitialTime,
+            last_executeTime: self.last_executeTime,
+            meteredTime: self.meteredTime,
+            operand_referred: self.operand_referred.clone(),
+            clock: self.clock.clone(),
+            request_attributes: attributes.clone(),
+            ..Default::default()
+        }
+    }
+
     pub fn eclipse_datetime(&self) -> i64 {
-        let now = chrono::Utc::now().timestamp_millis();
-        now - self.worldInitialTime
+        self.now() - self.worldInitialTime
     }
 
     pub fn now(&self) -> i64 {
-        let now = chrono::Utc::now().timestamp_millis();
-        now
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => SystemClock.now(),
+        }
     }
 
     pub fn last_execute_time(&self) -> i64 {
@@ -120,12 +365,96 @@ impl StateWorld {
         self.assets.insert(iri.to_string(), assets);
     }
 
+    #[deprecated(note = "policies live in GLOBAL_POLICY_STORE, use PolicyStore::get_policy")]
     pub fn get_policy(&self,iri: String) -> Option<PolicyUnion> {
-        self.global_policies.get(&iri).cloned()
+        GLOBAL_POLICY_STORE.get_policy(&iri)
     }
 
+    #[deprecated(note = "policies live in GLOBAL_POLICY_STORE, use PolicyStore::add_policy")]
     pub fn add_policy(&mut self,iri: String,policy: PolicyUnion) {
-        self.global_policies.insert(iri.to_string(), policy);
+        GLOBAL_POLICY_STORE.get_policies().insert(iri, policy);
+    }
+
+    //A snapshot of the store, changes to it are not written back
+    #[deprecated(note = "policies live in GLOBAL_POLICY_STORE")]
+    pub fn global_policies(&self) -> HashMap<String, PolicyUnion> {
+        GLOBAL_POLICY_STORE.get_policies().iter().map(|entry| (entry.key().clone(), entry.value().clone())).collect()
+    }
+
+    pub fn get_duty_state(&self, party: &str, duty: &str) -> Option<DutyState> {
+        self.duties.get(party).and_then(|duties| duties.get(duty)).cloned()
+    }
+
+    pub fn set_duty_state(&mut self, party: &str, duty: &str, state: DutyState) {
+        self.duties.entry(party.to_string()).or_default().insert(duty.to_string(), state);
+    }
+
+    pub fn fulfill_duty(&mut self, party: &str, duty: &str) {
+        self.set_duty_state(party, duty, DutyState::Fulfilled);
+    }
+
+    pub fn accept_duty(&mut self, party: &str, duty: &str) {
+        self.set_duty_state(party, duty, DutyState::Accepted);
+    }
+
+    fn duties_in_state(&self, party: &str, state: DutyState) -> Vec<String> {
+        let mut duties: Vec<String> = match self.duties.get(party) {
+            Some(duties) => duties.iter().filter(|(_, s)| **s == state).map(|(k, _)| k.clone()).collect(),
+            None => vec![],
+        };
+        duties.sort();
+        duties
+    }
+
+    pub fn pending_duties(&self, party: &str) -> Vec<String> {
+        self.duties_in_state(party, DutyState::Pending)
+    }
+
+    pub fn fulfilled_duties(&self, party: &str) -> Vec<String> {
+        self.duties_in_state(party, DutyState::Fulfilled)
+    }
+
+    pub fn violated_duties(&self, party: &str) -> Vec<String> {
+        self.duties_in_state(party, DutyState::Violated)
+    }
+
+    pub fn add_violation(&mut self, violation: Violation) {
+        self.violations.entry(violation.party.clone()).or_default().push(violation);
+    }
+
+    pub fn get_violations(&self, party: &str) -> Vec<Violation> {
+        self.violations.get(party).cloned().unwrap_or_default()
+    }
+
+    //Remedy duties become pending again on every new violation
+    pub fn activate_remedy(&mut self, party: &str, duty: &str) {
+        self.set_duty_state(party, duty, DutyState::Pending);
+        let remedies = self.remedies.entry(party.to_string()).or_default();
+        if !remedies.iter().any(|remedy| remedy == duty) {
+            remedies.push(duty.to_string());
+        }
+    }
+
+    //Remedies of the party which are neither fulfilled nor accepted
+    pub fn outstanding_remedies(&self, party: &str) -> Vec<String> {
+        match self.remedies.get(party) {
+            Some(remedies) => remedies.iter()
+                .filter(|remedy| !matches!(self.get_duty_state(party, remedy), Some(DutyState::Fulfilled) | Some(DutyState::Accepted)))
+                .cloned()
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    //Drop every remedy of the party along with its duty state, violations are kept
+    pub fn clear_remedies(&mut self, party: &str) {
+        if let Some(remedies) = self.remedies.remove(party) {
+            if let Some(duties) = self.duties.get_mut(party) {
+                for remedy in remedies {
+                    duties.remove(&remedy);
+                }
+            }
+        }
     }
 
     pub fn add_callback(&mut self, callback: Box<dyn WorldCallBack>, success: bool) {
@@ -181,7 +510,7 @@ pub struct WorldCache {
 }
 
 impl WorldCache {
-    pub fn find_world(&self, iri: &str) -> Option<RefMut<String, StateWorld>> {
+    pub fn find_world(&self, iri: &str) -> Option<RefMut<'_, String, StateWorld>> {
         self.cache.get_mut(iri)
     }
     pub fn add_world(&self, iri: &str, world: StateWorld) {
@@ -289,4 +618,20 @@ mod tests {
             println!("{:?}", value.unwrap().worldInitialTime);
         }
     }
+
+    #[test]
+    #[allow(deprecated)]
+    pub fn test_world_policies() {
+        use crate::model::policy::{PolicyUnion, Set};
+        use crate::model::policy_store::GLOBAL_POLICY_STORE;
+
+        //the old world api reads and writes the global policy store
+        let mut world = StateWorld::default();
+        world.add_policy(String::from("http://abc.tds/policy/world/1"), PolicyUnion::Set(Set::default()));
+        assert!(GLOBAL_POLICY_STORE.get_policy("http://abc.tds/policy/world/1").is_some());
+        assert!(world.get_policy(String::from("http://abc.tds/policy/world/1")).is_some());
+        assert!(world.global_policies().contains_key("http://abc.tds/policy/world/1"));
+        GLOBAL_POLICY_STORE.remove_policy("http://abc.tds/policy/world/1");
+        assert!(world.get_policy(String::from("http://abc.tds/policy/world/1")).is_none());
+    }
 }
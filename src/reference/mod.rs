@@ -12,4 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-pub mod types;
\ No newline at end of file
+
+pub mod temporal;
+pub mod timezone;
+pub mod types;
+pub mod units;
@@ -0,0 +1,391 @@
+// Copyright 2024 meduo <gao.brian@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(dead_code)]
+
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
+use crate::model::constraint_left_operand::ParseError;
+
+//xsd:duration, months and the exact day and time part are kept apart as a month has no fixed length
+#[derive(Debug, Clone, PartialEq)]
+pub struct XsdDuration {
+    pub months: i64,
+    pub duration: Duration,
+    //instant the duration is measured from, calendar months of either side are added to it
+    pub anchor: Option<DateTime<FixedOffset>>,
+}
+
+impl XsdDuration {
+    pub fn parse(lexical: &str) -> Result<Self, ParseError> {
+        let lexical = lexical.trim();
+        let (negative, rest) = match lexical.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, lexical),
+        };
+        let rest = rest.strip_prefix('P').ok_or(ParseError::InvalidCharacter)?;
+        let (date_part, time_part) = match rest.split_once('T') {
+            Some((date_part, time_part)) => {
+                //a T must be followed by a time component
+                if time_part.is_empty() {
+                    return Err(ParseError::IncompleteComponent);
+                }
+                (date_part, Some(time_part))
+            }
+            None => (rest, None),
+        };
+        if date_part.is_empty() && time_part.is_none() {
+            return Err(ParseError::IncompleteComponent);
+        }
+
+        let mut months: i64 = 0;
+        let mut seconds: i64 = 0;
+        let mut nanos: i64 = 0;
+        for (value, designator) in components(date_part, &['Y', 'M', 'D'])? {
+            let value = value.parse::<i64>()?;
+            match designator {
+                'Y' => months = checked(months, value, 12)?,
+                'M' => months = checked(months, value, 1)?,
+                _ => seconds = checked(seconds, value, 86400)?,
+            }
+        }
+        if let Some(time_part) = time_part {
+            for (value, designator) in components(time_part, &['H', 'M', 'S'])? {
+                match designator {
+                    'H' => seconds = checked(seconds, value.parse::<i64>()?, 3600)?,
+                    'M' => seconds = checked(seconds, value.parse::<i64>()?, 60)?,
+                    //only seconds may have a fraction
+                    _ => {
+                        let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+                        seconds = checked(seconds, whole.parse::<i64>()?, 1)?;
+                        nanos = fraction_nanos(fraction).ok_or(ParseError::InvalidCharacter)?;
+                    }
+                }
+            }
+        }
+
+        let duration = Duration::try_seconds(seconds).ok_or(ParseError::OutOfRange)? + Duration::nanoseconds(nanos);
+        let duration = XsdDuration {
+            months,
+            duration,
+            anchor: None,
+        };
+        Ok(if negative { duration.negate() } else { duration })
+    }
+
+    pub fn from_millis(millis: i64) -> Self {
+        XsdDuration {
+            months: 0,
+            duration: Duration::milliseconds(millis),
+            anchor: None,
+        }
+    }
+
+    //The time passed from start to now, measured from start
+    pub fn between(start: DateTime<FixedOffset>, now: DateTime<FixedOffset>) -> Self {
+        XsdDuration {
+            months: 0,
+            duration: now - start,
+            anchor: Some(start),
+        }
+    }
+
+    //Months first then the exact part, an end of month day is clamped as xsd does
+    pub fn add_to<Tz: TimeZone>(&self, instant: DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let shifted = if self.months >= 0 {
+            instant.checked_add_months(Months::new(u32::try_from(self.months).ok()?))?
+        } else {
+            instant.checked_sub_months(Months::new(u32::try_from(-self.months).ok()?))?
+        };
+        shifted.checked_add_signed(self.duration)
+    }
+
+    pub fn sub_from<Tz: TimeZone>(&self, instant: DateTime<Tz>) -> Option<DateTime<Tz>> {
+        self.negate().add_to(instant)
+    }
+
+    pub fn negate(&self) -> Self {
+        XsdDuration {
+            months: -self.months,
+            duration: -self.duration,
+            anchor: self.anchor,
+        }
+    }
+
+    //The exact length when started at the anchor
+    pub fn after(&self, anchor: DateTime<FixedOffset>) -> Option<Duration> {
+        Some(self.add_to(anchor)? - anchor)
+    }
+
+    //The exact length of the span ending at the anchor
+    pub fn before(&self, anchor: DateTime<FixedOffset>) -> Option<Duration> {
+        Some(anchor - self.sub_from(anchor)?)
+    }
+
+    //Compare at the anchor, or by the xsd partial order over its four reference instants;
+    //None when the order depends on the instant, as P1M and P30D do
+    pub fn compare(&self, other: &XsdDuration) -> Option<Ordering> {
+        let anchors = match self.anchor.or(other.anchor) {
+            Some(anchor) => vec![anchor],
+            None => [(1696, 9, 1), (1697, 2, 1), (1903, 3, 1), (1903, 7, 1)].iter()
+                .map(|(year, month, day)| Utc.with_ymd_and_hms(*year, *month, *day, 0, 0, 0).unwrap().fixed_offset())
+                .collect(),
+        };
+        let mut ordering = None;
+        for anchor in anchors {
+            let current = self.add_to(anchor)?.cmp(&other.add_to(anchor)?);
+            match ordering {
+                None => ordering = Some(current),
+                Some(previous) if previous != current => return None,
+                _ => {}
+            }
+        }
+        ordering
+    }
+}
+
+impl Display for XsdDuration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let negative = self.months < 0 || self.duration < Duration::zero();
+        let months = self.months.abs();
+        let duration = self.duration.abs();
+        if negative {
+            write!(f, "-")?;
+        }
+        write!(f, "P")?;
+        if months >= 12 {
+            write!(f, "{}Y", months / 12)?;
+        }
+        if months % 12 != 0 {
+            write!(f, "{}M", months % 12)?;
+        }
+        let days = duration.num_days();
+        if days != 0 {
+            write!(f, "{}D", days)?;
+        }
+        let rest = duration - Duration::days(days);
+        if rest != Duration::zero() || (months == 0 && days == 0) {
+            let seconds = rest.num_seconds();
+            let nanos = (rest - Duration::seconds(seconds)).num_nanoseconds().unwrap_or(0);
+            write!(f, "T")?;
+            if seconds >= 3600 {
+                write!(f, "{}H", seconds / 3600)?;
+            }
+            if seconds % 3600 >= 60 {
+                write!(f, "{}M", seconds % 3600 / 60)?;
+            }
+            if nanos != 0 {
+                let fraction = format!("{:09}", nanos);
+                write!(f, "{}.{}S", seconds % 60, fraction.trim_end_matches('0'))?;
+            } else if seconds % 60 != 0 || seconds == 0 {
+                write!(f, "{}S", seconds % 60)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+//Split a duration part into its number and designator pairs, designators must keep their order
+fn components<'a>(part: &'a str, designators: &[char]) -> Result<Vec<(&'a str, char)>, ParseError> {
+    let mut result = vec![];
+    let mut start = 0;
+    let mut next = 0;
+    for (at, ch) in part.char_indices() {
+        if ch.is_ascii_digit() || ch == '.' {
+            continue;
+        }
+        let position = designators.iter().position(|designator| *designator == ch).ok_or(ParseError::InvalidCharacter)?;
+        if position < next {
+            return Err(ParseError::MisplacedComponent);
+        }
+        if at == start {
+            return Err(ParseError::IncompleteComponent);
+        }
+        result.push((&part[start..at], ch));
+        next = position + 1;
+        start = at + ch.len_utf8();
+    }
+    if start != part.len() {
+        return Err(ParseError::IncompleteComponent);
+    }
+    Ok(result)
+}
+
+fn checked(total: i64, value: i64, factor: i64) -> Result<i64, ParseError> {
+    value.checked_mul(factor).and_then(|value| total.checked_add(value)).ok_or(ParseError::OutOfRange)
+}
+
+//Nanoseconds of a decimal fraction, digits past nanoseconds are dropped
+fn fraction_nanos(fraction: &str) -> Option<i64> {
+    if !fraction.chars().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    let digits: String = fraction.chars().chain(std::iter::repeat('0')).take(9).collect();
+    digits.parse().ok()
+}
+
+//Split the timezone off a lexical form, Z or ±hh:mm
+fn split_timezone(lexical: &str) -> Result<(&str, Option<FixedOffset>), anyhow::Error> {
+    if !lexical.is_ascii() {
+        return Err(anyhow::anyhow!("Invalid temporal value {}", lexical));
+    }
+    if let Some(rest) = lexical.strip_suffix('Z') {
+        return Ok((rest, FixedOffset::east_opt(0)));
+    }
+    if lexical.len() > 6 {
+        let (rest, zone) = lexical.split_at(lexical.len() - 6);
+        let bytes = zone.as_bytes();
+        if (bytes[0] == b'+' || bytes[0] == b'-') && bytes[3] == b':' {
+            let hours = zone[1..3].parse::<i32>()?;
+            let minutes = zone[4..6].parse::<i32>()?;
+            if minutes > 59 || hours * 60 + minutes > 14 * 60 {
+                return Err(anyhow::anyhow!("Invalid timezone {}", zone));
+            }
+            let sign = if bytes[0] == b'-' { -1 } else { 1 };
+            return Ok((rest, FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))));
+        }
+    }
+    Ok((lexical, None))
+}
+
+fn parse_date_part(lexical: &str) -> Result<NaiveDate, anyhow::Error> {
+    let mut parts = lexical.rsplitn(3, '-');
+    let (day, month, year) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(day), Some(month), Some(year)) => (day, month, year),
+        _ => return Err(anyhow::anyhow!("Invalid date {}", lexical)),
+    };
+    //years have four digits at least, a leading minus is before the common era
+    if year.trim_start_matches('-').len() < 4 {
+        return Err(anyhow::anyhow!("Invalid year {}", year));
+    }
+    NaiveDate::from_ymd_opt(year.parse()?, month.parse()?, day.parse()?)
+        .ok_or_else(|| anyhow::anyhow!("Invalid date {}", lexical))
+}
+
+//The time of day, and whether it is 24:00:00, the end of the day
+fn parse_time_part(lexical: &str) -> Result<(NaiveTime, bool), anyhow::Error> {
+    let parts = lexical.split(':').collect::<Vec<&str>>();
+    if parts.len() != 3 || parts[0].len() != 2 || parts[1].len() != 2 {
+        return Err(anyhow::anyhow!("Invalid time {}", lexical));
+    }
+    let (hour, minute) = (parts[0].parse::<u32>()?, parts[1].parse::<u32>()?);
+    let (second, fraction) = parts[2].split_once('.').unwrap_or((parts[2], ""));
+    if second.len() != 2 || (parts[2].contains('.') && fraction.is_empty()) {
+        return Err(anyhow::anyhow!("Invalid time {}", lexical));
+    }
+    let second = second.parse::<u32>()?;
+    let nanos = fraction_nanos(fraction).ok_or_else(|| anyhow::anyhow!("Invalid time {}", lexical))? as u32;
+    if hour == 24 && minute == 0 && second == 0 && nanos == 0 {
+        return Ok((NaiveTime::MIN, true));
+    }
+    let time = NaiveTime::from_hms_nano_opt(hour, minute, second, nanos)
+        .ok_or_else(|| anyhow::anyhow!("Invalid time {}", lexical))?;
+    Ok((time, false))
+}
+
+//xsd:dateTime, values without a timezone are taken as UTC;
+//a space instead of the T is accepted as policies written for the naive parser use it
+pub fn parse_xsd_datetime(lexical: &str) -> Result<DateTime<FixedOffset>, anyhow::Error> {
+    let (local, offset) = split_timezone(lexical.trim())?;
+    let (date, time) = local.split_once(['T', ' '])
+        .ok_or_else(|| anyhow::anyhow!("Invalid date time {}", lexical))?;
+    let date = parse_date_part(date)?;
+    let (time, end_of_day) = parse_time_part(time)?;
+    let mut naive = NaiveDateTime::new(date, time);
+    if end_of_day {
+        naive = naive.checked_add_signed(Duration::days(1)).ok_or_else(|| anyhow::anyhow!("Invalid date time {}", lexical))?;
+    }
+    let offset = offset.unwrap_or(FixedOffset::east_opt(0).unwrap());
+    offset.from_local_datetime(&naive).single().ok_or_else(|| anyhow::anyhow!("Invalid date time {}", lexical))
+}
+
+pub fn parse_xsd_date(lexical: &str) -> Result<(NaiveDate, Option<FixedOffset>), anyhow::Error> {
+    let (local, offset) = split_timezone(lexical.trim())?;
+    Ok((parse_date_part(local)?, offset))
+}
+
+pub fn parse_xsd_time(lexical: &str) -> Result<(NaiveTime, Option<FixedOffset>), anyhow::Error> {
+    let (local, offset) = split_timezone(lexical.trim())?;
+    Ok((parse_time_part(local)?.0, offset))
+}
+
+//The instant a date starts at, in UTC when it has no timezone
+pub fn start_of_date(date: &NaiveDate, offset: &Option<FixedOffset>) -> DateTime<FixedOffset> {
+    let offset = offset.unwrap_or(FixedOffset::east_opt(0).unwrap());
+    offset.from_local_datetime(&date.and_time(NaiveTime::MIN)).unwrap()
+}
+
+//The date and time of day of an instant as seen in a timezone, UTC when it has none
+pub fn local_parts(instant: &DateTime<FixedOffset>, offset: &Option<FixedOffset>) -> (NaiveDate, NaiveTime) {
+    let offset = offset.unwrap_or(FixedOffset::east_opt(0).unwrap());
+    let local = instant.with_timezone(&offset);
+    (NaiveDate::from_ymd_opt(local.year(), local.month(), local.day()).unwrap(),
+     NaiveTime::from_hms_nano_opt(local.hour(), local.minute(), local.second(), local.nanosecond()).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use chrono::{FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc};
+    use crate::reference::temporal::{parse_xsd_date, parse_xsd_datetime, parse_xsd_time, XsdDuration};
+
+    #[test]
+    fn test_parse_xsd_datetime() {
+        let berlin = parse_xsd_datetime("2025-03-24T10:00:00+01:00").unwrap();
+        let utc = parse_xsd_datetime("2025-03-24T09:00:00Z").unwrap();
+        assert_eq!(berlin, utc);
+        assert_eq!(berlin.offset(), &FixedOffset::east_opt(3600).unwrap());
+
+        //naive values are UTC, 24:00:00 is the start of the next day
+        assert_eq!(parse_xsd_datetime("2025-03-24T09:00:00.250").unwrap().timestamp_millis(), utc.timestamp_millis() + 250);
+        assert_eq!(parse_xsd_datetime("2025-03-24T24:00:00").unwrap(), Utc.with_ymd_and_hms(2025, 3, 25, 0, 0, 0).unwrap());
+        assert!(parse_xsd_datetime("2025-03-24T25:00:00").is_err());
+        assert!(parse_xsd_datetime("2025-02-30T10:00:00").is_err());
+        assert!(parse_xsd_datetime("2025-03-24T10:00:00+15:00").is_err());
+        assert!(parse_xsd_datetime("tomorrow").is_err());
+
+        assert_eq!(parse_xsd_date("2025-03-24-05:00").unwrap(),
+                   (NaiveDate::from_ymd_opt(2025, 3, 24).unwrap(), FixedOffset::west_opt(5 * 3600)));
+        assert_eq!(parse_xsd_time("10:30:00").unwrap(), (NaiveTime::from_hms_opt(10, 30, 0).unwrap(), None));
+    }
+
+    #[test]
+    fn test_parse_xsd_duration() {
+        let duration = XsdDuration::parse("P1Y2M3DT4H5M6.5S").unwrap();
+        assert_eq!(duration.months, 14);
+        assert_eq!(duration.duration.num_milliseconds(), ((3 * 24 + 4) * 3600 + 5 * 60 + 6) * 1000 + 500);
+        assert_eq!(duration.to_string(), "P1Y2M3DT4H5M6.5S");
+        assert_eq!(XsdDuration::parse("-PT1H").unwrap().duration.num_minutes(), -60);
+
+        for invalid in ["P", "PT", "P1H", "PT1D", "P1M1Y", "P1.5D", "1D", "P-1D"] {
+            assert!(XsdDuration::parse(invalid).is_err(), "{}", invalid);
+        }
+
+        //a month is as long as the calendar month it is added to
+        let month = XsdDuration::parse("P1M").unwrap();
+        let january = Utc.with_ymd_and_hms(2025, 1, 31, 0, 0, 0).unwrap().fixed_offset();
+        assert_eq!(month.add_to(january).unwrap(), Utc.with_ymd_and_hms(2025, 2, 28, 0, 0, 0).unwrap());
+        assert_eq!(month.after(january).unwrap().num_days(), 28);
+        assert_eq!(month.before(january).unwrap().num_days(), 31);
+
+        //P1M and P30D only compare at an anchor
+        let days = XsdDuration::parse("P30D").unwrap();
+        assert_eq!(month.compare(&days), None);
+        assert_eq!(XsdDuration::parse("P1Y").unwrap().compare(&XsdDuration::parse("P364D").unwrap()), Some(Ordering::Greater));
+        let mut anchored = days.clone();
+        anchored.anchor = Some(january);
+        assert_eq!(anchored.compare(&month), Some(Ordering::Greater));
+    }
+}
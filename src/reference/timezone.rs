@@ -0,0 +1,86 @@
+// Copyright 2024 meduo <gao.brian@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(dead_code)]
+
+use anyhow::anyhow;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Offset};
+use chrono_tz::Tz;
+
+//A timezone to see instants in, a fixed offset or an IANA zone such as Europe/Berlin.
+//IANA zones come from the database compiled into chrono-tz, they do not depend on the host
+#[derive(Debug, Clone)]
+pub enum Zone {
+    Fixed(FixedOffset),
+    Named(Tz),
+}
+
+impl Zone {
+    //UTC, Z, ±hh:mm or an IANA zone name
+    pub fn parse(name: &str) -> Result<Self, anyhow::Error> {
+        let name = name.trim();
+        if name.eq_ignore_ascii_case("UTC") || name == "Z" {
+            return Ok(Zone::Fixed(FixedOffset::east_opt(0).unwrap()));
+        }
+        if name.starts_with(['+', '-']) {
+            let offset = name.parse::<FixedOffset>().map_err(|_| anyhow!("invalid timezone offset: {}", name))?;
+            return Ok(Zone::Fixed(offset));
+        }
+        let tz = name.parse::<Tz>().map_err(|_| anyhow!("unknown timezone: {}", name))?;
+        Ok(Zone::Named(tz))
+    }
+
+    pub fn utc() -> Self {
+        Zone::Fixed(FixedOffset::east_opt(0).unwrap())
+    }
+
+    pub fn offset_at(&self, instant: &DateTime<FixedOffset>) -> FixedOffset {
+        match self {
+            Zone::Fixed(offset) => *offset,
+            Zone::Named(tz) => instant.with_timezone(tz).offset().fix(),
+        }
+    }
+
+    //The wall clock time of the instant in this zone
+    pub fn local(&self, instant: &DateTime<FixedOffset>) -> NaiveDateTime {
+        instant.with_timezone(&self.offset_at(instant)).naive_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, TimeZone, Utc};
+    use crate::reference::timezone::Zone;
+
+    #[test]
+    fn test_zone() {
+        let instant = Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap().fixed_offset();
+        assert_eq!(Zone::parse("UTC").unwrap().local(&instant).to_string(), "2025-07-01 12:00:00");
+        assert_eq!(Zone::parse("-05:00").unwrap().offset_at(&instant), FixedOffset::west_opt(5 * 3600).unwrap());
+        assert!(Zone::parse("../etc/passwd").is_err());
+        assert!(Zone::parse("Mars/Olympus_Mons").is_err());
+
+        let berlin = Zone::parse("Europe/Berlin").unwrap();
+        assert_eq!(berlin.local(&instant).to_string(), "2025-07-01 14:00:00");
+        let winter = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap().fixed_offset();
+        assert_eq!(berlin.local(&winter).to_string(), "2025-01-01 13:00:00");
+
+        //summer time starts at 01:00 UTC on the last Sunday of March, also in years far ahead
+        assert_eq!(berlin.offset_at(&Utc.with_ymd_and_hms(2040, 3, 25, 0, 59, 59).unwrap().fixed_offset()).local_minus_utc(), 3600);
+        assert_eq!(berlin.offset_at(&Utc.with_ymd_and_hms(2040, 3, 25, 1, 0, 0).unwrap().fixed_offset()).local_minus_utc(), 7200);
+        let sydney = Zone::parse("Australia/Sydney").unwrap();
+        assert_eq!(sydney.offset_at(&Utc.with_ymd_and_hms(2040, 1, 15, 0, 0, 0).unwrap().fixed_offset()).local_minus_utc(), 11 * 3600);
+        assert_eq!(Zone::parse("Asia/Kolkata").unwrap().offset_at(&instant).local_minus_utc(), 5 * 3600 + 1800);
+    }
+}
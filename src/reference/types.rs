@@ -16,7 +16,14 @@
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
 
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime};
 use lombok::{Builder,Setter};
+use crate::model::data_type::DataType;
+use crate::model::error::OdrlError;
+use crate::reference::temporal::{local_parts, parse_xsd_date, parse_xsd_datetime, parse_xsd_time, start_of_date, XsdDuration};
+use crate::reference::units::GLOBAL_UNIT_REGISTRY;
 
 #[derive(Debug,Default, Clone)]
 pub enum OperandValueType {
@@ -29,7 +36,10 @@ pub enum OperandValueType {
 pub struct OperandValue {
     pub ty: OperandValueType,
     pub sets: Option<Vec<String>>,
-    pub sval: Option<String>
+    pub sval: Option<String>,
+    //value parsed once from its lexical form, sval keeps the lexical form
+    pub typed: Option<TypedValue>,
+    pub unit: Option<String>,
 }
 
 impl OperandValue {
@@ -40,4 +50,212 @@ impl OperandValue {
     pub fn get_set(&self) -> Option<Vec<String>> {
         self.sets.clone()
     }
-}
\ No newline at end of file
+
+    pub fn from_typed(value: TypedValue, unit: Option<&str>) -> Self {
+        OperandValue {
+            ty: OperandValueType::string,
+            sets: None,
+            sval: Some(value.to_string()),
+            typed: Some(value),
+            unit: unit.map(|unit| unit.to_string()),
+        }
+    }
+
+    //Typed values and quantities are compared by value instead of by their lexical form
+    pub fn is_typed(&self) -> bool {
+        self.typed.is_some() || self.unit.is_some()
+    }
+
+    //Move a unit written after the number, as in "10 EUR", to the unit of the value
+    pub fn with_quantity(mut self) -> Self {
+        if self.unit.is_some() || self.typed.is_some() {
+            return self;
+        }
+        if let Some((number, unit)) = self.sval.as_deref().and_then(|sval| GLOBAL_UNIT_REGISTRY.split_quantity(sval)) {
+            self.sval = Some(number.to_string());
+            self.typed = Some(TypedValue::Decimal(number));
+            self.unit = Some(unit);
+        }
+        self
+    }
+
+    //The typed value, parsed like the other operand when only that one is typed, or by data type
+    pub fn typed_value(&self, dty: &DataType, like: Option<&TypedValue>, de: &Option<String>) -> Result<TypedValue, anyhow::Error> {
+        if let Some(typed) = &self.typed {
+            return Ok(typed.clone());
+        }
+        let lexical = match (&self.sval, de) {
+            (Some(sval), _) => sval,
+            //try to use de
+            (None, Some(de)) => de,
+            (None, None) => return Err(anyhow::anyhow!("Operand is None")),
+        };
+        match like {
+            Some(like) => TypedValue::parse_like(like, lexical),
+            None => TypedValue::parse(dty, lexical),
+        }
+    }
+}
+
+//Value of an operand, parsed from its lexical form and data type
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedValue {
+    Integer(i64),
+    Decimal(f64),
+    Boolean(bool),
+    DateTime(DateTime<FixedOffset>),
+    //a calendar day or a time of day, with the timezone they were written in
+    Date(NaiveDate, Option<FixedOffset>),
+    Time(NaiveTime, Option<FixedOffset>),
+    Duration(XsdDuration),
+    Iri(String),
+    //lexical form and language tag
+    String(String, Option<String>),
+}
+
+impl TypedValue {
+    pub fn parse(dty: &DataType, lexical: &str) -> Result<Self, anyhow::Error> {
+        let lexical = lexical.trim();
+        match dty {
+            DataType::String => Ok(TypedValue::String(lexical.to_string(), None)),
+            //durations are declared as integers too
+            DataType::Integer => match lexical.parse::<i64>() {
+                Ok(value) => Ok(TypedValue::Integer(value)),
+                Err(_) => Ok(TypedValue::Duration(XsdDuration::parse(lexical)?)),
+            },
+            DataType::Float => Ok(TypedValue::Decimal(lexical.parse()?)),
+            DataType::Boolean => Ok(TypedValue::Boolean(lexical.parse()?)),
+            DataType::Date | DataType::DateTime | DataType::Time => parse_temporal(lexical),
+        }
+    }
+
+    //Parse a literal with an xsd datatype, given by iri or as xsd:name
+    pub fn parse_xsd(datatype: &str, lexical: &str) -> Result<Self, anyhow::Error> {
+        let name = datatype.rsplit(['#', ':']).next().unwrap_or(datatype);
+        let lexical = lexical.trim();
+        match name {
+            "integer" | "int" | "long" | "short" | "byte" | "nonNegativeInteger" | "positiveInteger"
+            | "negativeInteger" | "nonPositiveInteger" | "unsignedLong" | "unsignedInt" | "unsignedShort"
+            | "unsignedByte" => Ok(TypedValue::Integer(lexical.parse()?)),
+            "decimal" | "float" | "double" => Ok(TypedValue::Decimal(lexical.parse()?)),
+            "boolean" => Ok(TypedValue::Boolean(lexical.parse()?)),
+            "dateTime" | "dateTimeStamp" => Ok(TypedValue::DateTime(parse_xsd_datetime(lexical)?)),
+            "date" => parse_xsd_date(lexical).map(|(date, offset)| TypedValue::Date(date, offset)),
+            "time" => parse_xsd_time(lexical).map(|(time, offset)| TypedValue::Time(time, offset)),
+            "duration" | "dayTimeDuration" | "yearMonthDuration" => Ok(TypedValue::Duration(XsdDuration::parse(lexical)?)),
+            "anyURI" => Ok(TypedValue::Iri(lexical.to_string())),
+            "string" | "normalizedString" | "token" | "language" => Ok(TypedValue::String(lexical.to_string(), None)),
+            _ => Err(anyhow::anyhow!("Unsupported datatype {}", datatype)),
+        }
+    }
+
+    //Parse an untyped lexical form as the same kind of value as the typed operand
+    pub fn parse_like(like: &TypedValue, lexical: &str) -> Result<Self, anyhow::Error> {
+        let lexical = lexical.trim();
+        match like {
+            TypedValue::Integer(_) | TypedValue::Decimal(_) => match lexical.parse::<i64>() {
+                Ok(value) => Ok(TypedValue::Integer(value)),
+                Err(_) => Ok(TypedValue::Decimal(lexical.parse()?)),
+            },
+            TypedValue::Boolean(_) => Ok(TypedValue::Boolean(lexical.parse()?)),
+            TypedValue::DateTime(_) | TypedValue::Date(..) | TypedValue::Time(..) => parse_temporal(lexical),
+            //the world reports elapsed time in milliseconds
+            TypedValue::Duration(_) => match lexical.parse::<i64>() {
+                Ok(millis) => Ok(TypedValue::Duration(XsdDuration::from_millis(millis))),
+                Err(_) => Ok(TypedValue::Duration(XsdDuration::parse(lexical)?)),
+            },
+            TypedValue::Iri(_) => Ok(TypedValue::Iri(lexical.to_string())),
+            TypedValue::String(_, language) => Ok(TypedValue::String(lexical.to_string(), language.clone())),
+        }
+    }
+
+    //Order of two values, None when they can only be told apart by equality
+    pub fn compare(&self, other: &TypedValue) -> Result<Option<Ordering>, OdrlError> {
+        match (self, other) {
+            (TypedValue::Integer(left), TypedValue::Integer(right)) => Ok(Some(left.cmp(right))),
+            (TypedValue::Integer(_) | TypedValue::Decimal(_), TypedValue::Integer(_) | TypedValue::Decimal(_)) => {
+                Ok(self.as_f64().partial_cmp(&other.as_f64()))
+            }
+            (TypedValue::Boolean(left), TypedValue::Boolean(right)) => Ok(Some(left.cmp(right))),
+            (TypedValue::DateTime(left), TypedValue::DateTime(right)) => Ok(Some(left.cmp(right))),
+            //an instant against a day or a time of day compares as seen in the timezone of the latter
+            (TypedValue::DateTime(left), TypedValue::Date(right, offset)) => Ok(Some(local_parts(left, offset).0.cmp(right))),
+            (TypedValue::DateTime(left), TypedValue::Time(right, offset)) => Ok(Some(local_parts(left, offset).1.cmp(right))),
+            (TypedValue::Date(..) | TypedValue::Time(..), TypedValue::DateTime(_)) => {
+                Ok(other.compare(self)?.map(Ordering::reverse))
+            }
+            (TypedValue::Date(left, left_offset), TypedValue::Date(right, right_offset)) => {
+                Ok(Some(start_of_date(left, left_offset).cmp(&start_of_date(right, right_offset))))
+            }
+            (TypedValue::Time(left, left_offset), TypedValue::Time(right, right_offset)) => {
+                let day = NaiveDate::default();
+                Ok(Some(start_of_date(&day, left_offset).with_time(*left).single()
+                    .cmp(&start_of_date(&day, right_offset).with_time(*right).single())))
+            }
+            (TypedValue::Duration(left), TypedValue::Duration(right)) => Ok(left.compare(right)),
+            (TypedValue::String(left, left_language), TypedValue::String(right, right_language)) => {
+                if left_language == right_language {
+                    Ok(Some(left.cmp(right)))
+                } else {
+                    Ok(None)
+                }
+            }
+            (TypedValue::Iri(left), TypedValue::Iri(right) | TypedValue::String(right, None))
+            | (TypedValue::String(left, None), TypedValue::Iri(right)) => {
+                Ok((left == right).then_some(Ordering::Equal))
+            }
+            _ => Err(OdrlError::IncomparableValues(self.to_string(), other.to_string())),
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            TypedValue::Integer(value) => *value as f64,
+            TypedValue::Decimal(value) => *value,
+            _ => f64::NAN,
+        }
+    }
+}
+
+impl Display for TypedValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypedValue::Integer(value) => write!(f, "{}", value),
+            TypedValue::Decimal(value) => write!(f, "{}", value),
+            TypedValue::Boolean(value) => write!(f, "{}", value),
+            TypedValue::DateTime(value) => write!(f, "{}", value.to_rfc3339()),
+            TypedValue::Date(value, offset) => write!(f, "{}{}", value.format("%Y-%m-%d"), timezone(offset)),
+            TypedValue::Time(value, offset) => write!(f, "{}{}", value.format("%H:%M:%S%.f"), timezone(offset)),
+            TypedValue::Duration(value) => write!(f, "{}", value),
+            TypedValue::Iri(value) => write!(f, "{}", value),
+            TypedValue::String(value, _) => write!(f, "{}", value),
+        }
+    }
+}
+
+fn timezone(offset: &Option<FixedOffset>) -> String {
+    match offset {
+        Some(offset) if offset.local_minus_utc() == 0 => String::from("Z"),
+        Some(offset) => offset.to_string(),
+        None => String::new(),
+    }
+}
+
+//xsd date, time, dateTime and duration literals, and epoch milliseconds as the world reports them
+fn parse_temporal(lexical: &str) -> Result<TypedValue, anyhow::Error> {
+    if let Ok(millis) = lexical.parse::<i64>() {
+        return DateTime::from_timestamp_millis(millis).map(|datetime| TypedValue::DateTime(datetime.fixed_offset()))
+            .ok_or_else(|| anyhow::anyhow!("Invalid timestamp {}", lexical));
+    }
+    if lexical.starts_with('P') || lexical.starts_with("-P") {
+        return Ok(TypedValue::Duration(XsdDuration::parse(lexical)?));
+    }
+    if lexical.contains(['T', ' ']) {
+        return Ok(TypedValue::DateTime(parse_xsd_datetime(lexical)?));
+    }
+    //a time has a colon before any timezone, a date has none
+    match lexical.get(..3) {
+        Some(prefix) if prefix.ends_with(':') => parse_xsd_time(lexical).map(|(time, offset)| TypedValue::Time(time, offset)),
+        _ => parse_xsd_date(lexical).map(|(date, offset)| TypedValue::Date(date, offset)),
+    }
+}
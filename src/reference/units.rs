@@ -0,0 +1,155 @@
+// Copyright 2024 meduo <gao.brian@gmail.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(dead_code)]
+
+use std::sync::Arc;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use crate::model::error::OdrlError;
+
+const QUDT_UNIT: &str = "http://qudt.org/vocab/unit/";
+const QUDT_CURRENCY: &str = "http://qudt.org/vocab/currency/";
+const DBPEDIA_RESOURCE: &str = "http://dbpedia.org/resource/";
+
+//A unit is a factor to the base unit of its dimension, quantities only compare within a dimension
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    pub dimension: String,
+    pub factor: f64,
+}
+
+//Units by symbol or iri, currencies are a dimension each as no exchange rate is known
+pub struct UnitRegistry {
+    units: DashMap<String, Unit>,
+}
+
+impl UnitRegistry {
+    pub fn new() -> Self {
+        let registry = UnitRegistry {
+            units: DashMap::new(),
+        };
+
+        let information = [
+            (&["bit", "BIT"][..], 0.125),
+            (&["B", "byte", "BYTE"][..], 1.0),
+            (&["kB", "KB", "KiloBYTE"][..], 1e3),
+            (&["MB", "MegaBYTE"][..], 1e6),
+            (&["GB", "GigaBYTE"][..], 1e9),
+            (&["TB", "TeraBYTE"][..], 1e12),
+            (&["KiB", "KibiBYTE"][..], 1024.0),
+            (&["MiB", "MebiBYTE"][..], 1024.0 * 1024.0),
+            (&["GiB", "GibiBYTE"][..], 1024.0 * 1024.0 * 1024.0),
+            (&["TiB", "TebiBYTE"][..], 1024.0 * 1024.0 * 1024.0 * 1024.0),
+        ];
+        let time = [
+            (&["ms", "MilliSEC"][..], 1e-3),
+            (&["s", "SEC"][..], 1.0),
+            (&["min", "MIN"][..], 60.0),
+            (&["h", "HR"][..], 3600.0),
+            (&["d", "DAY"][..], 86400.0),
+        ];
+        let length = [
+            (&["mm", "MilliM"][..], 1e-3),
+            (&["cm", "CentiM"][..], 1e-2),
+            (&["m", "M"][..], 1.0),
+            (&["km", "KiloM"][..], 1e3),
+        ];
+        for (dimension, units) in [("information", &information[..]), ("time", &time[..]), ("length", &length[..])] {
+            for (names, factor) in units {
+                for name in names.iter() {
+                    registry.register(name, dimension, *factor);
+                }
+                //the last name is the QUDT local name
+                registry.register(&format!("{}{}", QUDT_UNIT, names[names.len() - 1]), dimension, *factor);
+            }
+        }
+
+        let currencies = [
+            ("EUR", &["€", "Euro"][..]),
+            ("USD", &["United_States_dollar"][..]),
+            ("GBP", &["£", "Pound_sterling"][..]),
+            ("CHF", &["Swiss_franc"][..]),
+            ("JPY", &["¥", "Japanese_yen"][..]),
+            ("CNY", &["Renminbi"][..]),
+            ("CAD", &["Canadian_dollar"][..]),
+            ("AUD", &["Australian_dollar"][..]),
+            ("SEK", &["Swedish_krona"][..]),
+            ("NOK", &["Norwegian_krone"][..]),
+            ("DKK", &["Danish_krone"][..]),
+            ("PLN", &[][..]),
+            ("INR", &["Indian_rupee"][..]),
+        ];
+        for (code, aliases) in currencies {
+            let dimension = format!("currency:{}", code);
+            registry.register(code, &dimension, 1.0);
+            registry.register(&format!("{}{}", QUDT_CURRENCY, code), &dimension, 1.0);
+            for alias in aliases {
+                let name = if alias.is_ascii() { format!("{}{}", DBPEDIA_RESOURCE, alias) } else { alias.to_string() };
+                registry.register(&name, &dimension, 1.0);
+            }
+        }
+        registry
+    }
+
+    pub fn register(&self, unit: &str, dimension: &str, factor: f64) {
+        self.units.insert(unit.to_string(), Unit { dimension: dimension.to_string(), factor });
+    }
+
+    pub fn get(&self, unit: &str) -> Option<Unit> {
+        self.units.get(unit.trim()).map(|unit| unit.value().clone())
+    }
+
+    //Value in the base unit of the dimension
+    pub fn to_base(&self, value: f64, unit: &str) -> Result<(f64, String), OdrlError> {
+        match self.get(unit) {
+            Some(known) => Ok((value * known.factor, known.dimension)),
+            None => Err(OdrlError::UnknownUnit(unit.to_string())),
+        }
+    }
+
+    //Bring both quantities to a common base, units of different dimensions do not compare
+    pub fn convert_pair(&self, left: (f64, &str), right: (f64, &str)) -> Result<(f64, f64), OdrlError> {
+        let (left_value, left_dimension) = self.to_base(left.0, left.1)?;
+        let (right_value, right_dimension) = self.to_base(right.0, right.1)?;
+        if left_dimension != right_dimension {
+            return Err(OdrlError::UnitMismatch(left.1.to_string(), right.1.to_string()));
+        }
+        Ok((left_value, right_value))
+    }
+
+    //Split "10 EUR" into its number and a known unit
+    pub fn split_quantity(&self, text: &str) -> Option<(f64, String)> {
+        let text = text.trim();
+        let (number, unit) = match text.split_once(char::is_whitespace) {
+            Some((number, unit)) => (number, unit.trim()),
+            None => {
+                let at = text.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))?;
+                (&text[..at], &text[at..])
+            }
+        };
+        let number = number.parse::<f64>().ok()?;
+        self.get(unit).map(|_| (number, unit.to_string()))
+    }
+}
+
+impl Default for UnitRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub static GLOBAL_UNIT_REGISTRY: Lazy<Arc<UnitRegistry>> = Lazy::new(|| {
+    Arc::new(UnitRegistry::new())
+});
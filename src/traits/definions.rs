@@ -14,13 +14,35 @@
 
 #![allow(dead_code)]
 
+use async_trait::async_trait;
+use crate::model::constraint::Verdict;
+use crate::model::constraint_left_operand::ConstraintLeftOperand;
+use crate::model::policy::OdrlRequest;
 use crate::model::stateworld::StateWorld;
+use crate::reference::types::OperandValue;
 
 pub trait LogicEval {
     fn eval(&self, world: &mut StateWorld) -> Result<bool, anyhow::Error>;
+
+    //Three valued outcome, an error which is not an OdrlError makes the constraint indeterminate
+    fn verdict(&self, world: &mut StateWorld) -> Result<Verdict, anyhow::Error> {
+        Verdict::from_result(self.eval(world), &None)
+    }
 }
 
 pub trait WorldCallBack: Send + Sync {
     fn on_success(&mut self, world: &mut StateWorld) -> Result<(), anyhow::Error>;
     fn on_failure(&mut self, world: &mut StateWorld) -> Result<(), anyhow::Error>;
-}
\ No newline at end of file
+}
+//Resolves a left operand from outside the world state, e.g. the caller environment
+pub trait LeftOperandProvider: Send + Sync {
+    //None falls back to the world state
+    fn provide(&self, operand: &ConstraintLeftOperand, world: &StateWorld) -> Result<Option<OperandValue>, anyhow::Error>;
+}
+
+//Resolves a left operand from a remote source, e.g. a geo-ip lookup or a usage database
+#[async_trait]
+pub trait AsyncLeftOperandProvider: Send + Sync {
+    //None falls back to the world state
+    async fn provide(&self, operand: &ConstraintLeftOperand, req: &OdrlRequest) -> Result<Option<OperandValue>, anyhow::Error>;
+}